@@ -1,5 +1,7 @@
 import argparse
 import ast
+import fractions
+import hashlib
 from typing import Literal, Union
 import tensorflow as tf
 import numpy as np
@@ -17,6 +19,18 @@ def handle_numpy_or_literal(inp: Union[np.ndarray, Literal[0]]):
     return np.array([inp])
   return inp
 
+# Encodes a MaskNegInf mask, using the compact causal form when the mask is lower triangular
+def get_mask_params(mask: np.ndarray):
+  if mask.ndim >= 2 and min(mask.shape[-2:]) > 1:
+    rows, cols = mask.shape[-2:]
+    for offset in range(-rows, cols):
+      causal = np.triu(np.ones((rows, cols), dtype=np.int64), k=offset + 1)
+      if np.all(mask == causal):
+        return [-1, offset]
+  params = [len(mask.shape)] + list(mask.shape)
+  params += mask.flatten().tolist()
+  return params
+
 def get_inputs(op: tflite.Operator):
   idxes = handle_numpy_or_literal(op.InputsAsNumpy())
   idxes = idxes.tolist()
@@ -26,7 +40,7 @@ def get_inputs(op: tflite.Operator):
 class Converter:
   def __init__(
       self, model_path, scale_factor, k, num_cols, num_randoms, use_selectors, commit,
-      expose_output
+      expose_output, metadata_digests=[], reveal_examples=[]
     ):
     self.model_path = model_path
     self.scale_factor = scale_factor
@@ -36,6 +50,8 @@ class Converter:
     self.use_selectors = use_selectors
     self.commit = commit
     self.expose_output = expose_output
+    self.metadata_digests = metadata_digests
+    self.reveal_examples = reveal_examples
 
     self.interpreter = tf.lite.Interpreter(
       model_path=self.model_path,
@@ -88,8 +104,7 @@ class Converter:
       if not np.all(np.logical_or(np.isneginf(const_tensor), const_tensor == 0)):
         raise RuntimeError('Add constant tensor must be -inf and 0 only')
       mask = (const_tensor == -np.inf).astype(np.int64)
-      params = [len(mask.shape)] + list(mask.shape)
-      params += mask.flatten().tolist()
+      params = get_mask_params(mask)
       return ('MaskNegInf', params)
     else:
       return ('Add', params)
@@ -150,7 +165,11 @@ class Converter:
           raise RuntimeError('AvgPool2D options is None')
         opt = tflite.Pool2DOptions()
         opt.Init(op_opt.Bytes, op_opt.Pos)
-        params = [opt.FilterHeight(), opt.FilterWidth(), opt.StrideH(), opt.StrideW()]
+        if opt.FusedActivationFunction() != tflite.ActivationFunctionType.NONE:
+          raise NotImplementedError('Fused activation is not supported')
+        # TFLite doesn't count the padding towards the divisor
+        params = [opt.FilterHeight(), opt.FilterWidth(), opt.StrideH(), opt.StrideW()] + \
+          [opt.Padding(), 0]
       elif op_code == tflite.BuiltinOperator.MAX_POOL_2D:
         layer_type = 'MaxPool2D'
         op_opt = op.BuiltinOptions()
@@ -180,16 +199,20 @@ class Converter:
           raise RuntimeError('Conv2D options is None')
         opt = tflite.Conv2DOptions()
         opt.Init(op_opt.Bytes, op_opt.Pos)
-        if opt.DilationHFactor() != 1 or opt.DilationWFactor() != 1:
-          raise NotImplementedError('Dilation is not supported')
         if opt.FusedActivationFunction() not in self.valid_activations():
           raise NotImplementedError('Unsupported activation function at layer {op_idx}')
+        # Grouped convolutions have fewer weight channels than input channels
+        inp_channels = get_shape(interpreter, op.Inputs(0))[3]
+        weight_channels = get_shape(interpreter, op.Inputs(1))[3]
+        groups = inp_channels // weight_channels
         # 0 is Conv2D
         params = \
           [0] + \
           [opt.Padding()] + \
           [opt.FusedActivationFunction()] + \
-          [opt.StrideH(), opt.StrideW()]
+          [opt.StrideH(), opt.StrideW()] + \
+          [opt.DilationHFactor(), opt.DilationWFactor()] + \
+          [groups]
       # DepthwiseConv2D
       elif op_code == tflite.BuiltinOperator.DEPTHWISE_CONV_2D:
         layer_type = 'Conv2D'
@@ -198,8 +221,6 @@ class Converter:
           raise RuntimeError('DepthwiseConv2D options is None')
         opt = tflite.DepthwiseConv2DOptions()
         opt.Init(op_opt.Bytes, op_opt.Pos)
-        if opt.DilationHFactor() != 1 or opt.DilationWFactor() != 1:
-          raise NotImplementedError('Dilation is not supported')
         if opt.FusedActivationFunction() not in self.valid_activations():
           raise NotImplementedError('Unsupported activation function at layer {op_idx}')
         # 1 is DepthwiseConv2D
@@ -207,7 +228,8 @@ class Converter:
           [1] + \
           [opt.Padding()] + \
           [opt.FusedActivationFunction()] + \
-          [opt.StrideH(), opt.StrideW()]
+          [opt.StrideH(), opt.StrideW()] + \
+          [opt.DilationHFactor(), opt.DilationWFactor()]
       # Fully connected
       elif op_code == tflite.BuiltinOperator.FULLY_CONNECTED:
         layer_type = 'FullyConnected'
@@ -218,7 +240,8 @@ class Converter:
         opt.Init(op_opt.Bytes, op_opt.Pos)
         if opt.FusedActivationFunction() not in self.valid_activations():
           raise NotImplementedError(f'Unsupported activation function at layer {op_idx}')
-        params = [opt.FusedActivationFunction()]
+        # TFLite weights are [out, in], so transpose_b is set
+        params = [opt.FusedActivationFunction(), 1]
       elif op_code == tflite.BuiltinOperator.BATCH_MATMUL:
         layer_type = 'BatchMatMul'
         op_opt = op.BuiltinOptions()
@@ -226,7 +249,6 @@ class Converter:
           raise RuntimeError('BatchMatMul options is None')
         opt = tflite.BatchMatMulOptions()
         opt.Init(op_opt.Bytes, op_opt.Pos)
-        if opt.AdjX() is True: raise NotImplementedError('AdjX is not supported')
         params = [int(opt.AdjX()), int(opt.AdjY())]
 
       ## Arithmetic
@@ -244,25 +266,68 @@ class Converter:
         if np.any(np.isin(sub_val, 10000)):
           layer_type = 'MaskNegInf'
           mask = (sub_val == 10000).astype(np.int64)
-          params = [len(mask.shape)] + list(mask.shape)
-          params += mask.flatten().tolist()
+          params = get_mask_params(mask)
         else:
           layer_type = 'Sub'
           params = []
+      elif op_code == tflite.BuiltinOperator.MINIMUM:
+        layer_type = 'Minimum'
+        params = []
+      elif op_code == tflite.BuiltinOperator.MAXIMUM:
+        layer_type = 'Maximum'
+        params = []
+      # Comparisons and logical ops output 0 or 1
+      elif op_code == tflite.BuiltinOperator.EQUAL:
+        layer_type = 'Equal'
+        params = []
+      elif op_code == tflite.BuiltinOperator.GREATER:
+        layer_type = 'Greater'
+        params = []
+      elif op_code == tflite.BuiltinOperator.LESS:
+        layer_type = 'Less'
+        params = []
+      elif op_code == tflite.BuiltinOperator.LOGICAL_AND:
+        layer_type = 'LogicalAnd'
+        params = []
+      elif op_code == tflite.BuiltinOperator.LOGICAL_OR:
+        layer_type = 'LogicalOr'
+        params = []
+      elif op_code == tflite.BuiltinOperator.LOGICAL_NOT:
+        layer_type = 'LogicalNot'
+        params = []
       # Div
       elif op_code == tflite.BuiltinOperator.DIV:
-        # Implement division as multiplication by the inverse
-        layer_type = 'Mul'
         div_val = interpreter.get_tensor(op.Inputs(1))
-        if type(div_val) != np.float32: raise NotImplementedError('Only support one divisor')
-        adjusted_tensors[op.Inputs(1)] = np.array([(self.scale_factor / div_val).round().astype(np.int64)])
-        params = []
+        num_val = interpreter.get_tensor(op.Inputs(0))
+        if type(num_val) == np.float32 and type(div_val) != np.float32:
+          # A constant divided by a tensor, e.g. 1 / norm
+          layer_type = 'Reciprocal'
+          params = [int(np.round(num_val * self.scale_factor))]
+        elif type(div_val) == np.float32:
+          # Implement division by a constant as multiplication by the inverse
+          layer_type = 'Mul'
+          adjusted_tensors[op.Inputs(1)] = np.array([(self.scale_factor / div_val).round().astype(np.int64)])
+          params = []
+        else:
+          layer_type = 'DivElementwise'
+          params = []
       # Pad
       elif op_code == tflite.BuiltinOperator.PAD:
         layer_type = 'Pad'
         tensor_idx = op.Inputs(1)
         tensor = interpreter.get_tensor(tensor_idx).flatten().astype(np.int64)
         params = tensor.tolist()
+      elif op_code == tflite.BuiltinOperator.MIRROR_PAD:
+        layer_type = 'Pad'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('MirrorPad options is None')
+        opt = tflite.MirrorPadOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        tensor = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64)
+        # The pad modes are 1 = reflect and 2 = symmetric
+        mode = 1 if opt.Mode() == tflite.MirrorPadMode.REFLECT else 2
+        params = tensor.tolist() + [mode]
       # Softmax
       elif op_code == tflite.BuiltinOperator.SOFTMAX:
         layer_type = 'Softmax'
@@ -272,17 +337,49 @@ class Converter:
           params = layers[-1]['params']
         elif layers[-2]['layer_type'] == 'MaskNegInf':
           params = layers[-2]['params']
-          params = [params[0] - 1] + params[2:]
+          # Explicit masks drop the leading axis removed in between
+          if params[0] >= 0:
+            params = [params[0] - 1] + params[2:]
         else:
           params = []
+      elif op_code == tflite.BuiltinOperator.ARG_MAX:
+        layer_type = 'ArgMax'
+        axis = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64)
+        if len(axis) != 1: raise NotImplementedError(f'Only a single axis is supported: {op_idx}')
+        params = axis.tolist()
       # Mean
       elif op_code == tflite.BuiltinOperator.MEAN:
         layer_type = 'Mean'
-        inp_shape = interpreter.get_tensor(op.Inputs(0)).shape
+        # Whether the axes are kept is taken from the output shape
         mean_idxes = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64)
-        if len(mean_idxes) + 2 != len(inp_shape):
-          raise NotImplementedError(f'Only mean over all but one axis is supported: {op_idx}')
         params = mean_idxes.tolist()
+      elif op_code == tflite.BuiltinOperator.SUM:
+        layer_type = 'ReduceSum'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('Sum options is None')
+        opt = tflite.ReducerOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        axes = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64)
+        params = [int(opt.KeepDims())] + axes.tolist()
+      elif op_code == tflite.BuiltinOperator.REDUCE_MAX:
+        layer_type = 'ReduceMax'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('Reduce max options is None')
+        opt = tflite.ReducerOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        axes = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64)
+        params = [int(opt.KeepDims())] + axes.tolist()
+      elif op_code == tflite.BuiltinOperator.CUMSUM:
+        layer_type = 'CumSum'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('Cumsum options is None')
+        opt = tflite.CumsumOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        axis = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64)[0]
+        params = [int(axis), int(opt.Exclusive()), int(opt.Reverse())]
       elif op_code == tflite.BuiltinOperator.SQUARE:
         layer_type = 'Square'
         params = []
@@ -290,24 +387,78 @@ class Converter:
       elif op_code == tflite.BuiltinOperator.SQUARED_DIFFERENCE:
         layer_type = 'SquaredDifference'
         params = []
+      # Select (condition, x, y)
+      elif op_code in [tflite.BuiltinOperator.SELECT, tflite.BuiltinOperator.SELECT_V2]:
+        layer_type = 'Select'
+        params = []
 
       # Pointwise
       elif op_code == tflite.BuiltinOperator.RSQRT:
         layer_type = 'Rsqrt'
         params = []
+      elif op_code == tflite.BuiltinOperator.SQRT:
+        layer_type = 'Sqrt'
+        params = []
       elif op_code == tflite.BuiltinOperator.LOGISTIC:
         layer_type = 'Logistic'
         params = []
+      elif op_code == tflite.BuiltinOperator.L2_NORMALIZATION:
+        layer_type = 'L2Norm'
+        params = []
+      elif op_code == tflite.BuiltinOperator.LOCAL_RESPONSE_NORMALIZATION:
+        layer_type = 'LRN'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('LRN options is None')
+        opt = tflite.LocalResponseNormalizationOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        params = [opt.Radius()]
+        for val in [opt.Bias(), opt.Alpha(), opt.Beta()]:
+          val = fractions.Fraction(float(val)).limit_denominator(1000000)
+          params += [val.numerator, val.denominator]
       elif op_code == tflite.BuiltinOperator.TANH:
         layer_type = 'Tanh'
         params = []
+      elif op_code == tflite.BuiltinOperator.GELU:
+        layer_type = 'Gelu'
+        op_opt = op.BuiltinOptions()
+        if op_opt is not None:
+          opt = tflite.GeluOptions()
+          opt.Init(op_opt.Bytes, op_opt.Pos)
+          if opt.Approximate():
+            raise NotImplementedError('Approximate GELU is not supported')
+        params = []
+      elif op_code == tflite.BuiltinOperator.ABS:
+        layer_type = 'Abs'
+        params = []
+      elif op_code == tflite.BuiltinOperator.EXP:
+        layer_type = 'Exp'
+        params = []
+      elif op_code == tflite.BuiltinOperator.LOG:
+        layer_type = 'Log'
+        params = []
+      elif op_code == tflite.BuiltinOperator.LOG_SOFTMAX:
+        layer_type = 'LogSoftmax'
+        params = []
+      elif op_code == tflite.BuiltinOperator.SIN:
+        layer_type = 'Sin'
+        params = []
+      elif op_code == tflite.BuiltinOperator.COS:
+        layer_type = 'Cos'
+        params = []
+      elif op_code == tflite.BuiltinOperator.RELU_N1_TO_1:
+        layer_type = 'Clip'
+        params = [-self.scale_factor, self.scale_factor]
+      elif op_code == tflite.BuiltinOperator.RELU_0_TO_1:
+        layer_type = 'Clip'
+        params = [0, self.scale_factor]
       elif op_code == tflite.BuiltinOperator.POW:
         layer_type = 'Pow'
         power = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.float32)
-        if power != 3.: raise NotImplementedError(f'Only support power 3')
-        power = power.round().astype(np.int64)
         if len(power) != 1: raise NotImplementedError(f'Only scalar power is supported: {op_idx}')
-        params = power.tolist()
+        # Integer powers are exact, fractional powers go through a lookup table
+        power = fractions.Fraction(float(power[0])).limit_denominator(1000)
+        params = [power.numerator, power.denominator]
 
       # The following are no-ops in the sense that they don't change the tensor
       # However, we need to pass along the right tensors
@@ -337,6 +488,42 @@ class Converter:
       elif op_code == tflite.BuiltinOperator.RESHAPE:
         layer_type = 'Reshape'
         params = []
+      elif op_code == tflite.BuiltinOperator.TILE:
+        layer_type = 'Tile'
+        params = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64).tolist()
+      elif op_code == tflite.BuiltinOperator.SPACE_TO_DEPTH:
+        layer_type = 'SpaceToDepth'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('SpaceToDepth options is None')
+        opt = tflite.SpaceToDepthOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        params = [opt.BlockSize()]
+      elif op_code == tflite.BuiltinOperator.SPACE_TO_BATCH_ND:
+        layer_type = 'SpaceToBatch'
+        block_shape = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64).tolist()
+        paddings = interpreter.get_tensor(op.Inputs(2)).flatten().astype(np.int64).tolist()
+        if len(block_shape) != 2:
+          raise NotImplementedError(f'SpaceToBatch only supports 2 spatial dimensions at layer {op_idx}')
+        params = block_shape + paddings
+      elif op_code == tflite.BuiltinOperator.BATCH_TO_SPACE_ND:
+        layer_type = 'BatchToSpace'
+        block_shape = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64).tolist()
+        crops = interpreter.get_tensor(op.Inputs(2)).flatten().astype(np.int64).tolist()
+        if len(block_shape) != 2:
+          raise NotImplementedError(f'BatchToSpace only supports 2 spatial dimensions at layer {op_idx}')
+        params = block_shape + crops
+      elif op_code == tflite.BuiltinOperator.DEPTH_TO_SPACE:
+        layer_type = 'DepthToSpace'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('DepthToSpace options is None')
+        opt = tflite.DepthToSpaceOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        params = [opt.BlockSize()]
+      elif op_code == tflite.BuiltinOperator.REVERSE_V2:
+        layer_type = 'Rotate'
+        params = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64).tolist()
       elif op_code == tflite.BuiltinOperator.TRANSPOSE:
         layer_type = 'Transpose'
         params = get_shape(interpreter, op.Inputs(0)) + interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64).tolist()
@@ -357,7 +544,14 @@ class Converter:
         opt = tflite.PackOptions()
         opt.Init(op_opt.Bytes, op_opt.Pos)
         params = [opt.Axis()]
-        if params[0] > 1: raise NotImplementedError(f'Only axis=0,1 supported at layer {op_idx}')
+      elif op_code == tflite.BuiltinOperator.UNPACK:
+        layer_type = 'Unpack'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('Unpack options is None')
+        opt = tflite.UnpackOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        params = [opt.Axis()]
       elif op_code == tflite.BuiltinOperator.SPLIT:
         layer_type = 'Split'
         op_opt = op.BuiltinOptions()
@@ -376,6 +570,15 @@ class Converter:
         begin = interpreter.get_tensor(op.Inputs(1)).flatten().astype(np.int64).tolist()
         size = interpreter.get_tensor(op.Inputs(2)).flatten().astype(np.int64).tolist()
         params = begin + size
+      elif op_code == tflite.BuiltinOperator.RESIZE_BILINEAR:
+        layer_type = 'ResizeBilinear'
+        op_opt = op.BuiltinOptions()
+        if op_opt is None:
+          raise RuntimeError('ResizeBilinear options is None')
+        opt = tflite.ResizeBilinearOptions()
+        opt.Init(op_opt.Bytes, op_opt.Pos)
+        # The interpolation weights are computed from the shapes when the circuit is built
+        params = [int(opt.AlignCorners()), int(opt.HalfPixelCenters())]
       elif op_code == tflite.BuiltinOperator.RESIZE_NEAREST_NEIGHBOR:
         layer_type = 'ResizeNearestNeighbor'
         op_opt = op.BuiltinOptions()
@@ -383,10 +586,6 @@ class Converter:
           raise RuntimeError('ResizeNearestNeighbor options is None')
         opt = tflite.ResizeNearestNeighborOptions()
         opt.Init(op_opt.Bytes, op_opt.Pos)
-        if opt.AlignCorners():
-          raise NotImplementedError(f'Align corners not supported at layer {op_idx}')
-        if not opt.HalfPixelCenters():
-          raise NotImplementedError(f'Half pixel centers not supported at layer {op_idx}')
         # Can take the out shape directly from the tensor
         params = [int(opt.AlignCorners()), int(opt.HalfPixelCenters())]
 
@@ -489,6 +688,8 @@ class Converter:
       'use_selectors': self.use_selectors,
       'commit_before': commit_before,
       'commit_after': commit_after,
+      'metadata_digests': self.metadata_digests,
+      'reveal_examples': self.reveal_examples,
     }
     print()
     print(d['layers'][-1])
@@ -513,6 +714,7 @@ def main():
   parser.add_argument('--scale_factor', type=int, default=2**16)
   parser.add_argument('--k', type=int, default=19)
   parser.add_argument('--eta', type=float, default=0.001)
+  # Set to 0 to let the loader pick the number of columns
   parser.add_argument('--num_cols', type=int, default=6)
   parser.add_argument('--use_selectors', action=argparse.BooleanOptionalAction, required=False, default=True)
   parser.add_argument('--commit', action=argparse.BooleanOptionalAction, required=False, default=False)
@@ -520,8 +722,17 @@ def main():
   parser.add_argument('--start_layer', type=int, default=0)
   parser.add_argument('--end_layer', type=int, default=10000)
   parser.add_argument('--num_randoms', type=int, default=20001)
+  # Files (e.g., model card, license) whose SHA-256 digests are exposed in the public instance
+  parser.add_argument('--metadata_files', type=str, nargs='*', default=[])
+  # In batch mode, one flag per example: 1 reveals the output, 0 only commits to it
+  parser.add_argument('--reveal_examples', type=int, nargs='*', default=[])
   args = parser.parse_args()
 
+  metadata_digests = []
+  for fname in args.metadata_files:
+    with open(fname, 'rb') as f:
+      metadata_digests.append(hashlib.sha256(f.read()).hexdigest())
+
   converter = Converter(
     args.model,
     args.scale_factor,
@@ -531,6 +742,8 @@ def main():
     args.use_selectors,
     args.commit,
     args.expose_output,
+    metadata_digests,
+    args.reveal_examples,
   )
 
   model_packed, config_packed = converter.to_msgpack(
@@ -0,0 +1,113 @@
+import argparse
+import msgpack
+
+# Combines two converted models (e.g., a reference model and a pruned / distilled model) into a
+# single circuit. Both models are run on the same input and an AssertClose layer checks that their
+# outputs agree within eps.
+
+def load_model(path):
+  with open(path, 'rb') as f:
+    return msgpack.unpackb(f.read())
+
+def max_tensor_idx(model):
+  idxes = [tensor['idx'] for tensor in model['tensors']]
+  for layer in model['layers']:
+    idxes += layer['inp_idxes'] + layer['out_idxes']
+  return max(idxes)
+
+def final_output(model):
+  return model['layers'][-1]['out_idxes'][0]
+
+def merge(model_a, model_b, eps, commit):
+  if model_a['global_sf'] != model_b['global_sf']:
+    raise ValueError('Models must use the same scale factor')
+  if len(model_a['inp_idxes']) != len(model_b['inp_idxes']):
+    raise ValueError('Models must have the same number of inputs')
+
+  # Model b reads model a's inputs, all other tensors of b are moved past a's indices
+  offset = max_tensor_idx(model_a) + 1
+  remap = {b: a for a, b in zip(model_a['inp_idxes'], model_b['inp_idxes'])}
+  def remap_idx(idx):
+    return remap[idx] if idx in remap else idx + offset
+
+  tensors = list(model_a['tensors'])
+  for tensor in model_b['tensors']:
+    if tensor['idx'] in remap:
+      continue
+    tensors.append({**tensor, 'idx': remap_idx(tensor['idx'])})
+
+  layers = list(model_a['layers'])
+  for layer in model_b['layers']:
+    layers.append({
+      **layer,
+      'inp_idxes': [remap_idx(idx) for idx in layer['inp_idxes']],
+      'out_idxes': [remap_idx(idx) for idx in layer['out_idxes']],
+    })
+
+  out_a = final_output(model_a)
+  out_b = remap_idx(final_output(model_b))
+  out_shape = model_a['layers'][-1]['out_shapes'][0]
+  if out_shape != model_b['layers'][-1]['out_shapes'][0]:
+    raise ValueError('Models must have the same output shape')
+
+  eps_idx = max_tensor_idx(model_b) + offset + 1
+  diff_idx = eps_idx + 1
+  tensors.append({
+    'idx': eps_idx,
+    'shape': [1],
+    'data': [int(round(eps * model_a['global_sf']))],
+  })
+  layers.append({
+    'layer_type': 'AssertClose',
+    'params': [],
+    'inp_idxes': [out_a, out_b, eps_idx],
+    'inp_shapes': [out_shape, out_shape, [1]],
+    'out_idxes': [diff_idx],
+    'out_shapes': [out_shape],
+    'mask': [],
+  })
+
+  inp_idxes = model_a['inp_idxes']
+  commit_before = []
+  if commit:
+    weight_tensors = [tensor['idx'] for tensor in tensors if tensor['idx'] not in inp_idxes]
+    commit_before = [weight_tensors, inp_idxes]
+
+  return {
+    'global_sf': model_a['global_sf'],
+    'k': max(model_a['k'], model_b['k']),
+    'num_cols': max(model_a['num_cols'], model_b['num_cols']),
+    'num_random': max(model_a.get('num_random', 20001), model_b.get('num_random', 20001)),
+    'inp_idxes': inp_idxes,
+    # Only the agreement is proven, the outputs stay private
+    'out_idxes': [],
+    'layers': layers,
+    'tensors': tensors,
+    'use_selectors': model_a.get('use_selectors', True) and model_b.get('use_selectors', True),
+    'commit_before': commit_before,
+    'commit_after': [],
+    'metadata_digests': model_a.get('metadata_digests', []) + model_b.get('metadata_digests', []),
+  }
+
+def main():
+  parser = argparse.ArgumentParser()
+  parser.add_argument('--model_a', type=str, required=True)
+  parser.add_argument('--model_b', type=str, required=True)
+  parser.add_argument('--model_output', type=str, required=True)
+  parser.add_argument('--config_output', type=str, required=True)
+  parser.add_argument('--eps', type=float, required=True)
+  parser.add_argument('--commit', action=argparse.BooleanOptionalAction, required=False, default=True)
+  args = parser.parse_args()
+
+  d = merge(load_model(args.model_a), load_model(args.model_b), args.eps, args.commit)
+  model_packed = msgpack.packb(d, use_bin_type=True)
+  d['tensors'] = []
+  config_packed = msgpack.packb(d, use_bin_type=True)
+
+  with open(args.model_output, 'wb') as f:
+    f.write(model_packed)
+  with open(args.config_output, 'wb') as f:
+    f.write(config_packed)
+
+if __name__ == '__main__':
+  main()
@@ -0,0 +1,53 @@
+use std::fs::File;
+
+use halo2_proofs::{
+  halo2curves::bn256::{Fr, G1Affine},
+  plonk::{keygen_vk, VerifyingKey},
+  SerdeFormat,
+};
+use zkml::{
+  model::ModelCircuit,
+  utils::proving_kzg::{get_kzg_params, serialize},
+};
+
+// Regenerates the verification key of a model and compares it against a golden key. Any change to
+// the circuit shape breaks already-deployed verifiers, so this should pass before every release.
+// Pass --update to overwrite the golden key instead.
+fn main() {
+  let config_fname = std::env::args().nth(1).expect("config file path");
+  let inp_fname = std::env::args().nth(2).expect("input file path");
+  let golden_fname = std::env::args().nth(3).expect("golden vkey file path");
+  let update = std::env::args().nth(4).map_or(false, |x| x == "--update");
+
+  let circuit = ModelCircuit::<Fr>::generate_from_file(&config_fname, &inp_fname);
+  let params = get_kzg_params("./params_kzg", circuit.k as u32);
+  let vk = keygen_vk(&params, &circuit).unwrap();
+  let vk_bytes = vk.to_bytes(SerdeFormat::RawBytes);
+
+  if update {
+    serialize(&vk_bytes, &golden_fname);
+    println!("Wrote golden vkey to {}", golden_fname);
+    return;
+  }
+
+  let golden_bytes = std::fs::read(&golden_fname).unwrap();
+  if golden_bytes == vk_bytes {
+    println!("vkey matches {}", golden_fname);
+    return;
+  }
+
+  let golden_vk = VerifyingKey::<G1Affine>::read::<File, ModelCircuit<Fr>>(
+    &mut File::open(&golden_fname).unwrap(),
+    SerdeFormat::RawBytes,
+    circuit.gadget_config.clone(),
+  );
+  match golden_vk {
+    Ok(golden_vk) => println!(
+      "vkey digest changed: golden {:?}, current {:?}",
+      golden_vk.transcript_repr(),
+      vk.transcript_repr()
+    ),
+    Err(_) => println!("golden vkey could not be read for this circuit's shape"),
+  }
+  panic!("vkey does not match {}", golden_fname);
+}
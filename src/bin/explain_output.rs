@@ -0,0 +1,38 @@
+use zkml::utils::{explain::occlusion, loader::load_model_msgpack};
+
+fn flag_value(flag: &str) -> Option<String> {
+  let args = std::env::args().collect::<Vec<_>>();
+  let pos = args.iter().position(|x| x == flag)?;
+  Some(args.get(pos + 1).expect("missing flag value").clone())
+}
+
+// Explains a single output of the model for the given input by occluding the inputs, e.g.
+//   explain_output model.msgpack inp.msgpack 3 --patch 4 --baseline 0
+// Each run of the model synthesizes the full witness, so larger patches are much faster.
+fn main() {
+  let config_fname = std::env::args().nth(1).expect("config file path");
+  let inp_fname = std::env::args().nth(2).expect("input file path");
+  let output = std::env::args()
+    .nth(3)
+    .expect("output index")
+    .parse::<usize>()
+    .unwrap();
+  let patch = flag_value("--patch").map_or(1, |x| x.parse::<usize>().unwrap());
+  let baseline = flag_value("--baseline").map_or(0., |x| x.parse::<f64>().unwrap());
+
+  let config = load_model_msgpack(&config_fname, &inp_fname);
+  let attribution = occlusion(&config, output, patch, baseline);
+  println!("output {}: {}", output, attribution.output_value);
+
+  let mut scores = attribution
+    .inputs
+    .iter()
+    .flat_map(|inp| inp.data.iter().enumerate().map(move |(i, x)| (&inp.name, i, *x)))
+    .collect::<Vec<_>>();
+  scores.sort_by(|a, b| b.2.abs().partial_cmp(&a.2.abs()).unwrap());
+  for (name, i, score) in scores.iter().take(10) {
+    println!("input {} element {}: {}", name, i, score);
+  }
+
+  attribution.write("attribution.json");
+}
@@ -0,0 +1,13 @@
+use zkml::utils::verifier_vectors::{generate_vectors, write_vectors};
+
+// Writes verifier test vectors for the model, for reimplementations of the verifier in other
+// languages. Check them with the verifier_vectors test.
+fn main() {
+  let config_fname = std::env::args().nth(1).expect("config file path");
+  let inp_fname = std::env::args().nth(2).expect("input file path");
+  let out_fname = std::env::args().nth(3).expect("output file path");
+
+  let vectors = generate_vectors(&config_fname, &inp_fname);
+  write_vectors(&out_fname, &vectors);
+  println!("Wrote {} vectors to {}", vectors.len(), out_fname);
+}
@@ -3,17 +3,23 @@ use zkml::{
   model::ModelCircuit,
   utils::{
     helpers::get_public_values,
-    loader::{load_model_msgpack, ModelMsgpack},
+    loader::{load_model_msgpack_mode, LoaderMode, ModelMsgpack},
   },
 };
 
 fn main() {
   let config_fname = std::env::args().nth(1).expect("config file path");
   let inp_fname = std::env::args().nth(2).expect("input file path");
+  // Reject unknown fields and ops instead of skipping them
+  let mode = if std::env::args().skip(3).any(|x| x == "--strict") {
+    LoaderMode::Strict
+  } else {
+    LoaderMode::Permissive
+  };
 
-  let config: ModelMsgpack = load_model_msgpack(&config_fname, &inp_fname);
+  let config: ModelMsgpack = load_model_msgpack_mode(&config_fname, &inp_fname, mode);
 
-  let circuit = ModelCircuit::<Fr>::generate_from_file(&config_fname, &inp_fname);
+  let circuit = ModelCircuit::<Fr>::generate_from_file_mode(&config_fname, &inp_fname, mode);
 
   let _prover = MockProver::run(config.k.try_into().unwrap(), &circuit, vec![vec![]]).unwrap();
   let public_vals = get_public_values();
@@ -1,23 +1,74 @@
 use halo2_proofs::halo2curves::{bn256::Fr, pasta::Fp};
 use zkml::{
-  model::ModelCircuit,
-  utils::{proving_ipa::time_circuit_ipa, proving_kzg::time_circuit_kzg},
+  model::{ModelCircuitBn256, ModelCircuitVesta},
+  utils::{
+    bundle::{load_or_create_signing_key, sign_bundle},
+    helpers::get_public_values,
+    instance_layout::InstanceLayout,
+    json_io::write_json_outputs,
+    key_cache::{cache_keys, find_cached_keys},
+    loader::{load_model_msgpack_mode, LoaderMode},
+    proving_ipa::time_circuit_ipa,
+    proving_kzg::time_circuit_kzg,
+  },
 };
 
+fn flag_value(flag: &str) -> Option<String> {
+  let args = std::env::args().collect::<Vec<_>>();
+  let pos = args.iter().position(|x| x == flag)?;
+  Some(args.get(pos + 1).expect("missing flag value").clone())
+}
+
 fn main() {
   let config_fname = std::env::args().nth(1).expect("config file path");
   let inp_fname = std::env::args().nth(2).expect("input file path");
   let kzg_or_ipa = std::env::args().nth(3).expect("kzg or ipa");
+  // Optionally write timing / memory stats to telemetry.json and the proof bundle
+  let telemetry = std::env::args().skip(4).any(|x| x == "--telemetry");
+  // Reject unknown fields and ops instead of skipping them
+  let mode = if std::env::args().skip(4).any(|x| x == "--strict") {
+    LoaderMode::Strict
+  } else {
+    LoaderMode::Permissive
+  };
+  // Optionally sign the proof with the ed25519 key at this path, valid for --ttl seconds
+  let signing_key = flag_value("--sign");
+  let ttl = flag_value("--ttl").map_or(3600, |x| x.parse::<u64>().unwrap());
+  // Reuse the keys of a structurally identical model (e.g., the same model before fine-tuning)
+  let key_cache = flag_value("--key-cache");
 
   if kzg_or_ipa != "kzg" && kzg_or_ipa != "ipa" {
     panic!("Must specify kzg or ipa");
   }
 
+  // Describes the public values so that verifiers can decode the outputs
+  let config = load_model_msgpack_mode(&config_fname, &inp_fname, mode);
+  let layout = InstanceLayout::from_model(&config);
+  layout.write("instance_layout.json");
+
   if kzg_or_ipa == "kzg" {
-    let circuit = ModelCircuit::<Fr>::generate_from_file(&config_fname, &inp_fname);
-    time_circuit_kzg(circuit);
+    let cached_keys = key_cache
+      .as_ref()
+      .and_then(|cache_dir| find_cached_keys(cache_dir, &config));
+    let cached_pkey = cached_keys.map(|entry_dir| format!("{}/pkey", entry_dir));
+    let circuit = ModelCircuitBn256::generate_from_msgpack(config.clone(), true);
+    let telemetry = time_circuit_kzg(circuit, telemetry, cached_pkey.as_deref());
+    if let (Some(cache_dir), None) = (&key_cache, &cached_pkey) {
+      cache_keys(cache_dir, &config, "vkey", "pkey");
+    }
+    write_json_outputs("outputs.json", &layout, &get_public_values::<Fr>());
+    if let Some(key_path) = signing_key {
+      let key = load_or_create_signing_key(&key_path);
+      let proof = std::fs::read("proof").unwrap();
+      let public_vals = std::fs::read("public_vals").unwrap();
+      sign_bundle(&key, &proof, &public_vals, ttl, telemetry).write("proof_bundle.json");
+    }
   } else {
-    let circuit = ModelCircuit::<Fp>::generate_from_file(&config_fname, &inp_fname);
-    time_circuit_ipa(circuit);
+    if signing_key.is_some() || key_cache.is_some() {
+      panic!("Signing and key caching are only supported for kzg");
+    }
+    let circuit = ModelCircuitVesta::generate_from_msgpack(config, true);
+    time_circuit_ipa(circuit, telemetry);
+    write_json_outputs("outputs.json", &layout, &get_public_values::<Fp>());
   }
 }
@@ -1,15 +1,32 @@
 use halo2_proofs::halo2curves::bn256::Fr;
 use zkml::{
   model::ModelCircuit,
-  utils::{loader::load_config_msgpack, proving_kzg::verify_circuit_kzg},
+  utils::{
+    bundle::{verify_bundle, ProofBundle},
+    instance_layout::{decode_outputs, InstanceLayout},
+    json_io::write_json_outputs,
+    loader::load_config_msgpack,
+    proving_kzg::verify_circuit_kzg,
+  },
 };
 
+fn flag_value(flag: &str) -> Option<String> {
+  let args = std::env::args().collect::<Vec<_>>();
+  let pos = args.iter().position(|x| x == flag)?;
+  Some(args.get(pos + 1).expect("missing flag value").clone())
+}
+
 fn main() {
   let config_fname = std::env::args().nth(1).expect("config file path");
   let vkey_fname = std::env::args().nth(2).expect("verification key file path");
   let proof_fname = std::env::args().nth(3).expect("proof file path");
   let public_vals_fname = std::env::args().nth(4).expect("public values file path");
   let kzg_or_ipa = std::env::args().nth(5).expect("kzg or ipa");
+  // Optionally decode the outputs using the instance layout written by the prover
+  let layout_fname = std::env::args().nth(6).filter(|x| !x.starts_with("--"));
+  // Optionally check a signed bundle from the prover, and that it was signed by --signer
+  let bundle_fname = flag_value("--bundle");
+  let signer = flag_value("--signer");
 
   if kzg_or_ipa != "kzg" && kzg_or_ipa != "ipa" {
     panic!("Must specify kzg or ipa");
@@ -19,7 +36,21 @@ fn main() {
     let config = load_config_msgpack(&config_fname);
     let circuit = ModelCircuit::<Fr>::generate_from_msgpack(config, false);
     println!("Loaded configuration");
-    verify_circuit_kzg(circuit, &vkey_fname, &proof_fname, &public_vals_fname);
+    let public_vals = verify_circuit_kzg(circuit, &vkey_fname, &proof_fname, &public_vals_fname);
+    if let Some(bundle_fname) = bundle_fname {
+      let bundle = ProofBundle::read(&bundle_fname);
+      let proof = std::fs::read(&proof_fname).unwrap();
+      let public_vals = std::fs::read(&public_vals_fname).unwrap();
+      verify_bundle(&bundle, &proof, &public_vals, signer.as_deref()).unwrap();
+      println!("Bundle signed by {} is valid", bundle.public_key);
+    }
+    if let Some(layout_fname) = layout_fname {
+      let layout = InstanceLayout::read(&layout_fname);
+      for (entry, outp) in decode_outputs(&layout, &public_vals) {
+        println!("output {:?} (example {:?}): {}", entry.tensor_idx, entry.example, outp);
+      }
+      write_json_outputs("outputs.json", &layout, &public_vals);
+    }
   } else {
     // Serialization of the verification key doesn't seem to be supported for IPA
     panic!("Not implemented");
@@ -214,7 +214,7 @@ impl<F: PrimeField> PackerChip<F> {
     &self,
     mut layouter: impl Layouter<F>,
     gadget_config: Rc<GadgetConfig>,
-    values: Vec<&F>,
+    values: Vec<&Value<F>>,
     zero: &AssignedCell<F, F>,
   ) -> Result<(Vec<CellRc<F>>, Vec<CellRc<F>>), Error> {
     let columns = &gadget_config.columns;
@@ -247,7 +247,7 @@ impl<F: PrimeField> PackerChip<F> {
             .enumerate()
             .map(|(i, x)| {
               let tmp = region
-                .assign_advice(|| "", columns[col_offset + i], 0, || Value::known(*x))
+                .assign_advice(|| "", columns[col_offset + i], 0, || *x)
                 .unwrap();
               Rc::new(tmp)
             })
@@ -259,25 +259,24 @@ impl<F: PrimeField> PackerChip<F> {
               zero
                 .copy_advice(|| "", &mut region, columns[col_offset + i], 0)
                 .unwrap();
-              F::ZERO
+              Value::known(F::ZERO)
             })
             .collect::<Vec<_>>();
           values.extend(zero_vals);
 
-          let res =
-            values
-              .iter()
-              .zip(self.config.exponents.iter())
-              .fold(F::ZERO, |acc, (inp, exp)| {
-                let res = acc + (*inp + shift_val) * (*exp);
-                res
-              });
+          let res = values.iter().zip(self.config.exponents.iter()).fold(
+            Value::known(F::ZERO),
+            |acc, (inp, exp)| {
+              let res = acc + (*inp + Value::known(shift_val)) * Value::known(*exp);
+              res
+            },
+          );
 
           let outp = region.assign_advice(
             || "",
             columns[col_offset + self.config.num_elem_per_packed],
             0,
-            || Value::known(res),
+            || res,
           )?;
           packed.push(Rc::new(outp));
         }
@@ -294,7 +293,7 @@ impl<F: PrimeField> PackerChip<F> {
     mut layouter: impl Layouter<F>,
     gadget_config: Rc<GadgetConfig>,
     constants: &HashMap<i64, CellRc<F>>,
-    tensors: &BTreeMap<i64, Array<F, IxDyn>>,
+    tensors: &BTreeMap<i64, Array<Value<F>, IxDyn>>,
   ) -> Result<(BTreeMap<i64, AssignedTensor<F>>, Vec<CellRc<F>>), Error> {
     let mut values = vec![];
     for (_, tensor) in tensors {
@@ -2,11 +2,18 @@ pub mod add_pairs;
 pub mod adder;
 pub mod bias_div_floor_relu6;
 pub mod bias_div_round_relu6;
+pub mod bit_decomposition;
 pub mod dot_prod;
 pub mod gadget;
+pub mod greater_than;
 pub mod input_lookup;
+pub mod mac;
 pub mod max;
 pub mod mul_pairs;
+pub mod mux;
+pub mod range_check;
+pub mod shift;
+pub mod sign_bit;
 pub mod sqrt_big;
 pub mod square;
 pub mod squared_diff;
@@ -15,6 +22,7 @@ pub mod update;
 pub mod var_div;
 pub mod var_div_big;
 pub mod var_div_big3;
+pub mod var_mod;
 
 // Generics
 pub mod nonlinear;
@@ -1,115 +1,131 @@
-use std::{collections::HashMap, marker::PhantomData};
+use std::{marker::PhantomData, rc::Rc};
 
 use halo2_proofs::{
-  circuit::{AssignedCell, Layouter, Region},
+  circuit::{AssignedCell, Layouter, Region, Value},
   halo2curves::ff::PrimeField,
   plonk::{ConstraintSystem, Error, Expression},
   poly::Rotation,
 };
 
-use crate::gadgets::gadget::convert_to_u64;
+use crate::gadgets::{bias_div_round_relu6::BiasDivRoundRelu6Chip, gadget::convert_to_u64};
 
 use super::gadget::{Gadget, GadgetConfig, GadgetType};
 
 type BiasDivFloorRelu6Config = GadgetConfig;
 
-const SHIFT_MIN_VAL: i64 = -(1 << 30);
+const NUM_COLS_PER_OP: usize = 5;
 
+// Computes floor(inp / sf) + bias and its activation, as in the TFLite reference kernels that
+// floor. The outputs are interleaved [(activated, div'd), ...] as in BiasDivRoundRelu6Chip.
 pub struct BiasDivFloorRelu6Chip<F: PrimeField> {
-  config: BiasDivFloorRelu6Config,
+  config: Rc<BiasDivFloorRelu6Config>,
+  gadget_type: GadgetType,
   _marker: PhantomData<F>,
 }
 
 impl<F: PrimeField> BiasDivFloorRelu6Chip<F> {
-  pub fn construct(config: BiasDivFloorRelu6Config) -> Self {
+  pub fn construct(config: Rc<BiasDivFloorRelu6Config>) -> Self {
     Self {
       config,
+      gadget_type: GadgetType::BiasDivFloorRelu6,
       _marker: PhantomData,
     }
   }
 
-  pub fn get_map(scale_factor: u64, num_rows: i64, div_outp_min_val: i64) -> HashMap<i64, i64> {
-    let div_val = scale_factor;
-    let div_outp_min_val = div_outp_min_val;
-
-    let mut map = HashMap::new();
-    for i in 0..num_rows {
-      let shifted = i + div_outp_min_val;
-      let val = shifted.clamp(0, 6 * div_val as i64);
-      map.insert(i as i64, val);
+  pub fn construct_relu(config: Rc<BiasDivFloorRelu6Config>) -> Self {
+    Self {
+      config,
+      gadget_type: GadgetType::BiasDivFloorRelu,
+      _marker: PhantomData,
     }
-    map
   }
 
-  pub fn num_cols_per_op() -> usize {
-    5
+  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    Self::configure_activation(meta, gadget_config, GadgetType::BiasDivFloorRelu6)
   }
 
-  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+  pub fn configure_relu(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+  ) -> GadgetConfig {
+    Self::configure_activation(meta, gadget_config, GadgetType::BiasDivFloorRelu)
+  }
+
+  fn configure_activation(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+    gadget_type: GadgetType,
+  ) -> GadgetConfig {
     let selector = meta.complex_selector();
     let sf = Expression::Constant(F::from(gadget_config.scale_factor));
+    let one = Expression::Constant(F::ONE);
     let columns = gadget_config.columns;
 
-    let mod_lookup = meta.lookup_table_column();
+    let mut tables = gadget_config.tables;
+    let div_lookup = tables.get(&GadgetType::InputLookup).unwrap()[0];
     let relu_lookup = meta.lookup_table_column();
-    let div_lookup = meta.lookup_table_column();
 
-    meta.create_gate("bias_mul", |meta| {
+    meta.create_gate("bias_mul_floor", |meta| {
       let s = meta.query_selector(selector);
 
       let mut constraints = vec![];
-      for op_idx in 0..columns.len() / Self::num_cols_per_op() {
-        let offset = op_idx * Self::num_cols_per_op();
+      for op_idx in 0..columns.len() / NUM_COLS_PER_OP {
+        let offset = op_idx * NUM_COLS_PER_OP;
         let inp = meta.query_advice(columns[offset + 0], Rotation::cur());
         let bias = meta.query_advice(columns[offset + 1], Rotation::cur());
         let div_res = meta.query_advice(columns[offset + 2], Rotation::cur());
         let mod_res = meta.query_advice(columns[offset + 3], Rotation::cur());
 
+        // inp = (div - bias) * sf + mod
         constraints.push(s.clone() * (inp - (sf.clone() * (div_res - bias) + mod_res)));
       }
 
       constraints
     });
 
-    for op_idx in 0..columns.len() / Self::num_cols_per_op() {
-      let offset = op_idx * Self::num_cols_per_op();
-      meta.lookup("bias_div_relu6 lookup", |meta| {
+    for op_idx in 0..columns.len() / NUM_COLS_PER_OP {
+      let offset = op_idx * NUM_COLS_PER_OP;
+      // mod \in [0, sf)
+      meta.lookup("bias_div_floor mod lookup", |meta| {
         let s = meta.query_selector(selector);
         let mod_res = meta.query_advice(columns[offset + 3], Rotation::cur());
 
-        // Constrains that the modulus \in [0, DIV_VAL)
-        vec![(s.clone() * mod_res.clone(), mod_lookup)]
+        vec![(s.clone() * mod_res, div_lookup)]
       });
-      meta.lookup("bias_div_relu6 lookup", |meta| {
+      meta.lookup("bias_div_floor mod upper lookup", |meta| {
+        let s = meta.query_selector(selector);
+        let mod_res = meta.query_advice(columns[offset + 3], Rotation::cur());
+
+        vec![(s.clone() * (sf.clone() - one.clone() - mod_res), div_lookup)]
+      });
+      meta.lookup("bias_div_floor relu lookup", |meta| {
         let s = meta.query_selector(selector);
         let div = meta.query_advice(columns[offset + 2], Rotation::cur());
         let outp = meta.query_advice(columns[offset + 4], Rotation::cur());
-        let div_outp_min_val = Expression::Constant(F::from((-SHIFT_MIN_VAL) as u64));
+        let div_outp_min_val = gadget_config.div_outp_min_val;
+        let div_outp_min_val = Expression::Constant(F::from((-div_outp_min_val) as u64));
 
-        // Constrains that output \in [0, 6 * SF]
+        // Constrains that output = act(div)
         vec![
+          (s.clone() * (div + div_outp_min_val), div_lookup),
           (s.clone() * outp, relu_lookup),
-          (s * (div + div_outp_min_val), div_lookup),
         ]
       });
     }
 
     let mut selectors = gadget_config.selectors;
-    selectors.insert(GadgetType::BiasDivFloorRelu6, vec![selector]);
+    selectors.insert(gadget_type, vec![selector]);
 
-    let mut tables = gadget_config.tables;
-    tables.insert(
-      GadgetType::BiasDivFloorRelu6,
-      vec![mod_lookup, relu_lookup, div_lookup],
-    );
+    tables.insert(gadget_type, vec![relu_lookup]);
 
     let mut maps = gadget_config.maps;
-    let relu_map = Self::get_map(
+    let relu_map = BiasDivRoundRelu6Chip::<F>::get_map(
       gadget_config.scale_factor,
+      gadget_config.min_val,
       gadget_config.num_rows as i64,
-      gadget_config.div_outp_min_val,
+      gadget_type,
     );
-    maps.insert(GadgetType::BiasDivFloorRelu6, vec![relu_map]);
+    maps.insert(gadget_type, vec![relu_map]);
 
     GadgetConfig {
       columns,
@@ -123,19 +139,51 @@ impl<F: PrimeField> BiasDivFloorRelu6Chip<F> {
 
 impl<F: PrimeField> Gadget<F> for BiasDivFloorRelu6Chip<F> {
   fn name(&self) -> String {
-    "BiasDivRelu6".to_string()
+    match self.gadget_type {
+      GadgetType::BiasDivFloorRelu => "BiasDivFloorRelu".to_string(),
+      _ => "BiasDivFloorRelu6".to_string(),
+    }
   }
 
   fn num_cols_per_op(&self) -> usize {
-    Self::num_cols_per_op()
+    NUM_COLS_PER_OP
   }
 
   fn num_inputs_per_row(&self) -> usize {
-    self.config.columns.len() / self.num_cols_per_op()
+    self.config.columns.len() / NUM_COLS_PER_OP
   }
 
   fn num_outputs_per_row(&self) -> usize {
-    self.num_inputs_per_row()
+    self.num_inputs_per_row() * 2
+  }
+
+  fn load_lookups(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+    let map = &self.config.maps[&self.gadget_type][0];
+
+    let relu_lookup = self.config.tables[&self.gadget_type][0];
+
+    layouter
+      .assign_table(
+        || "bdr floor div/relu lookup",
+        |mut table| {
+          for i in 0..self.config.num_rows {
+            let i = i as i64;
+            let val = map.get(&i).unwrap();
+            table
+              .assign_cell(
+                || "relu lookup",
+                relu_lookup,
+                i as usize,
+                || Value::known(F::from(*val as u64)),
+              )
+              .unwrap();
+          }
+          Ok(())
+        },
+      )
+      .unwrap();
+
+    Ok(())
   }
 
   fn op_row_region(
@@ -147,9 +195,9 @@ impl<F: PrimeField> Gadget<F> for BiasDivFloorRelu6Chip<F> {
   ) -> Result<Vec<AssignedCell<F, F>>, Error> {
     let div_val = self.config.scale_factor as i64;
 
-    let div_outp_min_val_i64 = -self.config.div_outp_min_val;
+    let div_outp_min_val_i64 = self.config.div_outp_min_val;
 
-    let div_inp_min_val_pos_i64 = -SHIFT_MIN_VAL;
+    let div_inp_min_val_pos_i64 = -self.config.shift_min_val;
     let div_inp_min_val_pos = F::from(div_inp_min_val_pos_i64 as u64);
 
     let inp = &vec_inputs[0];
@@ -157,24 +205,16 @@ impl<F: PrimeField> Gadget<F> for BiasDivFloorRelu6Chip<F> {
     assert_eq!(inp.len(), bias.len());
     assert_eq!(inp.len() % self.num_inputs_per_row(), 0);
 
-    let relu_map = &self
-      .config
-      .maps
-      .get(&GadgetType::BiasDivFloorRelu6)
-      .unwrap()[0];
+    let relu_map = &self.config.maps.get(&self.gadget_type).unwrap()[0];
 
     if self.config.use_selectors {
-      let selector = self
-        .config
-        .selectors
-        .get(&GadgetType::BiasDivFloorRelu6)
-        .unwrap()[0];
+      let selector = self.config.selectors.get(&self.gadget_type).unwrap()[0];
       selector.enable(region, row_offset)?;
     }
 
     let mut outp_cells = vec![];
     for (i, (inp, bias)) in inp.iter().zip(bias.iter()).enumerate() {
-      let offset = i * self.num_cols_per_op();
+      let offset = i * NUM_COLS_PER_OP;
 
       let inp_f = inp.value().map(|x: &F| x.to_owned());
       let bias_f = bias.value().map(|x: &F| {
@@ -184,11 +224,9 @@ impl<F: PrimeField> Gadget<F> for BiasDivFloorRelu6Chip<F> {
       });
       let div_mod_res = inp_f.map(|x: F| {
         let x_pos = x + div_inp_min_val_pos;
-        let inp = convert_to_u64(&x_pos);
-        // println!("inp: {:?}, bias: {:?}, x_pos: {:?}", inp, bias, x_pos);
-        let div_res = inp as i64 / div_val - (div_inp_min_val_pos_i64 / div_val);
-        let mod_res = inp as i64 % div_val;
-        // println!("div_res: {:?}, mod_res: {:?}", div_res, mod_res);
+        let inp = convert_to_u64(&x_pos) as i64;
+        let div_res = inp / div_val - div_inp_min_val_pos_i64 / div_val;
+        let mod_res = inp % div_val;
         (div_res, mod_res)
       });
       let div_res = div_mod_res.map(|x: (i64, i64)| x.0) + bias_f;
@@ -201,7 +239,6 @@ impl<F: PrimeField> Gadget<F> for BiasDivFloorRelu6Chip<F> {
           x_pos = 0;
         }
         let outp_val = relu_map.get(&(x_pos)).unwrap();
-        // println!("x: {}, x_pos: {}, outp_val: {}", x, x_pos, outp_val);
         F::from(*outp_val as u64)
       });
 
@@ -240,7 +277,6 @@ impl<F: PrimeField> Gadget<F> for BiasDivFloorRelu6Chip<F> {
         )
         .unwrap();
 
-      // outp_cells.push((outp_cell, div_res_cell));
       outp_cells.push(outp_cell);
       outp_cells.push(div_res_cell);
     }
@@ -256,6 +292,7 @@ impl<F: PrimeField> Gadget<F> for BiasDivFloorRelu6Chip<F> {
   ) -> Result<Vec<AssignedCell<F, F>>, Error> {
     let mut inps = vec_inputs[0].clone();
     let mut biases = vec_inputs[1].clone();
+    let initial_len = inps.len();
 
     // Needed to pad: bias - bias = 0
     let default = biases[0].clone();
@@ -265,10 +302,10 @@ impl<F: PrimeField> Gadget<F> for BiasDivFloorRelu6Chip<F> {
     }
 
     let res = self.op_aligned_rows(
-      layouter.namespace(|| "bias_div_relu6"),
+      layouter.namespace(|| "bias_div_floor_relu6"),
       &vec![inps, biases],
       single_inputs,
     )?;
-    Ok(res)
+    Ok(res[0..initial_len * 2].to_vec())
   }
 }
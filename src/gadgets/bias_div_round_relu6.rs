@@ -15,8 +15,11 @@ type BiasDivRoundRelu6Config = GadgetConfig;
 
 const NUM_COLS_PER_OP: usize = 5;
 
+// Computes round(inp / sf) + bias and its activation. BiasDivRoundRelu6 clamps to [0, 6 * sf] and
+// BiasDivRoundRelu only clamps below, so that each fused activation has its own lookup table.
 pub struct BiasDivRoundRelu6Chip<F: PrimeField> {
   config: Rc<BiasDivRoundRelu6Config>,
+  gadget_type: GadgetType,
   _marker: PhantomData<F>,
 }
 
@@ -24,23 +27,57 @@ impl<F: PrimeField> BiasDivRoundRelu6Chip<F> {
   pub fn construct(config: Rc<BiasDivRoundRelu6Config>) -> Self {
     Self {
       config,
+      gadget_type: GadgetType::BiasDivRoundRelu6,
       _marker: PhantomData,
     }
   }
 
-  pub fn get_map(scale_factor: u64, min_val: i64, num_rows: i64) -> HashMap<i64, i64> {
+  pub fn construct_relu(config: Rc<BiasDivRoundRelu6Config>) -> Self {
+    Self {
+      config,
+      gadget_type: GadgetType::BiasDivRoundRelu,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn get_map(
+    scale_factor: u64,
+    min_val: i64,
+    num_rows: i64,
+    gadget_type: GadgetType,
+  ) -> HashMap<i64, i64> {
     let div_val = scale_factor;
+    let max_val = match gadget_type {
+      GadgetType::BiasDivRoundRelu6 | GadgetType::BiasDivFloorRelu6 => 6 * div_val as i64,
+      GadgetType::BiasDivRoundRelu | GadgetType::BiasDivFloorRelu => i64::MAX,
+      _ => panic!("Unsupported bias div activation {:?}", gadget_type),
+    };
 
     let mut map = HashMap::new();
     for i in 0..num_rows {
       let shifted = i + min_val;
-      let val = shifted.clamp(0, 6 * div_val as i64);
+      let val = shifted.clamp(0, max_val);
       map.insert(i as i64, val);
     }
     map
   }
 
   pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    Self::configure_activation(meta, gadget_config, GadgetType::BiasDivRoundRelu6)
+  }
+
+  pub fn configure_relu(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+  ) -> GadgetConfig {
+    Self::configure_activation(meta, gadget_config, GadgetType::BiasDivRoundRelu)
+  }
+
+  fn configure_activation(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+    gadget_type: GadgetType,
+  ) -> GadgetConfig {
     let selector = meta.complex_selector();
     let sf = Expression::Constant(F::from(gadget_config.scale_factor));
     let two = Expression::Constant(F::from(2));
@@ -89,7 +126,7 @@ impl<F: PrimeField> BiasDivRoundRelu6Chip<F> {
         let div_outp_min_val = gadget_config.div_outp_min_val;
         let div_outp_min_val = Expression::Constant(F::from((-div_outp_min_val) as u64));
 
-        // Constrains that output \in [0, 6 * SF]
+        // Constrains that output = act(div), e.g., \in [0, 6 * SF] for ReLU6
         vec![
           (s.clone() * (div + div_outp_min_val), div_lookup),
           (s.clone() * outp, relu_lookup),
@@ -98,17 +135,18 @@ impl<F: PrimeField> BiasDivRoundRelu6Chip<F> {
     }
 
     let mut selectors = gadget_config.selectors;
-    selectors.insert(GadgetType::BiasDivRoundRelu6, vec![selector]);
+    selectors.insert(gadget_type, vec![selector]);
 
-    tables.insert(GadgetType::BiasDivRoundRelu6, vec![relu_lookup]);
+    tables.insert(gadget_type, vec![relu_lookup]);
 
     let mut maps = gadget_config.maps;
     let relu_map = Self::get_map(
       gadget_config.scale_factor,
       gadget_config.min_val,
       gadget_config.num_rows as i64,
+      gadget_type,
     );
-    maps.insert(GadgetType::BiasDivRoundRelu6, vec![relu_map]);
+    maps.insert(gadget_type, vec![relu_map]);
 
     GadgetConfig {
       columns,
@@ -122,7 +160,10 @@ impl<F: PrimeField> BiasDivRoundRelu6Chip<F> {
 
 impl<F: PrimeField> Gadget<F> for BiasDivRoundRelu6Chip<F> {
   fn name(&self) -> String {
-    "BiasDivRelu6".to_string()
+    match self.gadget_type {
+      GadgetType::BiasDivRoundRelu => "BiasDivRelu".to_string(),
+      _ => "BiasDivRelu6".to_string(),
+    }
   }
 
   fn num_cols_per_op(&self) -> usize {
@@ -138,9 +179,9 @@ impl<F: PrimeField> Gadget<F> for BiasDivRoundRelu6Chip<F> {
   }
 
   fn load_lookups(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
-    let map = &self.config.maps[&GadgetType::BiasDivRoundRelu6][0];
+    let map = &self.config.maps[&self.gadget_type][0];
 
-    let relu_lookup = self.config.tables[&GadgetType::BiasDivRoundRelu6][0];
+    let relu_lookup = self.config.tables[&self.gadget_type][0];
 
     layouter
       .assign_table(
@@ -185,18 +226,10 @@ impl<F: PrimeField> Gadget<F> for BiasDivRoundRelu6Chip<F> {
     assert_eq!(inp.len(), bias.len());
     assert_eq!(inp.len() % self.num_inputs_per_row(), 0);
 
-    let relu_map = &self
-      .config
-      .maps
-      .get(&GadgetType::BiasDivRoundRelu6)
-      .unwrap()[0];
+    let relu_map = &self.config.maps.get(&self.gadget_type).unwrap()[0];
 
     if self.config.use_selectors {
-      let selector = self
-        .config
-        .selectors
-        .get(&GadgetType::BiasDivRoundRelu6)
-        .unwrap()[0];
+      let selector = self.config.selectors.get(&self.gadget_type).unwrap()[0];
       selector.enable(region, row_offset).unwrap();
     }
 
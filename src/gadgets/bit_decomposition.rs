@@ -0,0 +1,251 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region},
+  halo2curves::ff::PrimeField,
+  plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+  poly::Rotation,
+};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+use super::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  range_check::RangeCheckChip,
+};
+
+type BitDecompositionConfig = GadgetConfig;
+
+// Decomposes x into little-endian bits, or into limbs of RangeCheckChip::limb_bits bits, with a
+// running sum over consecutive rows: [acc, limb_0, ..., limb_{n - 1}] where
+// acc = sum_i limb_i * 2^(i * limb_bits) + 2^(n * limb_bits) * acc_next. The accumulator of the
+// row after the last one is zero, so x < 2^(num_limbs * limb_bits). Bits are constrained to be
+// boolean and limbs are looked up in the input table. The outputs are the limbs of every input.
+pub struct BitDecompositionChip<F: PrimeField> {
+  config: Rc<BitDecompositionConfig>,
+  num_limbs: usize,
+  bits: bool,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> BitDecompositionChip<F> {
+  pub fn construct(config: Rc<BitDecompositionConfig>, num_bits: usize) -> Self {
+    Self {
+      config,
+      num_limbs: num_bits,
+      bits: true,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn construct_limbs(config: Rc<BitDecompositionConfig>, num_limbs: usize) -> Self {
+    Self {
+      config,
+      num_limbs,
+      bits: false,
+      _marker: PhantomData,
+    }
+  }
+
+  fn running_sum_gate(
+    meta: &mut ConstraintSystem<F>,
+    columns: &Vec<Column<Advice>>,
+    selector: Selector,
+    limb_bits: usize,
+  ) {
+    meta.create_gate("bit decomposition running sum", |meta| {
+      let s = meta.query_selector(selector);
+      let acc = meta.query_advice(columns[0], Rotation::cur());
+      let acc_next = meta.query_advice(columns[0], Rotation::next());
+
+      let two = F::from(2);
+      let mut recomposed = Expression::Constant(F::ZERO);
+      for i in 1..columns.len() {
+        let limb = meta.query_advice(columns[i], Rotation::cur());
+        let base = two.pow_vartime([((i - 1) * limb_bits) as u64]);
+        recomposed = recomposed + limb * Expression::Constant(base);
+      }
+      let base = two.pow_vartime([((columns.len() - 1) * limb_bits) as u64]);
+      recomposed = recomposed + acc_next * Expression::Constant(base);
+
+      vec![s * (acc - recomposed)]
+    });
+  }
+
+  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    let bit_selector = meta.selector();
+    let limb_selector = meta.complex_selector();
+    let columns = gadget_config.columns;
+    let tables = gadget_config.tables;
+    let inp_lookup = tables.get(&GadgetType::InputLookup).unwrap()[0];
+    let limb_bits = RangeCheckChip::<F>::limb_bits(gadget_config.num_rows);
+
+    Self::running_sum_gate(meta, &columns, bit_selector, 1);
+    Self::running_sum_gate(meta, &columns, limb_selector, limb_bits);
+
+    meta.create_gate("bit decomposition boolean", |meta| {
+      let s = meta.query_selector(bit_selector);
+      let mut constraints = vec![];
+      for i in 1..columns.len() {
+        let bit = meta.query_advice(columns[i], Rotation::cur());
+        let one = Expression::Constant(F::ONE);
+        constraints.push(s.clone() * bit.clone() * (one - bit));
+      }
+      constraints
+    });
+
+    // The table is [0, num_rows), so limb + (num_rows - 2^limb_bits) is also looked up
+    let offset = (gadget_config.num_rows - (1 << limb_bits)) as u64;
+    for i in 1..columns.len() {
+      meta.lookup("bit decomposition limb", |meta| {
+        let s = meta.query_selector(limb_selector);
+        let limb = meta.query_advice(columns[i], Rotation::cur());
+        vec![(s * limb, inp_lookup)]
+      });
+      meta.lookup("bit decomposition limb offset", |meta| {
+        let s = meta.query_selector(limb_selector);
+        let limb = meta.query_advice(columns[i], Rotation::cur());
+        vec![(
+          s * (limb + Expression::Constant(F::from(offset))),
+          inp_lookup,
+        )]
+      });
+    }
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(
+      GadgetType::BitDecomposition,
+      vec![bit_selector, limb_selector],
+    );
+
+    GadgetConfig {
+      columns,
+      selectors,
+      tables,
+      ..gadget_config
+    }
+  }
+
+  fn limb_bits(&self) -> usize {
+    if self.bits {
+      1
+    } else {
+      RangeCheckChip::<F>::limb_bits(self.config.num_rows)
+    }
+  }
+
+  // The number of rows of one decomposition, including the final zero accumulator
+  fn num_rows_per_op(&self) -> usize {
+    let limbs_per_row = self.config.columns.len() - 1;
+    (self.num_limbs + limbs_per_row - 1) / limbs_per_row + 1
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for BitDecompositionChip<F> {
+  fn name(&self) -> String {
+    "bit decomposition".to_string()
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    self.config.columns.len()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    1
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    self.num_limbs
+  }
+
+  // Decomposes a single input over the rows starting at row_offset
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let columns = &self.config.columns;
+    let zero = single_inputs[0];
+    let limb_bits = self.limb_bits();
+    let limbs_per_row = columns.len() - 1;
+    let num_rows = self.num_rows_per_op() - 1;
+
+    let selector = self
+      .config
+      .selectors
+      .get(&GadgetType::BitDecomposition)
+      .unwrap()[if self.bits { 0 } else { 1 }];
+
+    let x = vec_inputs[0][0];
+    let limb_val = |idx: usize| {
+      x.value().map(|x: &F| {
+        let x = BigUint::from_bytes_le(x.to_repr().as_ref());
+        let limb = (x >> (idx * limb_bits)) % (BigUint::from(1u64) << limb_bits);
+        F::from(limb.to_u64().unwrap())
+      })
+    };
+    let acc_val = |idx: usize| {
+      x.value().map(|x: &F| {
+        let x = BigUint::from_bytes_le(x.to_repr().as_ref());
+        let acc = x >> (idx * limb_bits);
+        F::from_str_vartime(&acc.to_str_radix(10)).unwrap()
+      })
+    };
+
+    let mut limbs = vec![];
+    for row in 0..num_rows {
+      if self.config.use_selectors {
+        selector.enable(region, row_offset + row)?;
+      }
+      if row == 0 {
+        x.copy_advice(|| "", region, columns[0], row_offset)?;
+      } else {
+        let acc = acc_val(row * limbs_per_row);
+        region.assign_advice(|| "", columns[0], row_offset + row, || acc)?;
+      }
+
+      for i in 0..limbs_per_row {
+        let idx = row * limbs_per_row + i;
+        let limb =
+          region.assign_advice(|| "", columns[1 + i], row_offset + row, || limb_val(idx))?;
+        // The limbs past the requested ones must be zero
+        if idx < self.num_limbs {
+          limbs.push(limb);
+        } else {
+          region.constrain_equal(limb.cell(), zero.cell())?;
+        }
+      }
+    }
+    zero.copy_advice(|| "", region, columns[0], row_offset + num_rows)?;
+
+    Ok(limbs)
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    assert!(self.num_limbs * self.limb_bits() < F::NUM_BITS as usize - 1);
+
+    layouter.assign_region(
+      || format!("gadget {}", self.name()),
+      |mut region| {
+        let mut outputs = vec![];
+        for (i, x) in vec_inputs[0].iter().enumerate() {
+          let limbs = self.op_row_region(
+            &mut region,
+            i * self.num_rows_per_op(),
+            &vec![vec![*x]],
+            single_inputs,
+          )?;
+          outputs.extend(limbs);
+        }
+        Ok(outputs)
+      },
+    )
+  }
+}
@@ -11,19 +11,38 @@ use halo2_proofs::{
 use num_bigint::{BigUint, ToBigUint};
 use num_traits::cast::ToPrimitive;
 
+use super::nonlinear::custom::CustomFunctions;
+
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub enum GadgetType {
   AddPairs,
   Adder,
   BiasDivRoundRelu6,
+  BiasDivRoundRelu,
   BiasDivFloorRelu6,
+  BiasDivFloorRelu,
+  BitDecomposition,
+  Cos,
+  Custom(&'static str),
   DotProduct,
+  Erf,
   Exp,
+  Gelu,
+  GreaterThan,
+  Ln,
   Logistic,
+  Mac,
   Max,
+  PiecewiseLinear(&'static str),
   Pow,
+  RangeCheck,
   Relu,
   Rsqrt,
+  Shift,
+  Sign,
+  SignBit,
+  Sin,
+  Softplus,
   Sqrt,
   SqrtBig,
   Square,
@@ -31,14 +50,21 @@ pub enum GadgetType {
   SubPairs,
   Tanh,
   MulPairs,
+  Mux,
   VarDivRound,
   VarDivRoundBig,
   VarDivRoundBig3,
+  VarMod,
   Packer,      // This is a special case
   InputLookup, // Dummy placeholder for the input lookup
   Update,
 }
 
+// Rows at the bottom of the circuit that halo2 reserves: blinding_factors(), which is
+// max(3, queries per advice column) + 2, plus the row after them. No gadget queries more than 3
+// rotations of a column, so this is 3 + 2 + 1. Lookup tables must fit in the remaining rows.
+pub const DEFAULT_RESERVED_ROWS: i64 = 6;
+
 #[derive(Clone, Debug, Default)]
 pub struct GadgetConfig {
   pub used_gadgets: Arc<BTreeSet<GadgetType>>,
@@ -50,16 +76,21 @@ pub struct GadgetConfig {
   pub scale_factor: u64,
   pub shift_min_val: i64, // MUST be divisible by 2 * scale_factor
   pub num_rows: usize,
+  pub reserved_rows: usize,
   pub num_cols: usize,
   pub k: usize,
   pub eta: f64,
+  pub pow_exponent: f64,
   pub min_val: i64,
   pub max_val: i64,
   pub div_outp_min_val: i64,
   pub use_selectors: bool,
   pub commit_before: Vec<Vec<i64>>,
   pub commit_after: Vec<Vec<i64>>,
+  pub reveal_examples: Vec<i64>,
   pub num_bits_per_elem: i64,
+  pub nonlinear_input_bits: i64,
+  pub custom_functions: CustomFunctions,
 }
 
 // TODO: refactor
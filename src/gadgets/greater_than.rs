@@ -0,0 +1,168 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region},
+  halo2curves::ff::PrimeField,
+  plonk::{ConstraintSystem, Error, Expression},
+  poly::Rotation,
+};
+
+use crate::gadgets::gadget::convert_to_u64;
+
+use super::gadget::{Gadget, GadgetConfig, GadgetType};
+
+type GreaterThanConfig = GadgetConfig;
+
+// c = 1 if a > b else 0. c is boolean, and a - b - 1 (if c = 1) or b - a (if c = 0) is range
+// checked, so |a - b| must be less than the number of rows.
+pub struct GreaterThanChip<F: PrimeField> {
+  config: Rc<GreaterThanConfig>,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> GreaterThanChip<F> {
+  pub fn construct(config: Rc<GreaterThanConfig>) -> Self {
+    Self {
+      config,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn num_cols_per_op() -> usize {
+    3
+  }
+
+  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    let selector = meta.complex_selector();
+    let columns = gadget_config.columns;
+    let tables = gadget_config.tables;
+
+    let inp_lookup = tables.get(&GadgetType::InputLookup).unwrap()[0];
+
+    meta.create_gate("greater than boolean", |meta| {
+      let s = meta.query_selector(selector);
+      let mut constraints = vec![];
+      for i in 0..columns.len() / Self::num_cols_per_op() {
+        let offset = i * Self::num_cols_per_op();
+        let outp = meta.query_advice(columns[offset + 2], Rotation::cur());
+        let one = Expression::Constant(F::ONE);
+
+        constraints.push(s.clone() * outp.clone() * (one - outp))
+      }
+      constraints
+    });
+
+    for idx in 0..columns.len() / Self::num_cols_per_op() {
+      meta.lookup("greater than diff", |meta| {
+        let s = meta.query_selector(selector);
+        let offset = idx * Self::num_cols_per_op();
+        let inp1 = meta.query_advice(columns[offset + 0], Rotation::cur());
+        let inp2 = meta.query_advice(columns[offset + 1], Rotation::cur());
+        let outp = meta.query_advice(columns[offset + 2], Rotation::cur());
+        let one = Expression::Constant(F::ONE);
+
+        let gt_diff = inp1.clone() - inp2.clone() - one.clone();
+        let le_diff = inp2 - inp1;
+        vec![(
+          s * (outp.clone() * gt_diff + (one - outp) * le_diff),
+          inp_lookup,
+        )]
+      });
+    }
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(GadgetType::GreaterThan, vec![selector]);
+
+    GadgetConfig {
+      columns,
+      selectors,
+      tables,
+      ..gadget_config
+    }
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for GreaterThanChip<F> {
+  fn name(&self) -> String {
+    "greater than chip".to_string()
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    Self::num_cols_per_op()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    _single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let inp1 = &vec_inputs[0];
+    let inp2 = &vec_inputs[1];
+    assert_eq!(inp1.len(), inp2.len());
+
+    let columns = &self.config.columns;
+
+    if self.config.use_selectors {
+      let selector = self.config.selectors.get(&GadgetType::GreaterThan).unwrap()[0];
+      selector.enable(region, row_offset)?;
+    }
+
+    let min_val_pos = F::from((-self.config.shift_min_val) as u64);
+
+    let mut outps = vec![];
+    for i in 0..inp1.len() {
+      let offset = i * self.num_cols_per_op();
+      let inp1 = inp1[i].copy_advice(|| "", region, columns[offset + 0], row_offset)?;
+      let inp2 = inp2[i].copy_advice(|| "", region, columns[offset + 1], row_offset)?;
+      let outp = inp1.value().zip(inp2.value()).map(|(a, b)| {
+        let a = convert_to_u64(&(*a + min_val_pos));
+        let b = convert_to_u64(&(*b + min_val_pos));
+        if a > b {
+          F::ONE
+        } else {
+          F::ZERO
+        }
+      });
+
+      let outp = region.assign_advice(|| "", columns[offset + 2], row_offset, || outp)?;
+      outps.push(outp);
+    }
+    Ok(outps)
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let zero = &single_inputs[0];
+
+    let mut inp1 = vec_inputs[0].clone();
+    let mut inp2 = vec_inputs[1].clone();
+    let initial_len = inp1.len();
+    while inp1.len() % self.num_inputs_per_row() != 0 {
+      inp1.push(zero);
+      inp2.push(zero);
+    }
+
+    let vec_inputs = vec![inp1, inp2];
+
+    let res = self.op_aligned_rows(
+      layouter.namespace(|| format!("forward row {}", self.name())),
+      &vec_inputs,
+      single_inputs,
+    )?;
+    Ok(res[0..initial_len].to_vec())
+  }
+}
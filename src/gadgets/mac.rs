@@ -0,0 +1,229 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region},
+  halo2curves::ff::PrimeField,
+  plonk::{Advice, Column, ConstraintSystem, Error, Expression},
+  poly::Rotation,
+};
+
+use crate::gadgets::dot_prod::DotProductChip;
+
+use super::gadget::{Gadget, GadgetConfig, GadgetType};
+
+type MacConfig = GadgetConfig;
+
+// Multiply-accumulate: each row is [a..., b..., acc] with acc = acc_prev + sum(a * b), where
+// acc_prev is the accumulator of the row above. The first row of the region only holds the
+// initial accumulator (zero). Unlike DotProductChip, the partial sums are never summed again by
+// the adder, which saves the adder rows.
+pub struct MacChip<F: PrimeField> {
+  config: Rc<MacConfig>,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> MacChip<F> {
+  pub fn construct(config: Rc<MacConfig>) -> Self {
+    Self {
+      config,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn get_input_columns(config: &GadgetConfig) -> Vec<Column<Advice>> {
+    DotProductChip::<F>::get_input_columns(config)
+  }
+
+  pub fn get_weight_columns(config: &GadgetConfig) -> Vec<Column<Advice>> {
+    DotProductChip::<F>::get_weight_columns(config)
+  }
+
+  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    let selector = meta.selector();
+    let columns = &gadget_config.columns;
+
+    meta.create_gate("mac gate", |meta| {
+      let s = meta.query_selector(selector);
+      let gate_inp = MacChip::<F>::get_input_columns(&gadget_config)
+        .iter()
+        .map(|col| meta.query_advice(*col, Rotation::cur()))
+        .collect::<Vec<_>>();
+      let gate_weights = MacChip::<F>::get_weight_columns(&gadget_config)
+        .iter()
+        .map(|col| meta.query_advice(*col, Rotation::cur()))
+        .collect::<Vec<_>>();
+      let acc_prev = meta.query_advice(columns[columns.len() - 1], Rotation::prev());
+      let acc = meta.query_advice(columns[columns.len() - 1], Rotation::cur());
+
+      let res = gate_inp
+        .iter()
+        .zip(gate_weights)
+        .map(|(a, b)| a.clone() * b.clone())
+        .fold(Expression::Constant(F::ZERO), |a, b| a + b);
+
+      vec![s * (acc_prev + res - acc)]
+    });
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(GadgetType::Mac, vec![selector]);
+
+    GadgetConfig {
+      columns: gadget_config.columns,
+      selectors,
+      ..gadget_config
+    }
+  }
+
+  // Uses the MAC gadget if the model enables it and the dot product gadget otherwise
+  pub fn dot_product(
+    layouter: impl Layouter<F>,
+    gadget_config: Rc<GadgetConfig>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    if gadget_config.used_gadgets.contains(&GadgetType::Mac) {
+      let mac_chip = MacChip::<F>::construct(gadget_config);
+      mac_chip.forward(layouter, vec_inputs, single_inputs)
+    } else {
+      let dot_prod_chip = DotProductChip::<F>::construct(gadget_config);
+      dot_prod_chip.forward(layouter, vec_inputs, single_inputs)
+    }
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for MacChip<F> {
+  fn name(&self) -> String {
+    "mac".to_string()
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    self.config.columns.len()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    (self.config.columns.len() - 1) / 2
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    1
+  }
+
+  // The caller is expected to pad the inputs and to assign the accumulator of the row above
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    assert_eq!(vec_inputs.len(), 2);
+    assert_eq!(single_inputs.len(), 2);
+    assert!(row_offset > 0);
+
+    let inp = &vec_inputs[0];
+    let weights = &vec_inputs[1];
+    assert_eq!(inp.len(), weights.len());
+    assert_eq!(inp.len(), self.num_inputs_per_row());
+
+    let zero = &single_inputs[0];
+    let acc_prev = &single_inputs[1];
+
+    if self.config.use_selectors {
+      let selector = self.config.selectors.get(&GadgetType::Mac).unwrap()[0];
+      selector.enable(region, row_offset).unwrap();
+    }
+
+    let inp_cols = MacChip::<F>::get_input_columns(&self.config);
+    inp
+      .iter()
+      .enumerate()
+      .map(|(i, cell)| cell.copy_advice(|| "", region, inp_cols[i], row_offset))
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+
+    let weight_cols = MacChip::<F>::get_weight_columns(&self.config);
+    weights
+      .iter()
+      .enumerate()
+      .map(|(i, cell)| cell.copy_advice(|| "", region, weight_cols[i], row_offset))
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+
+    // All columns need to be assigned
+    if self.config.columns.len() % 2 == 0 {
+      zero
+        .copy_advice(
+          || "",
+          region,
+          self.config.columns[self.config.columns.len() - 2],
+          row_offset,
+        )
+        .unwrap();
+    }
+
+    let e = inp
+      .iter()
+      .zip(weights.iter())
+      .map(|(a, b)| a.value().map(|x: &F| *x) * b.value())
+      .fold(acc_prev.value().map(|x: &F| *x), |a, b| a + b);
+
+    let res = region
+      .assign_advice(
+        || "",
+        self.config.columns[self.config.columns.len() - 1],
+        row_offset,
+        || e,
+      )
+      .unwrap();
+
+    Ok(vec![res])
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    assert_eq!(vec_inputs.len(), 2);
+    assert_eq!(single_inputs.len(), 1);
+    let zero = &single_inputs[0];
+
+    let mut inputs = vec_inputs[0].clone();
+    let mut weights = vec_inputs[1].clone();
+    while inputs.len() % self.num_inputs_per_row() != 0 || inputs.len() == 0 {
+      inputs.push(&zero);
+      weights.push(&zero);
+    }
+
+    let output = layouter
+      .assign_region(
+        || "mac rows",
+        |mut region| {
+          // The initial accumulator
+          let mut acc = zero
+            .copy_advice(
+              || "",
+              &mut region,
+              self.config.columns[self.config.columns.len() - 1],
+              0,
+            )
+            .unwrap();
+          for i in 0..inputs.len() / self.num_inputs_per_row() {
+            let inp =
+              inputs[i * self.num_inputs_per_row()..(i + 1) * self.num_inputs_per_row()].to_vec();
+            let weights =
+              weights[i * self.num_inputs_per_row()..(i + 1) * self.num_inputs_per_row()].to_vec();
+            let res = self
+              .op_row_region(&mut region, i + 1, &vec![inp, weights], &vec![zero, &acc])
+              .unwrap();
+            acc = res[0].clone();
+          }
+          Ok(acc)
+        },
+      )
+      .unwrap();
+
+    Ok(vec![output])
+  }
+}
@@ -28,6 +28,34 @@ impl<F: PrimeField> MaxChip<F> {
     3
   }
 
+  // Elementwise max(a, b). Each row compares the first half of its inputs against the second half
+  pub fn pairwise_max(
+    &self,
+    mut layouter: impl Layouter<F>,
+    a: &Vec<&AssignedCell<F, F>>,
+    b: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    assert_eq!(a.len(), b.len());
+    let per_row = self.num_outputs_per_row();
+    let mut inputs = vec![];
+    for i in (0..a.len()).step_by(per_row) {
+      let end = (i + per_row).min(a.len());
+      let pad = per_row - (end - i);
+      inputs.extend(a[i..end].iter().cloned());
+      inputs.extend(vec![a[0]; pad]);
+      inputs.extend(b[i..end].iter().cloned());
+      inputs.extend(vec![b[0]; pad]);
+    }
+
+    let mut outp = self.op_aligned_rows(
+      layouter.namespace(|| "pairwise max"),
+      &vec![inputs],
+      &vec![],
+    )?;
+    outp.truncate(a.len());
+    Ok(outp)
+  }
+
   pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
     let selector = meta.complex_selector();
     let columns = gadget_config.columns;
@@ -0,0 +1,148 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region},
+  halo2curves::ff::PrimeField,
+  plonk::{ConstraintSystem, Error, Expression},
+  poly::Rotation,
+};
+
+use super::gadget::{Gadget, GadgetConfig, GadgetType};
+
+type MuxConfig = GadgetConfig;
+
+// out = c ? a : b = b + c * (a - b), with c constrained to be 0 or 1. Ops are [c, a, b, out].
+pub struct MuxChip<F: PrimeField> {
+  config: Rc<MuxConfig>,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> MuxChip<F> {
+  pub fn construct(config: Rc<MuxConfig>) -> Self {
+    Self {
+      config,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn num_cols_per_op() -> usize {
+    4
+  }
+
+  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    let selector = meta.selector();
+    let columns = gadget_config.columns;
+    let one = Expression::Constant(F::ONE);
+
+    meta.create_gate("mux", |meta| {
+      let s = meta.query_selector(selector);
+      let mut constraints = vec![];
+      for i in 0..columns.len() / Self::num_cols_per_op() {
+        let offset = i * Self::num_cols_per_op();
+        let c = meta.query_advice(columns[offset + 0], Rotation::cur());
+        let a = meta.query_advice(columns[offset + 1], Rotation::cur());
+        let b = meta.query_advice(columns[offset + 2], Rotation::cur());
+        let outp = meta.query_advice(columns[offset + 3], Rotation::cur());
+
+        // c \in {0, 1}
+        constraints.push(s.clone() * c.clone() * (one.clone() - c.clone()));
+        let res = b.clone() + c * (a - b);
+        constraints.push(s.clone() * (res - outp));
+      }
+
+      constraints
+    });
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(GadgetType::Mux, vec![selector]);
+
+    GadgetConfig {
+      columns,
+      selectors,
+      ..gadget_config
+    }
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for MuxChip<F> {
+  fn name(&self) -> String {
+    "mux chip".to_string()
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    Self::num_cols_per_op()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    _single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let cond = &vec_inputs[0];
+    let inp1 = &vec_inputs[1];
+    let inp2 = &vec_inputs[2];
+    assert_eq!(cond.len(), inp1.len());
+    assert_eq!(cond.len(), inp2.len());
+
+    let columns = &self.config.columns;
+
+    if self.config.use_selectors {
+      let selector = self.config.selectors.get(&GadgetType::Mux).unwrap()[0];
+      selector.enable(region, row_offset)?;
+    }
+
+    let mut outps = vec![];
+    for i in 0..cond.len() {
+      let offset = i * self.num_cols_per_op();
+      let c = cond[i].copy_advice(|| "", region, columns[offset + 0], row_offset)?;
+      let a = inp1[i].copy_advice(|| "", region, columns[offset + 1], row_offset)?;
+      let b = inp2[i].copy_advice(|| "", region, columns[offset + 2], row_offset)?;
+      let c = c.value().map(|x: &F| x.to_owned());
+      let a = a.value().map(|x: &F| x.to_owned());
+      let b = b.value().map(|x: &F| x.to_owned());
+      let outp = b + c * (a - b);
+
+      let outp = region.assign_advice(|| "", columns[offset + 3], row_offset, || outp)?;
+      outps.push(outp);
+    }
+    Ok(outps)
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let zero = &single_inputs[0];
+
+    let mut cond = vec_inputs[0].clone();
+    let mut inp1 = vec_inputs[1].clone();
+    let mut inp2 = vec_inputs[2].clone();
+    let initial_len = cond.len();
+    while cond.len() % self.num_inputs_per_row() != 0 {
+      cond.push(zero);
+      inp1.push(zero);
+      inp2.push(zero);
+    }
+
+    let vec_inputs = vec![cond, inp1, inp2];
+
+    let res = self.op_aligned_rows(
+      layouter.namespace(|| format!("forward row {}", self.name())),
+      &vec_inputs,
+      single_inputs,
+    )?;
+    Ok(res[0..initial_len].to_vec())
+  }
+}
@@ -1,8 +1,17 @@
+pub mod cos;
+pub mod custom;
+pub mod erf;
 pub mod exp;
+pub mod gelu;
+pub mod ln;
 pub mod logistic;
 pub mod non_linearity;
+pub mod piecewise_linear;
 pub mod pow;
 pub mod relu;
 pub mod rsqrt;
+pub mod sign;
+pub mod sin;
+pub mod softplus;
 pub mod sqrt;
 pub mod tanh;
@@ -0,0 +1,192 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  marker::PhantomData,
+  rc::Rc,
+};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region},
+  halo2curves::ff::PrimeField,
+  plonk::{ConstraintSystem, Error},
+};
+
+use super::{
+  super::gadget::{Gadget, GadgetConfig, GadgetType},
+  non_linearity::NonLinearGadget,
+  piecewise_linear::{compress, PiecewiseLinear, PiecewiseLinearGadgetChip},
+};
+
+// Functions of the quantized values (x * sf -> y * sf) that can be used by name in models with the
+// CustomNonlinear and PiecewiseLinear layers. They are registered with the circuit when it is
+// generated and reach configure through the gadget config, so circuits in the same process don't
+// share them.
+#[derive(Clone, Debug, Default)]
+pub struct CustomFunctions {
+  pub nonlinearities: BTreeMap<&'static str, fn(i64) -> i64>,
+  pub piecewise_linear: BTreeMap<&'static str, PiecewiseLinear>,
+}
+
+impl CustomFunctions {
+  pub fn register_nonlinearity(&mut self, name: &'static str, func: fn(i64) -> i64) {
+    self.nonlinearities.insert(name, func);
+  }
+
+  pub fn nonlinearity(&self, name: &str) -> (&'static str, fn(i64) -> i64) {
+    let (name, func) = self
+      .nonlinearities
+      .get_key_value(name)
+      .unwrap_or_else(|| panic!("custom nonlinearity {} is not registered", name));
+    (*name, *func)
+  }
+
+  pub fn register_piecewise_linear(&mut self, name: &'static str, pwl: PiecewiseLinear) {
+    self.piecewise_linear.insert(name, pwl);
+  }
+
+  pub fn piecewise_linear(&self, name: &str) -> (&'static str, PiecewiseLinear) {
+    let (name, pwl) = self
+      .piecewise_linear
+      .get_key_value(name)
+      .unwrap_or_else(|| panic!("piecewise linear function {} is not registered", name));
+    (*name, pwl.clone())
+  }
+}
+
+// A lookup table for any function, so new activations don't need their own gadget. The table is
+// func(x) for every x in [min_val, max_val].
+pub struct CustomNonlinearGadgetChip<F: PrimeField> {
+  config: Rc<GadgetConfig>,
+  name: &'static str,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> CustomNonlinearGadgetChip<F> {
+  pub fn construct(config: Rc<GadgetConfig>, name: &'static str) -> Self {
+    Self {
+      config,
+      name,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn generate_custom_map(
+    func: fn(i64) -> i64,
+    min_val: i64,
+    num_rows: i64,
+  ) -> HashMap<i64, i64> {
+    let mut map = HashMap::new();
+    for i in 0..num_rows {
+      map.insert(i as i64, func(i + min_val));
+    }
+    map
+  }
+
+  pub fn configure(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+    name: &'static str,
+  ) -> GadgetConfig {
+    let (name, func) = gadget_config.custom_functions.nonlinearity(name);
+    if gadget_config.nonlinear_input_bits > 0 {
+      let pwl = compress(func, gadget_config.nonlinear_input_bits, gadget_config.num_rows);
+      return PiecewiseLinearGadgetChip::configure_segments(
+        meta,
+        gadget_config,
+        GadgetType::Custom(name),
+        &pwl,
+      );
+    }
+
+    let map = Self::generate_custom_map(func, gadget_config.min_val, gadget_config.num_rows as i64);
+    <CustomNonlinearGadgetChip<F> as NonLinearGadget<F>>::configure_with_map(
+      meta,
+      gadget_config,
+      GadgetType::Custom(name),
+      map,
+    )
+  }
+}
+
+impl<F: PrimeField> NonLinearGadget<F> for CustomNonlinearGadgetChip<F> {
+  // The map depends on the function, see generate_custom_map
+  fn generate_map(_scale_factor: u64, _min_val: i64, _num_rows: i64) -> HashMap<i64, i64> {
+    panic!("custom nonlinearities are generated from their function")
+  }
+
+  fn get_map(&self) -> &HashMap<i64, i64> {
+    &self
+      .config
+      .maps
+      .get(&GadgetType::Custom(self.name))
+      .unwrap()[0]
+  }
+
+  fn get_selector(&self) -> halo2_proofs::plonk::Selector {
+    self
+      .config
+      .selectors
+      .get(&GadgetType::Custom(self.name))
+      .unwrap()[0]
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for CustomNonlinearGadgetChip<F> {
+  fn name(&self) -> String {
+    format!("CustomNonlinearGadgetChip {}", self.name)
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    <CustomNonlinearGadgetChip<F> as NonLinearGadget<F>>::num_cols_per_op()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn load_lookups(&self, layouter: impl Layouter<F>) -> Result<(), Error> {
+    NonLinearGadget::load_lookups(
+      self,
+      layouter,
+      self.config.clone(),
+      GadgetType::Custom(self.name),
+    )?;
+    Ok(())
+  }
+
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    NonLinearGadget::op_row_region(
+      self,
+      region,
+      row_offset,
+      vec_inputs,
+      single_inputs,
+      self.config.clone(),
+    )
+  }
+
+  fn forward(
+    &self,
+    layouter: impl halo2_proofs::circuit::Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    NonLinearGadget::forward(
+      self,
+      layouter,
+      vec_inputs,
+      single_inputs,
+      self.config.clone(),
+      GadgetType::Custom(self.name),
+    )
+  }
+}
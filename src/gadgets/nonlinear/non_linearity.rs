@@ -11,12 +11,22 @@ use crate::gadgets::gadget::convert_to_u128;
 
 use super::super::gadget::Gadget;
 use super::super::gadget::{GadgetConfig, GadgetType};
+use super::piecewise_linear::{compress, PiecewiseLinearGadgetChip};
 
 const NUM_COLS_PER_OP: usize = 2;
 
 pub trait NonLinearGadget<F: PrimeField>: Gadget<F> {
   fn generate_map(scale_factor: u64, min_val: i64, num_rows: i64) -> HashMap<i64, i64>;
 
+  // For the maps that depend on more of the config than the scale factor
+  fn generate_config_map(
+    gadget_config: &GadgetConfig,
+    min_val: i64,
+    num_rows: i64,
+  ) -> HashMap<i64, i64> {
+    Self::generate_map(gadget_config.scale_factor, min_val, num_rows)
+  }
+
   fn get_map(&self) -> &HashMap<i64, i64>;
 
   fn get_selector(&self) -> Selector;
@@ -29,6 +39,30 @@ pub trait NonLinearGadget<F: PrimeField>: Gadget<F> {
     meta: &mut ConstraintSystem<F>,
     gadget_config: GadgetConfig,
     gadget_type: GadgetType,
+  ) -> GadgetConfig {
+    // Inputs wider than the table are handled by interpolating a coarse version of the map
+    if gadget_config.nonlinear_input_bits > 0 {
+      let pwl = compress(
+        |x| Self::generate_config_map(&gadget_config, x, 1)[&0],
+        gadget_config.nonlinear_input_bits,
+        gadget_config.num_rows,
+      );
+      return PiecewiseLinearGadgetChip::configure_segments(meta, gadget_config, gadget_type, &pwl);
+    }
+
+    let non_linear_map = Self::generate_config_map(
+      &gadget_config,
+      gadget_config.min_val,
+      gadget_config.num_rows as i64,
+    );
+    Self::configure_with_map(meta, gadget_config, gadget_type, non_linear_map)
+  }
+
+  fn configure_with_map(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+    gadget_type: GadgetType,
+    non_linear_map: HashMap<i64, i64>,
   ) -> GadgetConfig {
     let selector = meta.complex_selector();
     let columns = gadget_config.columns;
@@ -59,11 +93,6 @@ pub trait NonLinearGadget<F: PrimeField>: Gadget<F> {
     tables.insert(gadget_type, vec![inp_lookup, outp_lookup]);
 
     let mut maps = gadget_config.maps;
-    let non_linear_map = Self::generate_map(
-      gadget_config.scale_factor,
-      gadget_config.min_val,
-      gadget_config.num_rows as i64,
-    );
     maps.insert(gadget_type, vec![non_linear_map]);
 
     GadgetConfig {
@@ -81,6 +110,11 @@ pub trait NonLinearGadget<F: PrimeField>: Gadget<F> {
     config: Rc<GadgetConfig>,
     gadget_type: GadgetType,
   ) -> Result<(), Error> {
+    if config.nonlinear_input_bits > 0 {
+      return PiecewiseLinearGadgetChip::<F>::construct_type(config, gadget_type)
+        .load_lookups(layouter);
+    }
+
     let map = self.get_map();
     let table_col = config.tables.get(&gadget_type).unwrap()[1];
 
@@ -169,7 +203,17 @@ pub trait NonLinearGadget<F: PrimeField>: Gadget<F> {
     mut layouter: impl Layouter<F>,
     vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
     single_inputs: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+    gadget_type: GadgetType,
   ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    if gadget_config.nonlinear_input_bits > 0 {
+      return PiecewiseLinearGadgetChip::<F>::construct_type(gadget_config, gadget_type).forward(
+        layouter,
+        vec_inputs,
+        single_inputs,
+      );
+    }
+
     let zero = &single_inputs[0];
     let inp_len = vec_inputs[0].len();
     let mut inp = vec_inputs[0].clone();
@@ -0,0 +1,379 @@
+use std::{collections::HashMap, marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region, Value},
+  halo2curves::ff::PrimeField,
+  plonk::{ConstraintSystem, Error, Expression},
+  poly::Rotation,
+};
+
+use crate::gadgets::gadget::convert_to_u128;
+
+use super::super::gadget::{Gadget, GadgetConfig, GadgetType};
+
+// A function of the quantized values, interpolated linearly between the points
+// x_min + i * seg_width. The segments are (f(x_i), f(x_{i + 1}) - f(x_i)) and the domain is
+// [x_min, x_min + segments.len() * seg_width), which can be much wider than the lookup tables.
+#[derive(Clone, Debug, Default)]
+pub struct PiecewiseLinear {
+  pub x_min: i64,
+  pub seg_width: i64,
+  pub segments: Vec<(i64, i64)>,
+}
+
+impl PiecewiseLinear {
+  pub fn new(func: impl Fn(i64) -> i64, x_min: i64, seg_width: i64, num_segments: i64) -> Self {
+    let segments = (0..num_segments)
+      .map(|i| {
+        let x = x_min + i * seg_width;
+        let y = func(x);
+        (y, func(x + seg_width) - y)
+      })
+      .collect();
+    Self {
+      x_min,
+      seg_width,
+      segments,
+    }
+  }
+}
+
+// The coarse version of a nonlinearity on inputs of the given number of bits. There are at most
+// 2^12 segments, and the segments must be small enough to range check their remainders.
+pub fn compress(func: impl Fn(i64) -> i64, input_bits: i64, num_rows: usize) -> PiecewiseLinear {
+  let mut num_segments = 1 << 12;
+  while num_segments >= num_rows as i64 {
+    num_segments /= 2;
+  }
+  let x_min = -(1 << (input_bits - 1));
+  let seg_width = ((1 << input_bits) / num_segments).max(1);
+  assert!(
+    2 * seg_width as usize <= num_rows,
+    "{} bit inputs need more rows to compress",
+    input_bits
+  );
+  PiecewiseLinear::new(func, x_min, seg_width, num_segments)
+}
+
+const NUM_COLS_PER_OP: usize = 7;
+
+fn to_field<F: PrimeField>(x: i64) -> F {
+  if x >= 0 {
+    F::from(x as u64)
+  } else {
+    -F::from(x.unsigned_abs())
+  }
+}
+
+// Ops are [x, s, r, y_i, delta_i, y, rem], where s = segment + 1 so that the all-zero row of the
+// segment table can be used for the disabled rows, x = x_min + segment * seg_width + r, and
+// 2 * (seg_width * y_i + delta_i * r) + seg_width = 2 * seg_width * y + rem. r and rem are range
+// checked with the input lookup, so 2 * seg_width must be at most the number of rows.
+// The maps are [y_i, delta_i, {0: x_min, 1: seg_width}].
+pub struct PiecewiseLinearGadgetChip<F: PrimeField> {
+  config: Rc<GadgetConfig>,
+  gadget_type: GadgetType,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> PiecewiseLinearGadgetChip<F> {
+  pub fn construct(config: Rc<GadgetConfig>, name: &'static str) -> Self {
+    Self::construct_type(config, GadgetType::PiecewiseLinear(name))
+  }
+
+  // For the gadgets that are configured with configure_segments under their own type
+  pub fn construct_type(config: Rc<GadgetConfig>, gadget_type: GadgetType) -> Self {
+    Self {
+      config,
+      gadget_type,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn configure(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+    name: &'static str,
+  ) -> GadgetConfig {
+    let (name, pwl) = gadget_config.custom_functions.piecewise_linear(name);
+    Self::configure_segments(meta, gadget_config, GadgetType::PiecewiseLinear(name), &pwl)
+  }
+
+  pub fn configure_segments(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+    gadget_type: GadgetType,
+    pwl: &PiecewiseLinear,
+  ) -> GadgetConfig {
+    let (x_min, seg_width, segments) = (pwl.x_min, pwl.seg_width, &pwl.segments);
+    // With fewer columns there would be no ops per row, so nothing would be constrained
+    assert!(
+      gadget_config.columns.len() >= NUM_COLS_PER_OP,
+      "piecewise linear functions need at least {} columns",
+      NUM_COLS_PER_OP
+    );
+    assert!(2 * seg_width as usize <= gadget_config.num_rows);
+    assert!(segments.len() < gadget_config.num_rows);
+
+    let selector = meta.complex_selector();
+    let columns = gadget_config.columns;
+    let x_min_expr = Expression::Constant(to_field::<F>(x_min));
+    let w = Expression::Constant(F::from(seg_width as u64));
+    let one = Expression::Constant(F::ONE);
+    let two = Expression::Constant(F::from(2));
+
+    let mut tables = gadget_config.tables;
+    let inp_lookup = tables.get(&GadgetType::InputLookup).unwrap()[0];
+    let seg_lookup = meta.lookup_table_column();
+    let y_lookup = meta.lookup_table_column();
+    let delta_lookup = meta.lookup_table_column();
+
+    meta.create_gate("piecewise linear", |meta| {
+      let s = meta.query_selector(selector);
+      let mut constraints = vec![];
+      for i in 0..columns.len() / NUM_COLS_PER_OP {
+        let offset = i * NUM_COLS_PER_OP;
+        let x = meta.query_advice(columns[offset + 0], Rotation::cur());
+        let seg = meta.query_advice(columns[offset + 1], Rotation::cur());
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+        let y0 = meta.query_advice(columns[offset + 3], Rotation::cur());
+        let delta = meta.query_advice(columns[offset + 4], Rotation::cur());
+        let y = meta.query_advice(columns[offset + 5], Rotation::cur());
+        let rem = meta.query_advice(columns[offset + 6], Rotation::cur());
+
+        // x = x_min + (s - 1) * w + r
+        let x_rhs = x_min_expr.clone() + (seg - one.clone()) * w.clone() + r.clone();
+        constraints.push(s.clone() * (x - x_rhs));
+
+        // 2 * (w * y_i + delta_i * r) + w = 2 * w * y + rem
+        let lhs = two.clone() * (w.clone() * y0 + delta * r) + w.clone();
+        let rhs = two.clone() * w.clone() * y + rem;
+        constraints.push(s.clone() * (lhs - rhs));
+      }
+      constraints
+    });
+
+    for i in 0..columns.len() / NUM_COLS_PER_OP {
+      let offset = i * NUM_COLS_PER_OP;
+      meta.lookup("piecewise linear segment", |meta| {
+        let s = meta.query_selector(selector);
+        let seg = meta.query_advice(columns[offset + 1], Rotation::cur());
+        let y0 = meta.query_advice(columns[offset + 3], Rotation::cur());
+        let delta = meta.query_advice(columns[offset + 4], Rotation::cur());
+
+        vec![
+          (s.clone() * seg, seg_lookup),
+          (s.clone() * y0, y_lookup),
+          (s.clone() * delta, delta_lookup),
+        ]
+      });
+
+      // r \in [0, w)
+      meta.lookup("piecewise linear r", |meta| {
+        let s = meta.query_selector(selector);
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+        vec![(s * r, inp_lookup)]
+      });
+      meta.lookup("piecewise linear w-1-r", |meta| {
+        let s = meta.query_selector(selector);
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+        vec![(s * (w.clone() - one.clone() - r), inp_lookup)]
+      });
+
+      // rem \in [0, 2w)
+      meta.lookup("piecewise linear rem", |meta| {
+        let s = meta.query_selector(selector);
+        let rem = meta.query_advice(columns[offset + 6], Rotation::cur());
+        vec![(s * rem, inp_lookup)]
+      });
+      meta.lookup("piecewise linear 2w-1-rem", |meta| {
+        let s = meta.query_selector(selector);
+        let rem = meta.query_advice(columns[offset + 6], Rotation::cur());
+        vec![(
+          s * (two.clone() * w.clone() - one.clone() - rem),
+          inp_lookup,
+        )]
+      });
+    }
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(gadget_type, vec![selector]);
+
+    tables.insert(gadget_type, vec![seg_lookup, y_lookup, delta_lookup]);
+
+    let mut maps = gadget_config.maps;
+    let y_map = segments
+      .iter()
+      .enumerate()
+      .map(|(i, x)| (i as i64, x.0))
+      .collect::<HashMap<_, _>>();
+    let delta_map = segments
+      .iter()
+      .enumerate()
+      .map(|(i, x)| (i as i64, x.1))
+      .collect::<HashMap<_, _>>();
+    let params_map = HashMap::from([(0, x_min), (1, seg_width)]);
+    maps.insert(gadget_type, vec![y_map, delta_map, params_map]);
+
+    GadgetConfig {
+      columns,
+      selectors,
+      tables,
+      maps,
+      ..gadget_config
+    }
+  }
+
+  pub fn x_min(&self) -> i64 {
+    self.config.maps[&self.gadget_type][2][&0]
+  }
+
+  // Returns [s, r, y_i, delta_i, y, rem] for the input x
+  fn eval(&self, x: i64) -> [i64; 6] {
+    let maps = &self.config.maps[&self.gadget_type];
+    let (x_min, w) = (maps[2][&0], maps[2][&1]);
+    let segment = (x - x_min).div_euclid(w);
+    let r = (x - x_min).rem_euclid(w);
+    assert!(
+      (0..maps[0].len() as i64).contains(&segment),
+      "{} is outside of the piecewise linear domain",
+      x
+    );
+    let (y0, delta) = (maps[0][&segment], maps[1][&segment]);
+    let num = 2 * (w * y0 + delta * r) + w;
+    [
+      segment + 1,
+      r,
+      y0,
+      delta,
+      num.div_euclid(2 * w),
+      num.rem_euclid(2 * w),
+    ]
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for PiecewiseLinearGadgetChip<F> {
+  fn name(&self) -> String {
+    format!("PiecewiseLinearGadgetChip {:?}", self.gadget_type)
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    NUM_COLS_PER_OP
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn load_lookups(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+    let tables = &self.config.tables[&self.gadget_type];
+    let maps = &self.config.maps[&self.gadget_type];
+
+    layouter.assign_table(
+      || "piecewise linear table",
+      |mut table| {
+        // Row 0 is the all-zero row for the disabled rows
+        for s in 0..maps[0].len() as i64 + 1 {
+          let (y0, delta) = if s == 0 {
+            (0, 0)
+          } else {
+            (maps[0][&(s - 1)], maps[1][&(s - 1)])
+          };
+          let vals = [s, y0, delta];
+          for (col, val) in tables.iter().zip(vals.iter()) {
+            table.assign_cell(
+              || "piecewise linear cell",
+              *col,
+              s as usize,
+              || Value::known(to_field::<F>(*val)),
+            )?;
+          }
+        }
+        Ok(())
+      },
+    )?;
+    Ok(())
+  }
+
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    _single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let columns = &self.config.columns;
+    let inp = &vec_inputs[0];
+    let shift_val_pos_i64 = -self.config.shift_min_val;
+    let shift_val_pos = F::from(shift_val_pos_i64 as u64);
+
+    if self.config.use_selectors {
+      let selector = self.config.selectors.get(&self.gadget_type).unwrap()[0];
+      selector.enable(region, row_offset)?;
+    }
+
+    let mut outps = vec![];
+    for i in 0..inp.len() {
+      let offset = i * NUM_COLS_PER_OP;
+      inp[i].copy_advice(|| "", region, columns[offset + 0], row_offset)?;
+      let vals = inp[i].value().map(|x: &F| {
+        let x = convert_to_u128(&(*x + shift_val_pos)) as i64 - shift_val_pos_i64;
+        self.eval(x)
+      });
+
+      let mut cells = vec![];
+      for (j, col) in columns[offset + 1..offset + NUM_COLS_PER_OP]
+        .iter()
+        .enumerate()
+      {
+        let cell = region.assign_advice(
+          || "piecewise linear",
+          *col,
+          row_offset,
+          || vals.map(|x| to_field::<F>(x[j])),
+        )?;
+        cells.push(cell);
+      }
+      outps.push(cells[4].clone());
+    }
+
+    Ok(outps)
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let mut inp = vec_inputs[0].clone();
+    let inp_len = inp.len();
+
+    // Pad with x_min, which is always in the domain
+    let x_min = layouter.assign_region(
+      || "piecewise linear padding",
+      |mut region| {
+        region.assign_fixed(
+          || "x_min",
+          self.config.fixed_columns[0],
+          0,
+          || Value::known(to_field::<F>(self.x_min())),
+        )
+      },
+    )?;
+    while inp.len() % self.num_inputs_per_row() != 0 {
+      inp.push(&x_min);
+    }
+
+    let outp = self.op_aligned_rows(
+      layouter.namespace(|| format!("forward row {}", self.name())),
+      &vec![inp],
+      single_inputs,
+    )?;
+    Ok(outp[0..inp_len].to_vec())
+  }
+}
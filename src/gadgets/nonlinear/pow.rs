@@ -11,7 +11,8 @@ use super::{
   non_linearity::NonLinearGadget,
 };
 
-// IMPORTANT: PowGadget assumes a single power across the entire DAG
+// IMPORTANT: PowGadget assumes a single power across the entire DAG. Integer powers don't use
+// this gadget (see PowChip).
 pub struct PowGadgetChip<F: PrimeField> {
   config: Rc<GadgetConfig>,
   _marker: PhantomData<F>,
@@ -31,8 +32,17 @@ impl<F: PrimeField> PowGadgetChip<F> {
 }
 
 impl<F: PrimeField> NonLinearGadget<F> for PowGadgetChip<F> {
-  fn generate_map(scale_factor: u64, min_val: i64, num_rows: i64) -> HashMap<i64, i64> {
-    let power = 3.; // FIXME: need to make this variable somehow...
+  fn generate_map(_scale_factor: u64, _min_val: i64, _num_rows: i64) -> HashMap<i64, i64> {
+    panic!("the pow map depends on the exponent, see generate_config_map")
+  }
+
+  fn generate_config_map(
+    gadget_config: &GadgetConfig,
+    min_val: i64,
+    num_rows: i64,
+  ) -> HashMap<i64, i64> {
+    let scale_factor = gadget_config.scale_factor;
+    let power = gadget_config.pow_exponent;
 
     let mut map = HashMap::new();
     for i in 0..num_rows {
@@ -100,6 +110,13 @@ impl<F: PrimeField> Gadget<F> for PowGadgetChip<F> {
     vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
     single_inputs: &Vec<&AssignedCell<F, F>>,
   ) -> Result<Vec<AssignedCell<F, F>>, Error> {
-    NonLinearGadget::forward(self, layouter, vec_inputs, single_inputs)
+    NonLinearGadget::forward(
+      self,
+      layouter,
+      vec_inputs,
+      single_inputs,
+      self.config.clone(),
+      GadgetType::Pow,
+    )
   }
 }
@@ -95,6 +95,13 @@ impl<F: PrimeField> Gadget<F> for ReluChip<F> {
     vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
     single_inputs: &Vec<&AssignedCell<F, F>>,
   ) -> Result<Vec<AssignedCell<F, F>>, Error> {
-    NonLinearGadget::forward(self, layouter, vec_inputs, single_inputs)
+    NonLinearGadget::forward(
+      self,
+      layouter,
+      vec_inputs,
+      single_inputs,
+      self.config.clone(),
+      GadgetType::Relu,
+    )
   }
 }
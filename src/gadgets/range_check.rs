@@ -0,0 +1,206 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region, Value},
+  halo2curves::ff::PrimeField,
+  plonk::{ConstraintSystem, Error, Expression},
+  poly::Rotation,
+};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+use super::gadget::{Gadget, GadgetConfig, GadgetType};
+
+type RangeCheckConfig = GadgetConfig;
+
+// Checks that x \in [0, 2^num_bits). x is decomposed into limbs of limb_bits bits that are each
+// looked up in the input table, so the number of bits is not limited by the table size. The limbs
+// above the top (partial) limb are constrained to zero, and the top limb is shifted to the top of
+// the limb range and looked up again. One op per row: [x, top, shift, shifted top, limbs...]. The
+// outputs are the range checked copies of the inputs.
+pub struct RangeCheckChip<F: PrimeField> {
+  config: Rc<RangeCheckConfig>,
+  num_bits: usize,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> RangeCheckChip<F> {
+  pub fn construct(config: Rc<RangeCheckConfig>, num_bits: usize) -> Self {
+    Self {
+      config,
+      num_bits,
+      _marker: PhantomData,
+    }
+  }
+
+  // The largest number of bits such that every limb fits in the input table
+  pub fn limb_bits(num_rows: usize) -> usize {
+    (usize::BITS - 1 - num_rows.leading_zeros()) as usize
+  }
+
+  pub fn num_limbs(num_cols: usize) -> usize {
+    num_cols - 4
+  }
+
+  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    let selector = meta.complex_selector();
+    let columns = gadget_config.columns;
+    let tables = gadget_config.tables;
+    let inp_lookup = tables.get(&GadgetType::InputLookup).unwrap()[0];
+    let limb_bits = Self::limb_bits(gadget_config.num_rows);
+    let num_limbs = Self::num_limbs(columns.len());
+
+    meta.create_gate("range check", |meta| {
+      let s = meta.query_selector(selector);
+      let x = meta.query_advice(columns[0], Rotation::cur());
+      let top = meta.query_advice(columns[1], Rotation::cur());
+      let shift = meta.query_advice(columns[2], Rotation::cur());
+      let shifted = meta.query_advice(columns[3], Rotation::cur());
+
+      let mut recomposed = Expression::Constant(F::ZERO);
+      for i in 0..num_limbs {
+        let limb = meta.query_advice(columns[4 + i], Rotation::cur());
+        let base = Expression::Constant(F::from(2).pow_vartime([(i * limb_bits) as u64]));
+        recomposed = recomposed + limb * base;
+      }
+
+      vec![s.clone() * (x - recomposed), s * (shifted - top * shift)]
+    });
+
+    // The table is [0, num_rows), so limb + (num_rows - 2^limb_bits) is also looked up
+    let offset = (gadget_config.num_rows - (1 << limb_bits)) as u64;
+    for i in 0..num_limbs + 1 {
+      let col = if i == num_limbs { 3 } else { 4 + i };
+      meta.lookup("range check limb", |meta| {
+        let s = meta.query_selector(selector);
+        let limb = meta.query_advice(columns[col], Rotation::cur());
+        vec![(s * limb, inp_lookup)]
+      });
+      meta.lookup("range check limb offset", |meta| {
+        let s = meta.query_selector(selector);
+        let limb = meta.query_advice(columns[col], Rotation::cur());
+        vec![(
+          s * (limb + Expression::Constant(F::from(offset))),
+          inp_lookup,
+        )]
+      });
+    }
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(GadgetType::RangeCheck, vec![selector]);
+
+    GadgetConfig {
+      columns,
+      selectors,
+      tables,
+      ..gadget_config
+    }
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for RangeCheckChip<F> {
+  fn name(&self) -> String {
+    "range check".to_string()
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    self.config.columns.len()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    1
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    1
+  }
+
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let columns = &self.config.columns;
+    let zero = single_inputs[0];
+    let shift = single_inputs[1];
+    let limb_bits = Self::limb_bits(self.config.num_rows);
+    let num_limbs = Self::num_limbs(columns.len());
+    let (top_idx, top_bits) = (self.num_bits / limb_bits, self.num_bits % limb_bits);
+
+    if self.config.use_selectors {
+      let selector = self.config.selectors.get(&GadgetType::RangeCheck).unwrap()[0];
+      selector.enable(region, row_offset)?;
+    }
+
+    let x = vec_inputs[0][0].copy_advice(|| "", region, columns[0], row_offset)?;
+    let mut limbs = vec![];
+    for i in 0..num_limbs {
+      let limb = x.value().map(|x: &F| {
+        let x = BigUint::from_bytes_le(x.to_repr().as_ref());
+        let limb = (x >> (i * limb_bits)) % (BigUint::from(1u64) << limb_bits);
+        F::from(limb.to_u64().unwrap())
+      });
+      let limb = region.assign_advice(|| "", columns[4 + i], row_offset, || limb)?;
+      limbs.push(limb);
+    }
+
+    // The limbs above the top limb are zero
+    let num_zeros = if top_bits == 0 { top_idx } else { top_idx + 1 };
+    for limb in limbs.iter().skip(num_zeros) {
+      region.constrain_equal(limb.cell(), zero.cell())?;
+    }
+    let top = if top_bits == 0 {
+      zero.copy_advice(|| "", region, columns[1], row_offset)?
+    } else {
+      let top = limbs[top_idx].value().map(|x: &F| x.to_owned());
+      let top = region.assign_advice(|| "", columns[1], row_offset, || top)?;
+      region.constrain_equal(top.cell(), limbs[top_idx].cell())?;
+      top
+    };
+    let shift = shift.copy_advice(|| "", region, columns[2], row_offset)?;
+    let shifted = top.value().zip(shift.value()).map(|(t, s)| *t * *s);
+    region.assign_advice(|| "", columns[3], row_offset, || shifted)?;
+
+    Ok(vec![x])
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let limb_bits = Self::limb_bits(self.config.num_rows);
+    let num_limbs = Self::num_limbs(self.config.columns.len());
+    assert!(self.num_bits <= limb_bits * num_limbs);
+    assert!(self.num_bits < F::NUM_BITS as usize - 1);
+
+    // Shift the top limb so that it is only in range if its unused bits are zero
+    let top_bits = self.num_bits % limb_bits;
+    let shift_bits = if top_bits == 0 {
+      0
+    } else {
+      limb_bits - top_bits
+    };
+    let shift = layouter.assign_region(
+      || "range check shift",
+      |mut region| {
+        region.assign_fixed(
+          || "range check shift",
+          self.config.fixed_columns[0],
+          0,
+          || Value::known(F::from(1u64 << shift_bits)),
+        )
+      },
+    )?;
+
+    let zero = single_inputs[0];
+    self.op_aligned_rows(
+      layouter.namespace(|| format!("forward row {}", self.name())),
+      vec_inputs,
+      &vec![zero, &shift],
+    )
+  }
+}
@@ -0,0 +1,246 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region},
+  halo2curves::ff::PrimeField,
+  plonk::{ConstraintSystem, Error, Expression},
+  poly::Rotation,
+};
+use rounded_div::RoundedDiv;
+
+use super::gadget::{convert_to_u128, Gadget, GadgetConfig, GadgetType};
+
+type ShiftConfig = GadgetConfig;
+
+// Multiplies or divides (with rounding) by the scale factor, which must be a power of two. Since
+// the divisor is a constant, there is no shared divisor column and the divisor is not range
+// checked, so this is cheaper than VarDivRoundChip. Ops are [a, c, r] per row.
+pub struct ShiftChip<F: PrimeField> {
+  config: Rc<ShiftConfig>,
+  left: bool,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> ShiftChip<F> {
+  // Divides by the scale factor
+  pub fn construct(config: Rc<ShiftConfig>) -> Self {
+    Self {
+      config,
+      left: false,
+      _marker: PhantomData,
+    }
+  }
+
+  // Multiplies by the scale factor
+  pub fn construct_left(config: Rc<ShiftConfig>) -> Self {
+    Self {
+      config,
+      left: true,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn num_cols_per_op() -> usize {
+    3
+  }
+
+  // The remainders go up to 2 * sf - 1, which must be in the input lookup table
+  pub fn supports(scale_factor: u64, num_rows: usize) -> bool {
+    scale_factor.is_power_of_two() && 2 * scale_factor as usize <= num_rows
+  }
+
+  pub fn configure(
+    meta: &mut ConstraintSystem<F>,
+    gadget_config: GadgetConfig,
+  ) -> Result<GadgetConfig, Error> {
+    let sf = gadget_config.scale_factor;
+    if !sf.is_power_of_two() {
+      return Err(Error::Synthesis);
+    }
+    if !Self::supports(sf, gadget_config.num_rows) {
+      return Err(Error::NotEnoughRowsAvailable {
+        current_k: gadget_config.k as u32,
+      });
+    }
+
+    let columns = gadget_config.columns;
+    let right_selector = meta.complex_selector();
+    let left_selector = meta.selector();
+
+    let sf_expr = Expression::Constant(F::from(sf));
+    let two = Expression::Constant(F::from(2));
+
+    let tables = gadget_config.tables;
+    let lookup = tables.get(&GadgetType::InputLookup).unwrap()[0];
+
+    // a | c | r
+    // (2 * a + sf) = (2 * sf) * c + r
+    meta.create_gate("shift_right_arithm", |meta| {
+      let s = meta.query_selector(right_selector);
+      let mut constraints = vec![];
+      for i in 0..columns.len() / Self::num_cols_per_op() {
+        let offset = i * Self::num_cols_per_op();
+        let a = meta.query_advice(columns[offset], Rotation::cur());
+        let c = meta.query_advice(columns[offset + 1], Rotation::cur());
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+
+        let lhs = a * two.clone() + sf_expr.clone();
+        let rhs = sf_expr.clone() * two.clone() * c + r;
+        constraints.push(s.clone() * (lhs - rhs));
+      }
+      constraints
+    });
+
+    // a | c | -
+    // c = a * sf
+    meta.create_gate("shift_left_arithm", |meta| {
+      let s = meta.query_selector(left_selector);
+      let mut constraints = vec![];
+      for i in 0..columns.len() / Self::num_cols_per_op() {
+        let offset = i * Self::num_cols_per_op();
+        let a = meta.query_advice(columns[offset], Rotation::cur());
+        let c = meta.query_advice(columns[offset + 1], Rotation::cur());
+        constraints.push(s.clone() * (c - a * sf_expr.clone()));
+      }
+      constraints
+    });
+
+    for i in 0..columns.len() / Self::num_cols_per_op() {
+      let offset = i * Self::num_cols_per_op();
+      // r \in [0, 2^N)
+      meta.lookup("shift range checks r", |meta| {
+        let s = meta.query_selector(right_selector);
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+
+        vec![(s * r, lookup)]
+      });
+
+      // 2 * sf - 1 - r \in [0, 2^N)
+      meta.lookup("shift range checks 2sf-1-r", |meta| {
+        let s = meta.query_selector(right_selector);
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+        let max_r = Expression::Constant(F::from(2 * sf - 1));
+
+        vec![(s * (max_r - r), lookup)]
+      });
+    }
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(GadgetType::Shift, vec![right_selector, left_selector]);
+
+    Ok(GadgetConfig {
+      columns,
+      tables,
+      selectors,
+      ..gadget_config
+    })
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for ShiftChip<F> {
+  fn name(&self) -> String {
+    "ShiftChip".to_string()
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    Self::num_cols_per_op()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    self.num_inputs_per_row()
+  }
+
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    _single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let sf = self.config.scale_factor;
+    let div_outp_min_val_i64 = self.config.div_outp_min_val;
+    // Needs to be divisible by sf
+    let div_inp_min_val_pos_i64 = -self.config.shift_min_val / (sf as i64) * (sf as i64);
+
+    if self.config.use_selectors {
+      let selectors = self.config.selectors.get(&GadgetType::Shift).unwrap();
+      let selector = if self.left {
+        selectors[1]
+      } else {
+        selectors[0]
+      };
+      selector.enable(region, row_offset)?;
+    }
+
+    let mut outp = vec![];
+    for (i, a) in vec_inputs[0].iter().enumerate() {
+      let offset = i * self.num_cols_per_op();
+      a.copy_advice(|| "", region, self.config.columns[offset], row_offset)?;
+
+      if self.left {
+        let c = a.value().map(|a| *a * F::from(sf));
+        let c_cell =
+          region.assign_advice(|| "", self.config.columns[offset + 1], row_offset, || c)?;
+        outp.push(c_cell);
+        continue;
+      }
+
+      let div_mod = a.value().map(|a| {
+        let a_pos = *a + F::from(div_inp_min_val_pos_i64 as u64);
+        let a = convert_to_u128(&a_pos);
+        // c = (2 * a + sf) / (2 * sf)
+        let c_pos = a.rounded_div(sf as u128);
+        let c = (c_pos as i128 - (div_inp_min_val_pos_i64 as u128 / sf as u128) as i128) as i64;
+
+        // r = (2 * a + sf) % (2 * sf)
+        let rem_floor = (a as i128) - (c_pos * sf as u128) as i128;
+        let r = 2 * rem_floor + (sf as i128);
+        (c, r as i64)
+      });
+
+      let div_cell = region.assign_advice(
+        || "",
+        self.config.columns[offset + 1],
+        row_offset,
+        || {
+          div_mod.map(|(c, _)| {
+            let offset = F::from(-div_outp_min_val_i64 as u64);
+            let c = F::from((c - div_outp_min_val_i64) as u64);
+            c - offset
+          })
+        },
+      )?;
+      region.assign_advice(
+        || "",
+        self.config.columns[offset + 2],
+        row_offset,
+        || div_mod.map(|(_, r)| F::from(r as u64)),
+      )?;
+      outp.push(div_cell);
+    }
+
+    Ok(outp)
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let mut inps = vec_inputs[0].clone();
+    let initial_len = inps.len();
+
+    let default = &single_inputs[0];
+    while inps.len() % self.num_inputs_per_row() != 0 {
+      inps.push(&default);
+    }
+
+    let res = self.op_aligned_rows(layouter.namespace(|| "shift"), &vec![inps], single_inputs)?;
+    Ok(res[..initial_len].to_vec())
+  }
+}
@@ -0,0 +1,188 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region, Value},
+  halo2curves::ff::PrimeField,
+  plonk::{ConstraintSystem, Error, Expression},
+  poly::Rotation,
+};
+use num_bigint::BigUint;
+
+use super::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  range_check::RangeCheckChip,
+};
+
+type SignBitConfig = GadgetConfig;
+
+// Outputs b = 1 if x < 0 and 0 otherwise, for x \in [-2^num_bits, 2^num_bits). Unlike SignChip,
+// the range of x is not limited by the table size. b is boolean and y = x + b * 2^num_bits is
+// range checked to [0, 2^num_bits), so b must be the sign bit. Ops are [x, b, shift, y] per row.
+// RangeCheck must also be configured.
+pub struct SignBitChip<F: PrimeField> {
+  config: Rc<SignBitConfig>,
+  num_bits: usize,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> SignBitChip<F> {
+  pub fn construct(config: Rc<SignBitConfig>, num_bits: usize) -> Self {
+    Self {
+      config,
+      num_bits,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn num_cols_per_op() -> usize {
+    4
+  }
+
+  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    let selector = meta.selector();
+    let columns = gadget_config.columns;
+
+    meta.create_gate("sign bit", |meta| {
+      let s = meta.query_selector(selector);
+      let mut constraints = vec![];
+      for i in 0..columns.len() / Self::num_cols_per_op() {
+        let offset = i * Self::num_cols_per_op();
+        let x = meta.query_advice(columns[offset], Rotation::cur());
+        let b = meta.query_advice(columns[offset + 1], Rotation::cur());
+        let shift = meta.query_advice(columns[offset + 2], Rotation::cur());
+        let y = meta.query_advice(columns[offset + 3], Rotation::cur());
+
+        let one = Expression::Constant(F::ONE);
+        constraints.push(s.clone() * b.clone() * (one - b.clone()));
+        constraints.push(s.clone() * (y - (x + b * shift)));
+      }
+      constraints
+    });
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(GadgetType::SignBit, vec![selector]);
+
+    GadgetConfig {
+      columns,
+      selectors,
+      ..gadget_config
+    }
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for SignBitChip<F> {
+  fn name(&self) -> String {
+    "sign bit".to_string()
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    Self::num_cols_per_op()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    self.config.columns.len() / self.num_cols_per_op()
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    2 * self.num_inputs_per_row()
+  }
+
+  // Outputs the sign bits followed by the shifted values
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let columns = &self.config.columns;
+    let shift = single_inputs[1];
+
+    if self.config.use_selectors {
+      let selector = self.config.selectors.get(&GadgetType::SignBit).unwrap()[0];
+      selector.enable(region, row_offset)?;
+    }
+
+    let mut bits = vec![];
+    let mut shifted = vec![];
+    for (i, inp) in vec_inputs[0].iter().enumerate() {
+      let offset = i * self.num_cols_per_op();
+      let x = inp.copy_advice(|| "", region, columns[offset], row_offset)?;
+      // x is negative if -x has the smaller representative
+      let b = x.value().map(|x: &F| {
+        let pos = BigUint::from_bytes_le(x.to_repr().as_ref());
+        let neg = BigUint::from_bytes_le((-*x).to_repr().as_ref());
+        if pos > neg {
+          F::ONE
+        } else {
+          F::ZERO
+        }
+      });
+      let b = region.assign_advice(|| "", columns[offset + 1], row_offset, || b)?;
+      let shift = shift.copy_advice(|| "", region, columns[offset + 2], row_offset)?;
+      let y = x
+        .value()
+        .zip(b.value())
+        .zip(shift.value())
+        .map(|((x, b), s)| *x + *b * *s);
+      let y = region.assign_advice(|| "", columns[offset + 3], row_offset, || y)?;
+      bits.push(b);
+      shifted.push(y);
+    }
+    bits.extend(shifted);
+
+    Ok(bits)
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let zero = single_inputs[0];
+    let shift = layouter.assign_region(
+      || "sign bit shift",
+      |mut region| {
+        region.assign_fixed(
+          || "sign bit shift",
+          self.config.fixed_columns[0],
+          0,
+          || Value::known(F::from(2).pow_vartime([self.num_bits as u64])),
+        )
+      },
+    )?;
+
+    let mut inps = vec_inputs[0].clone();
+    let initial_len = inps.len();
+    while inps.len() % self.num_inputs_per_row() != 0 {
+      inps.push(zero);
+    }
+
+    let outp = self.op_aligned_rows(
+      layouter.namespace(|| format!("forward row {}", self.name())),
+      &vec![inps],
+      &vec![zero, &shift],
+    )?;
+
+    // Split the per-row outputs into the sign bits and the shifted values
+    let num_per_row = self.num_inputs_per_row();
+    let mut bits = vec![];
+    let mut shifted = vec![];
+    for row in outp.chunks(2 * num_per_row) {
+      bits.extend(row[..num_per_row].iter().cloned());
+      shifted.extend(row[num_per_row..].iter().cloned());
+    }
+    bits.truncate(initial_len);
+    shifted.truncate(initial_len);
+
+    let range_check_chip = RangeCheckChip::<F>::construct(self.config.clone(), self.num_bits);
+    range_check_chip.forward(
+      layouter.namespace(|| "sign bit range check"),
+      &vec![shifted.iter().collect()],
+      &vec![zero],
+    )?;
+
+    Ok(bits)
+  }
+}
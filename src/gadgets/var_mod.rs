@@ -0,0 +1,227 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Region},
+  halo2curves::ff::PrimeField,
+  plonk::{ConstraintSystem, Error, Expression},
+  poly::Rotation,
+};
+
+use super::gadget::{convert_to_u128, Gadget, GadgetConfig, GadgetType};
+
+type VarModConfig = GadgetConfig;
+
+// Floored division with remainder: a = q * b + r with 0 <= r < b. The outputs are the quotients
+// followed by the remainders.
+pub struct VarModChip<F: PrimeField> {
+  config: Rc<VarModConfig>,
+  _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> VarModChip<F> {
+  pub fn construct(config: Rc<VarModConfig>) -> Self {
+    Self {
+      config,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn num_cols_per_op() -> usize {
+    3
+  }
+
+  pub fn configure(meta: &mut ConstraintSystem<F>, gadget_config: GadgetConfig) -> GadgetConfig {
+    let columns = gadget_config.columns;
+    let selector = meta.complex_selector();
+
+    let tables = gadget_config.tables;
+    let lookup = tables.get(&GadgetType::InputLookup).unwrap()[0];
+    let q_shift = Expression::Constant(F::from(-gadget_config.div_outp_min_val as u64));
+
+    // a | q | r | ... | b
+    // a = b * q + r
+    // b - 1 - r \in [0, 2^N) <-- forces b > r
+    // q - div_outp_min_val \in [0, 2^N) <-- otherwise q could wrap around the field
+    meta.create_gate("var_mod_arithm", |meta| {
+      let s = meta.query_selector(selector);
+      let mut constraints = vec![];
+
+      let b = meta.query_advice(columns[columns.len() - 1], Rotation::cur());
+      for i in 0..(columns.len() - 1) / Self::num_cols_per_op() {
+        let offset = i * Self::num_cols_per_op();
+        let a = meta.query_advice(columns[offset], Rotation::cur());
+        let q = meta.query_advice(columns[offset + 1], Rotation::cur());
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+
+        constraints.push(s.clone() * (a - (b.clone() * q + r)));
+      }
+
+      constraints
+    });
+
+    for i in 0..(columns.len() - 1) / Self::num_cols_per_op() {
+      let offset = i * Self::num_cols_per_op();
+      // r \in [0, 2^N)
+      meta.lookup("var mod range checks r", |meta| {
+        let s = meta.query_selector(selector);
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+
+        vec![(s.clone() * r, lookup)]
+      });
+
+      // b - 1 - r \in [0, 2^N)
+      meta.lookup("var mod range checks b-1-r", |meta| {
+        let s = meta.query_selector(selector);
+        let b = meta.query_advice(columns[columns.len() - 1], Rotation::cur());
+        let r = meta.query_advice(columns[offset + 2], Rotation::cur());
+        let one = Expression::Constant(F::ONE);
+
+        vec![(s.clone() * (b - one - r), lookup)]
+      });
+
+      // q - div_outp_min_val \in [0, 2^N)
+      meta.lookup("var mod range checks q", |meta| {
+        let s = meta.query_selector(selector);
+        let q = meta.query_advice(columns[offset + 1], Rotation::cur());
+
+        vec![(s.clone() * (q + q_shift.clone()), lookup)]
+      });
+    }
+    // b \in [0, 2^N)
+    meta.lookup("var mod range checks b", |meta| {
+      let s = meta.query_selector(selector);
+      let b = meta.query_advice(columns[columns.len() - 1], Rotation::cur());
+
+      vec![(s.clone() * b, lookup)]
+    });
+
+    let mut selectors = gadget_config.selectors;
+    selectors.insert(GadgetType::VarMod, vec![selector]);
+
+    GadgetConfig {
+      columns,
+      tables,
+      selectors,
+      ..gadget_config
+    }
+  }
+}
+
+impl<F: PrimeField> Gadget<F> for VarModChip<F> {
+  fn name(&self) -> String {
+    "VarModChip".to_string()
+  }
+
+  fn num_cols_per_op(&self) -> usize {
+    Self::num_cols_per_op()
+  }
+
+  fn num_inputs_per_row(&self) -> usize {
+    (self.config.columns.len() - 1) / self.num_cols_per_op()
+  }
+
+  fn num_outputs_per_row(&self) -> usize {
+    2 * self.num_inputs_per_row()
+  }
+
+  // Outputs the quotients followed by the remainders of the row
+  fn op_row_region(
+    &self,
+    region: &mut Region<F>,
+    row_offset: usize,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let a_vec = &vec_inputs[0];
+    let b = &single_inputs[1];
+
+    let div_inp_min_val_pos_i64 = -self.config.shift_min_val;
+
+    if self.config.use_selectors {
+      let selector = self.config.selectors.get(&GadgetType::VarMod).unwrap()[0];
+      selector.enable(region, row_offset)?;
+    }
+
+    b.copy_advice(
+      || "",
+      region,
+      self.config.columns[self.config.columns.len() - 1],
+      row_offset,
+    )?;
+
+    let mut quotients = vec![];
+    let mut remainders = vec![];
+    for (i, a) in a_vec.iter().enumerate() {
+      let offset = i * self.num_cols_per_op();
+      a.copy_advice(|| "", region, self.config.columns[offset], row_offset)?;
+
+      let div_mod = a.value().zip(b.value()).map(|(a, b)| {
+        let b = convert_to_u128(b);
+        // Shift a to be positive by a multiple of b
+        let div_inp_min_val_pos_i64 = div_inp_min_val_pos_i64 / (b as i64) * (b as i64);
+        let div_inp_min_val_pos = F::from(div_inp_min_val_pos_i64 as u64);
+
+        let a_pos = convert_to_u128(&(*a + div_inp_min_val_pos));
+        let q = (a_pos / b) as i64 - div_inp_min_val_pos_i64 / (b as i64);
+        let r = (a_pos % b) as u64;
+        (q, r)
+      });
+
+      let q_cell = region.assign_advice(
+        || "",
+        self.config.columns[offset + 1],
+        row_offset,
+        || {
+          div_mod.map(|(q, _)| {
+            if q < 0 {
+              -F::from(-q as u64)
+            } else {
+              F::from(q as u64)
+            }
+          })
+        },
+      )?;
+      let r_cell = region.assign_advice(
+        || "",
+        self.config.columns[offset + 2],
+        row_offset,
+        || div_mod.map(|(_, r)| F::from(r)),
+      )?;
+      quotients.push(q_cell);
+      remainders.push(r_cell);
+    }
+    quotients.extend(remainders);
+
+    Ok(quotients)
+  }
+
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    single_inputs: &Vec<&AssignedCell<F, F>>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let mut inps = vec_inputs[0].clone();
+    let initial_len = inps.len();
+
+    let default = &single_inputs[0];
+    while inps.len() % self.num_inputs_per_row() != 0 {
+      inps.push(&default);
+    }
+
+    let res = self.op_aligned_rows(layouter.namespace(|| "var_mod"), &vec![inps], single_inputs)?;
+
+    let num_per_row = self.num_inputs_per_row();
+    let mut quotients = vec![];
+    let mut remainders = vec![];
+    for row in res.chunks(2 * num_per_row) {
+      quotients.extend(row[..num_per_row].iter().cloned());
+      remainders.extend(row[num_per_row..].iter().cloned());
+    }
+    quotients.truncate(initial_len);
+    remainders.truncate(initial_len);
+    quotients.extend(remainders);
+
+    Ok(quotients)
+  }
+}
@@ -5,23 +5,57 @@ pub mod arithmetic;
 pub mod shape;
 
 // Concrete implementations
+pub mod abs;
+pub mod arg_max;
+pub mod assert_close;
 pub mod avg_pool_2d;
 pub mod batch_mat_mul;
+pub mod clip;
+pub mod conv1d;
 pub mod conv2d;
+pub mod conv3d;
+pub mod cos;
+pub mod cum_sum;
+pub mod custom_nonlinear;
 pub mod div_fixed;
+pub mod einsum;
+pub mod exp;
 pub mod fully_connected;
+pub mod gelu;
+pub mod gru;
+pub mod hardmax;
+pub mod l2_norm;
+pub mod log;
+pub mod log_softmax;
 pub mod logistic;
+pub mod lrn;
 pub mod max_pool_2d;
 pub mod mean;
+pub mod mish;
+pub mod multi_head_attention;
+pub mod non_max_suppression;
 pub mod noop;
+pub mod piecewise_linear;
 pub mod pow;
+pub mod reciprocal;
+pub mod reduce_max;
+pub mod reduce_sum;
+pub mod requantize;
+pub mod resize_bilinear;
+pub mod roi_align;
 pub mod rsqrt;
+pub mod scaled_dot_product_attention;
+pub mod select;
+pub mod sin;
 pub mod softmax;
+pub mod softplus;
 pub mod sqrt;
 pub mod square;
 pub mod squared_diff;
 pub mod tanh;
+pub mod top_k;
 pub mod update;
+pub mod upsample;
 
 // Special: dag
 pub mod dag;
@@ -0,0 +1,57 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  mul_pairs::MulPairsChip,
+  nonlinear::sign::SignChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// |x| = x * sign(x). The sign is not scaled, so the product doesn't need to be rescaled.
+#[derive(Clone, Debug)]
+pub struct AbsChip {}
+
+impl<F: PrimeField> Layer<F> for AbsChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let sign_chip = SignChip::<F>::construct(gadget_config.clone());
+    let sign = sign_chip.forward(
+      layouter.namespace(|| "abs sign"),
+      &vec![inp_vec.clone()],
+      &vec![zero],
+    )?;
+
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let vec_inps = vec![inp_vec, sign.iter().collect()];
+    let out = mul_pairs_chip.forward(layouter.namespace(|| "abs mul"), &vec_inps, &vec![zero])?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for AbsChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Sign,
+      GadgetType::MulPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,212 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, Axis, IxDyn};
+
+use crate::{
+  gadgets::{
+    adder::AdderChip,
+    dot_prod::DotProductChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    max::MaxChip,
+    mul_pairs::MulPairsChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+  utils::helpers::convert_pos_int,
+};
+
+use super::layer::{Layer, LayerConfig};
+
+// Outputs the (unscaled) index of the maximum along an axis. Ties go to the first index. The index
+// is <e, [0, 1, ..., n - 1]> for the one-hot vector e of the max (see one_hots).
+pub struct ArgMaxChip {}
+
+impl ArgMaxChip {
+  pub fn get_axis(&self, layer_config: &LayerConfig) -> usize {
+    let axis = layer_config.layer_params[0];
+    let ndim = layer_config.inp_shapes[0].len() as i64;
+    (if axis < 0 { axis + ndim } else { axis }) as usize
+  }
+
+  // For every slice, the prover supplies a one-hot vector e. The circuit checks that e is binary,
+  // sums to one, and that <e, x> is the max of x.
+  pub fn one_hots<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    slices: &Vec<Vec<&AssignedCell<F, F>>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<Vec<AssignedCell<F, F>>>, Error> {
+    let zero = constants.get(&0).unwrap().as_ref();
+    let one = constants.get(&1).unwrap().as_ref();
+
+    let one_hots = layouter.assign_region(
+      || "arg max one hot",
+      |mut region| {
+        let num_cols = gadget_config.columns.len();
+        let mut one_hots = vec![];
+        for (slice_idx, slice) in slices.iter().enumerate() {
+          let n = slice.len();
+          let vals = slice
+            .iter()
+            .map(|x| convert_pos_int(x.value().map(|x| x.to_owned())))
+            .collect::<Vec<_>>();
+          let mut max_idx = 0;
+          for (i, val) in vals.iter().enumerate() {
+            if *val > vals[max_idx] {
+              max_idx = i;
+            }
+          }
+
+          let mut one_hot = vec![];
+          for i in 0..n {
+            let assignment_idx = slice_idx * n + i;
+            let cell = region.assign_advice(
+              || "arg max one hot",
+              gadget_config.columns[assignment_idx % num_cols],
+              assignment_idx / num_cols,
+              || Value::known(if i == max_idx { F::ONE } else { F::ZERO }),
+            )?;
+            one_hot.push(cell);
+          }
+          one_hots.push(one_hot);
+        }
+        Ok(one_hots)
+      },
+    )?;
+
+    let adder_chip = AdderChip::<F>::construct(gadget_config.clone());
+    let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
+    let max_chip = MaxChip::<F>::construct(gadget_config.clone());
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+
+    // The one hot vectors are binary
+    let flat_one_hots = one_hots.iter().flatten().collect::<Vec<_>>();
+    let one_hots_sq = mul_pairs_chip.forward(
+      layouter.namespace(|| "arg max one hot sq"),
+      &vec![flat_one_hots.clone(), flat_one_hots.clone()],
+      &vec![zero],
+    )?;
+
+    let mut to_check: Vec<(AssignedCell<F, F>, AssignedCell<F, F>)> = vec![];
+    for (slice, one_hot) in slices.iter().zip(one_hots.iter()) {
+      let one_hot = one_hot.iter().collect::<Vec<_>>();
+
+      // The one hot vector sums to one
+      let sum = adder_chip.forward(
+        layouter.namespace(|| "arg max one hot sum"),
+        &vec![one_hot.clone()],
+        &vec![zero],
+      )?;
+      to_check.push((sum[0].clone(), one.clone()));
+
+      // The one hot vector selects the max
+      let max = max_chip.forward(
+        layouter.namespace(|| "arg max max"),
+        &vec![slice.clone()],
+        &vec![],
+      )?;
+      let selected = dot_prod_chip.forward(
+        layouter.namespace(|| "arg max selected"),
+        &vec![one_hot, slice.clone()],
+        &vec![zero],
+      )?;
+      to_check.push((selected[0].clone(), max[0].clone()));
+    }
+
+    layouter.assign_region(
+      || "arg max equality check",
+      |mut region| {
+        for (e, e_sq) in flat_one_hots.iter().zip(one_hots_sq.iter()) {
+          region.constrain_equal(e.cell(), e_sq.cell())?;
+        }
+        for (a, b) in to_check.iter() {
+          region.constrain_equal(a.cell(), b.cell())?;
+        }
+        Ok(())
+      },
+    )?;
+
+    Ok(one_hots)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for ArgMaxChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let axis = self.get_axis(layer_config);
+    let n = inp.shape()[axis];
+
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    // Each slice is moved to the last axis
+    let mut slices = vec![];
+    for lane in inp.lanes(Axis(axis)) {
+      slices.push(lane.iter().map(|x| x.as_ref()).collect::<Vec<_>>());
+    }
+
+    let indices = layouter.assign_region(
+      || "arg max indices",
+      |mut region| {
+        let mut indices = vec![];
+        for i in 0..n {
+          let cell = region.assign_fixed(
+            || format!("arg max index {}", i),
+            gadget_config.fixed_columns[0],
+            i,
+            || Value::known(F::from(i as u64)),
+          )?;
+          indices.push(cell);
+        }
+        Ok(indices)
+      },
+    )?;
+    let indices = indices.iter().collect::<Vec<_>>();
+
+    let one_hots = Self::one_hots(
+      layouter.namespace(|| "arg max one hots"),
+      &slices,
+      constants,
+      gadget_config.clone(),
+    )?;
+
+    let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
+    let mut outp = vec![];
+    for one_hot in one_hots.iter() {
+      let idx = dot_prod_chip.forward(
+        layouter.namespace(|| "arg max index"),
+        &vec![one_hot.iter().collect(), indices.clone()],
+        &vec![zero],
+      )?;
+      outp.push(Rc::new(idx[0].clone()));
+    }
+
+    let mut out_shape = inp.shape().to_vec();
+    out_shape.remove(axis);
+    let outp = Array::from_shape_vec(IxDyn(&out_shape), outp).unwrap();
+
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for ArgMaxChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::DotProduct,
+      GadgetType::Max,
+      GadgetType::MulPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
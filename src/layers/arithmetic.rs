@@ -11,7 +11,12 @@ use crate::{gadgets::gadget::GadgetConfig, utils::helpers::broadcast};
 use super::layer::{AssignedTensor, CellRc};
 
 pub mod add;
+pub mod compare;
+pub mod div;
 pub mod div_var;
+pub mod logical;
+pub mod max;
+pub mod min;
 pub mod mul;
 pub mod sub;
 
@@ -0,0 +1,223 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    add_pairs::AddPairsChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    greater_than::GreaterThanChip,
+    mul_pairs::MulPairsChip,
+    sub_pairs::SubPairsChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::{
+  super::layer::{Layer, LayerConfig},
+  Arithmetic,
+};
+
+// The gadgets compute 0 or 1, which is scaled to 0 or the scale factor so the output is a regular
+// fixed point tensor
+pub fn compare_forward<F: PrimeField, A: Arithmetic<F>>(
+  chip: &A,
+  mut layouter: impl Layouter<F>,
+  tensors: &Vec<AssignedTensor<F>>,
+  constants: &HashMap<i64, CellRc<F>>,
+  gadget_config: Rc<GadgetConfig>,
+) -> Result<Vec<AssignedTensor<F>>, Error> {
+  let (out, out_shape) = chip.arithmetic_forward(
+    layouter.namespace(|| ""),
+    tensors,
+    constants,
+    gadget_config.clone(),
+  )?;
+
+  let zero = constants.get(&0).unwrap().as_ref();
+  let sf = constants
+    .get(&(gadget_config.scale_factor as i64))
+    .unwrap()
+    .as_ref();
+  let sf_vec = vec![sf; out.len()];
+  let out_vec = out.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+
+  let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config);
+  let out = mul_pairs_chip.forward(
+    layouter.namespace(|| "compare scale"),
+    &vec![out_vec, sf_vec],
+    &vec![zero],
+  )?;
+  let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+  let out = Array::from_shape_vec(IxDyn(out_shape.as_slice()), out).unwrap();
+
+  Ok(vec![out])
+}
+
+#[derive(Clone, Debug)]
+pub struct GreaterChip {}
+
+impl<F: PrimeField> Arithmetic<F> for GreaterChip {
+  fn gadget_forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    constants: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let greater_than_chip = GreaterThanChip::<F>::construct(gadget_config);
+    let out = greater_than_chip.forward(layouter.namespace(|| "greater"), vec_inputs, constants)?;
+    Ok(out)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for GreaterChip {
+  fn forward(
+    &self,
+    layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    compare_forward(self, layouter, tensors, constants, gadget_config)
+  }
+}
+
+impl GadgetConsumer for GreaterChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::GreaterThan,
+      GadgetType::MulPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct LessChip {}
+
+impl<F: PrimeField> Arithmetic<F> for LessChip {
+  fn gadget_forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    constants: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let greater_than_chip = GreaterThanChip::<F>::construct(gadget_config);
+    let vec_inputs = vec![vec_inputs[1].clone(), vec_inputs[0].clone()];
+    let out = greater_than_chip.forward(layouter.namespace(|| "less"), &vec_inputs, constants)?;
+    Ok(out)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for LessChip {
+  fn forward(
+    &self,
+    layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    compare_forward(self, layouter, tensors, constants, gadget_config)
+  }
+}
+
+impl GadgetConsumer for LessChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::GreaterThan,
+      GadgetType::MulPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
+
+// a == b iff neither a > b nor b > a. The gadgets compute (a > b) + (b > a), which is then
+// subtracted from the scale factor.
+#[derive(Clone, Debug)]
+pub struct EqualChip {}
+
+impl<F: PrimeField> Arithmetic<F> for EqualChip {
+  fn gadget_forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    constants: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let greater_than_chip = GreaterThanChip::<F>::construct(gadget_config.clone());
+    let gt = greater_than_chip.forward(layouter.namespace(|| "equal gt"), vec_inputs, constants)?;
+    let lt = greater_than_chip.forward(
+      layouter.namespace(|| "equal lt"),
+      &vec![vec_inputs[1].clone(), vec_inputs[0].clone()],
+      constants,
+    )?;
+
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config);
+    let out = add_pairs_chip.forward(
+      layouter.namespace(|| "equal neq"),
+      &vec![gt.iter().collect(), lt.iter().collect()],
+      constants,
+    )?;
+    Ok(out)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for EqualChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let neq = compare_forward(
+      self,
+      layouter.namespace(|| ""),
+      tensors,
+      constants,
+      gadget_config.clone(),
+    )?;
+    let neq = &neq[0];
+
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+    let sf_vec = vec![sf; neq.len()];
+    let neq_vec = neq.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config);
+    let out = sub_pairs_chip.forward(
+      layouter.namespace(|| "equal sub"),
+      &vec![sf_vec, neq_vec],
+      &vec![zero],
+    )?;
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(neq.shape()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for EqualChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::GreaterThan,
+      GadgetType::AddPairs,
+      GadgetType::MulPairs,
+      GadgetType::SubPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
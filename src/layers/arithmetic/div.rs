@@ -0,0 +1,112 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    mul_pairs::MulPairsChip,
+    var_div::VarDivRoundChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+  utils::helpers::broadcast,
+};
+
+use super::Arithmetic;
+
+// Elementwise a / b with broadcasting. The divisors must be positive; the numerators may be
+// negative (VarDivRoundChip shifts them before dividing).
+pub struct DivChip {}
+
+impl<F: PrimeField> Arithmetic<F> for DivChip {
+  fn gadget_forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    constants: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+
+    let out = mul_pairs_chip.forward(
+      layouter.namespace(|| "div mul pairs chip"),
+      &vec_inputs,
+      constants,
+    )?;
+    Ok(out)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for DivChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    assert_eq!(tensors.len(), 2);
+    let (inp, div) = broadcast(&tensors[0], &tensors[1]);
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .clone();
+
+    // out = inp * SF
+    let sf_tensor = Array::from_shape_vec(IxDyn(&[1]), vec![sf]).unwrap();
+    let (scaled, out_shape) = self.arithmetic_forward(
+      layouter.namespace(|| ""),
+      &vec![inp, sf_tensor],
+      constants,
+      gadget_config.clone(),
+    )?;
+
+    // The divisor is a single input of the gadget, so group the elements that share a divisor.
+    // Broadcast divisors share the same cell. Groups are in order of first use so that the layout
+    // is deterministic.
+    let mut group_idxes: HashMap<*const AssignedCell<F, F>, usize> = HashMap::new();
+    let mut groups: Vec<(&AssignedCell<F, F>, Vec<usize>)> = vec![];
+    for (i, d) in div.iter().enumerate() {
+      let group_idx = *group_idxes.entry(Rc::as_ptr(d)).or_insert_with(|| {
+        groups.push((d.as_ref(), vec![]));
+        groups.len() - 1
+      });
+      groups[group_idx].1.push(i);
+    }
+
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let mut out = vec![None; scaled.len()];
+    for (d, idxes) in groups.iter() {
+      let group = idxes.iter().map(|i| scaled[*i].as_ref()).collect::<Vec<_>>();
+      let group_out = var_div_chip.forward(
+        layouter.namespace(|| "div var div"),
+        &vec![group],
+        &vec![zero, *d],
+      )?;
+      for (i, x) in idxes.iter().zip(group_out.into_iter()) {
+        out[*i] = Some(Rc::new(x));
+      }
+    }
+
+    let out = out.into_iter().map(|x| x.unwrap()).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(out_shape.as_slice()), out).unwrap();
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for DivChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::MulPairs,
+      GadgetType::VarDivRound,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,226 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    add_pairs::AddPairsChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    greater_than::GreaterThanChip,
+    mul_pairs::MulPairsChip,
+    sub_pairs::SubPairsChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::{
+  super::layer::{Layer, LayerConfig},
+  compare::compare_forward,
+  Arithmetic,
+};
+
+// Inputs are true if positive, so both 0/1 and 0/scale factor masks work. Converting them with the
+// greater than gadget also constrains them to be boolean. Outputs are 0 or the scale factor.
+fn to_bool<F: PrimeField>(
+  mut layouter: impl Layouter<F>,
+  inp: &Vec<&AssignedCell<F, F>>,
+  zero: &AssignedCell<F, F>,
+  gadget_config: Rc<GadgetConfig>,
+) -> Result<Vec<AssignedCell<F, F>>, Error> {
+  let greater_than_chip = GreaterThanChip::<F>::construct(gadget_config);
+  let zeros = vec![zero; inp.len()];
+  greater_than_chip.forward(
+    layouter.namespace(|| "to bool"),
+    &vec![inp.clone(), zeros],
+    &vec![zero],
+  )
+}
+
+#[derive(Clone, Debug)]
+pub struct LogicalAndChip {}
+
+impl<F: PrimeField> Arithmetic<F> for LogicalAndChip {
+  fn gadget_forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    constants: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let zero = constants[0];
+    let a = to_bool(
+      layouter.namespace(|| "and a"),
+      &vec_inputs[0],
+      zero,
+      gadget_config.clone(),
+    )?;
+    let b = to_bool(
+      layouter.namespace(|| "and b"),
+      &vec_inputs[1],
+      zero,
+      gadget_config.clone(),
+    )?;
+
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config);
+    let out = mul_pairs_chip.forward(
+      layouter.namespace(|| "and mul"),
+      &vec![a.iter().collect(), b.iter().collect()],
+      constants,
+    )?;
+    Ok(out)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for LogicalAndChip {
+  fn forward(
+    &self,
+    layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    compare_forward(self, layouter, tensors, constants, gadget_config)
+  }
+}
+
+impl GadgetConsumer for LogicalAndChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::GreaterThan,
+      GadgetType::MulPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
+
+// a or b = (a + b) > 0
+#[derive(Clone, Debug)]
+pub struct LogicalOrChip {}
+
+impl<F: PrimeField> Arithmetic<F> for LogicalOrChip {
+  fn gadget_forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    constants: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let zero = constants[0];
+    let a = to_bool(
+      layouter.namespace(|| "or a"),
+      &vec_inputs[0],
+      zero,
+      gadget_config.clone(),
+    )?;
+    let b = to_bool(
+      layouter.namespace(|| "or b"),
+      &vec_inputs[1],
+      zero,
+      gadget_config.clone(),
+    )?;
+
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config.clone());
+    let sum = add_pairs_chip.forward(
+      layouter.namespace(|| "or add"),
+      &vec![a.iter().collect(), b.iter().collect()],
+      constants,
+    )?;
+    to_bool(
+      layouter.namespace(|| "or"),
+      &sum.iter().collect(),
+      zero,
+      gadget_config,
+    )
+  }
+}
+
+impl<F: PrimeField> Layer<F> for LogicalOrChip {
+  fn forward(
+    &self,
+    layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    compare_forward(self, layouter, tensors, constants, gadget_config)
+  }
+}
+
+impl GadgetConsumer for LogicalOrChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::GreaterThan,
+      GadgetType::AddPairs,
+      GadgetType::MulPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
+
+// not a = sf - sf * (a > 0)
+#[derive(Clone, Debug)]
+pub struct LogicalNotChip {}
+
+impl<F: PrimeField> Layer<F> for LogicalNotChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+    let sf_vec = vec![sf; inp_vec.len()];
+
+    let inp_bool = to_bool(
+      layouter.namespace(|| "not inp"),
+      &inp_vec,
+      zero,
+      gadget_config.clone(),
+    )?;
+
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let inp_scaled = mul_pairs_chip.forward(
+      layouter.namespace(|| "not scale"),
+      &vec![inp_bool.iter().collect(), sf_vec.clone()],
+      &vec![zero],
+    )?;
+
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config);
+    let out = sub_pairs_chip.forward(
+      layouter.namespace(|| "not sub"),
+      &vec![sf_vec, inp_scaled.iter().collect()],
+      &vec![zero],
+    )?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for LogicalNotChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::GreaterThan,
+      GadgetType::MulPairs,
+      GadgetType::SubPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,69 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    gadget::{GadgetConfig, GadgetType},
+    max::MaxChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::{
+  super::layer::{Layer, LayerConfig},
+  Arithmetic,
+};
+
+#[derive(Clone, Debug)]
+pub struct MaximumChip {}
+
+impl<F: PrimeField> Arithmetic<F> for MaximumChip {
+  fn gadget_forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    _constants: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let max_chip = MaxChip::<F>::construct(gadget_config);
+    let out = max_chip.pairwise_max(
+      layouter.namespace(|| "max chip"),
+      &vec_inputs[0],
+      &vec_inputs[1],
+    )?;
+    Ok(out)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for MaximumChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let (out, out_shape) = self.arithmetic_forward(
+      layouter.namespace(|| ""),
+      tensors,
+      constants,
+      gadget_config.clone(),
+    )?;
+    let out = Array::from_shape_vec(IxDyn(out_shape.as_slice()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for MaximumChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![GadgetType::Max, GadgetType::InputLookup]
+  }
+}
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    add_pairs::AddPairsChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    max::MaxChip,
+    sub_pairs::SubPairsChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::{
+  super::layer::{Layer, LayerConfig},
+  Arithmetic,
+};
+
+#[derive(Clone, Debug)]
+pub struct MinChip {}
+
+impl<F: PrimeField> Arithmetic<F> for MinChip {
+  fn gadget_forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    vec_inputs: &Vec<Vec<&AssignedCell<F, F>>>,
+    constants: &Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let inp1 = &vec_inputs[0];
+    let inp2 = &vec_inputs[1];
+
+    // min(a, b) = a + b - max(a, b)
+    let max_chip = MaxChip::<F>::construct(gadget_config.clone());
+    let max = max_chip.pairwise_max(layouter.namespace(|| "min chip max"), inp1, inp2)?;
+    let max = max.iter().collect::<Vec<_>>();
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config.clone());
+    let diff = sub_pairs_chip.forward(
+      layouter.namespace(|| "min chip sub"),
+      &vec![inp1.clone(), max],
+      constants,
+    )?;
+    let diff = diff.iter().collect::<Vec<_>>();
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config);
+    let out = add_pairs_chip.forward(
+      layouter.namespace(|| "min chip add"),
+      &vec![diff, inp2.clone()],
+      constants,
+    )?;
+    Ok(out)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for MinChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let (out, out_shape) = self.arithmetic_forward(
+      layouter.namespace(|| ""),
+      tensors,
+      constants,
+      gadget_config.clone(),
+    )?;
+    let out = Array::from_shape_vec(IxDyn(out_shape.as_slice()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for MinChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Max,
+      GadgetType::AddPairs,
+      GadgetType::SubPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
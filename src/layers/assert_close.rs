@@ -0,0 +1,100 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    add_pairs::AddPairsChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    nonlinear::relu::ReluChip,
+    sub_pairs::SubPairsChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+  utils::helpers::broadcast,
+};
+
+use super::layer::{Layer, LayerConfig};
+
+// Checks that |a - b| <= eps elementwise. Used to prove that two models (e.g., a reference
+// model and a pruned / distilled model) run on the same input agree up to eps.
+// Inputs: a, b, eps (broadcastable to a). Output: a - b
+pub struct AssertCloseChip {}
+
+impl<F: PrimeField> Layer<F> for AssertCloseChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    assert_eq!(tensors.len(), 3);
+    assert_eq!(tensors[0].shape(), tensors[1].shape());
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let inp1 = tensors[0].iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let inp2 = tensors[1].iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config.clone());
+    let diff = sub_pairs_chip.forward(
+      layouter.namespace(|| "assert close diff"),
+      &vec![inp1, inp2],
+      &vec![zero],
+    )?;
+    let diff = Array::from_shape_vec(
+      IxDyn(tensors[0].shape()),
+      diff.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>(),
+    )
+    .unwrap();
+
+    let (diff, eps) = broadcast(&diff, &tensors[2]);
+    let diff_vec = diff.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let eps_vec = eps.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+
+    // eps - (a - b) >= 0 and eps + (a - b) >= 0
+    let lower = sub_pairs_chip.forward(
+      layouter.namespace(|| "assert close lower"),
+      &vec![eps_vec.clone(), diff_vec.clone()],
+      &vec![zero],
+    )?;
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config.clone());
+    let upper = add_pairs_chip.forward(
+      layouter.namespace(|| "assert close upper"),
+      &vec![eps_vec, diff_vec],
+      &vec![zero],
+    )?;
+
+    // x >= 0 iff relu(x) == x
+    let bounds = lower.iter().chain(upper.iter()).collect::<Vec<_>>();
+    let relu_chip = ReluChip::<F>::construct(gadget_config.clone());
+    let relu_bounds = relu_chip.forward(
+      layouter.namespace(|| "assert close relu"),
+      &vec![bounds.clone()],
+      &vec![zero],
+    )?;
+
+    layouter.assign_region(
+      || "assert close equality check",
+      |mut region| {
+        for (bound, relu_bound) in bounds.iter().zip(relu_bounds.iter()) {
+          region.constrain_equal(bound.cell(), relu_bound.cell())?;
+        }
+        Ok(())
+      },
+    )?;
+
+    Ok(vec![diff])
+  }
+}
+
+impl GadgetConsumer for AssertCloseChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::AddPairs,
+      GadgetType::SubPairs,
+      GadgetType::Relu,
+      GadgetType::InputLookup,
+    ]
+  }
+}
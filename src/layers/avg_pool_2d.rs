@@ -1,86 +1,191 @@
-use std::{collections::HashMap, rc::Rc};
+use std::{
+  collections::{BTreeMap, HashMap},
+  rc::Rc,
+};
 
 use halo2_proofs::{
-  circuit::{AssignedCell, Layouter, Value},
+  circuit::{AssignedCell, Layouter},
   halo2curves::ff::PrimeField,
   plonk::Error,
 };
 use ndarray::{Array, IxDyn};
 
 use crate::{
-  gadgets::gadget::{GadgetConfig, GadgetType},
-  layers::max_pool_2d::MaxPool2DChip,
+  gadgets::{
+    adder::AdderChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    var_div::VarDivRoundChip,
+  },
+  layers::conv2d::Conv2DChip,
 };
 
 use super::{
-  averager::Averager,
   layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+  resize_bilinear::ResizeBilinearChip,
 };
 
+// NHWC, params: [filter h, filter w, stride h, stride w, padding, count_include_pad]. The padding
+// is 0 for SAME, 1 for VALID (the default) or 2 for explicit, followed by [top, bottom, left,
+// right]. The padded cells are zero, and count_include_pad (default 0, as in TFLite) decides
+// whether they count towards the divisor, which can vary per output cell at the borders.
 pub struct AvgPool2DChip {}
 
-impl<F: PrimeField> Averager<F> for AvgPool2DChip {
-  fn splat(&self, input: &AssignedTensor<F>, layer_config: &LayerConfig) -> Vec<Vec<CellRc<F>>> {
-    assert_eq!(input.shape().len(), 4);
-    // Don't support batch size > 1 yet
-    assert_eq!(input.shape()[0], 1);
+pub struct AvgPool2DConfig {
+  pub filter: (usize, usize),
+  pub stride: (usize, usize),
+  pub padding: ((usize, usize), (usize, usize)),
+  pub count_include_pad: bool,
+}
 
-    // TODO: refactor this
-    MaxPool2DChip::splat(input, layer_config).unwrap()
+impl AvgPool2DChip {
+  pub fn param_vec_to_config<F: PrimeField>(
+    layer_params: &Vec<i64>,
+    h: usize,
+    w: usize,
+  ) -> AvgPool2DConfig {
+    let filter = (layer_params[0] as usize, layer_params[1] as usize);
+    let stride = (layer_params[2] as usize, layer_params[3] as usize);
+    let padding = match layer_params.get(4).cloned().unwrap_or(1) {
+      0 => Conv2DChip::<F>::get_padding(h, w, stride.0, stride.1, filter.0, filter.1),
+      1 => ((0, 0), (0, 0)),
+      2 => {
+        let p = layer_params[6..10]
+          .iter()
+          .map(|x| *x as usize)
+          .collect::<Vec<_>>();
+        ((p[0], p[1]), (p[2], p[3]))
+      }
+      _ => panic!("Invalid padding"),
+    };
+    let count_include_pad = layer_params.get(5).cloned().unwrap_or(0) == 1;
+    AvgPool2DConfig {
+      filter,
+      stride,
+      padding,
+      count_include_pad,
+    }
   }
 
-  fn get_div_val(
-    &self,
-    mut layouter: impl Layouter<F>,
-    _tensors: &Vec<AssignedTensor<F>>,
-    gadget_config: Rc<GadgetConfig>,
-    layer_config: &LayerConfig,
-  ) -> Result<AssignedCell<F, F>, Error> {
-    // FIXME: this needs to be revealed
-    let div = layer_config.layer_params[0] * layer_config.layer_params[1];
-    let div = F::from(div as u64);
-    let div = layouter
-      .assign_region(
-        || "avg pool 2d div",
-        |mut region| {
-          let div = region
-            .assign_advice(
-              || "avg pool 2d div",
-              gadget_config.columns[0],
-              0,
-              || Value::known(div),
-            )
-            .unwrap();
-          Ok(div)
-        },
-      )
-      .unwrap();
-
-    Ok(div)
+  // The input positions and the divisor of every output position along an axis
+  fn windows(
+    size: usize,
+    filter: usize,
+    stride: usize,
+    pad: (usize, usize),
+    count_include_pad: bool,
+  ) -> Vec<(Vec<usize>, usize)> {
+    let out_size = (size + pad.0 + pad.1 - filter) / stride + 1;
+    (0..out_size)
+      .map(|o| {
+        let start = (o * stride) as i64 - pad.0 as i64;
+        let end = start + filter as i64;
+        let idxes = (start.max(0)..end.min(size as i64))
+          .map(|x| x as usize)
+          .collect::<Vec<_>>();
+        let count = if count_include_pad {
+          (end.min((size + pad.1) as i64) - start) as usize
+        } else {
+          idxes.len()
+        };
+        (idxes, count)
+      })
+      .collect()
   }
 }
 
 impl<F: PrimeField> Layer<F> for AvgPool2DChip {
   fn forward(
     &self,
-    layouter: impl Layouter<F>,
+    mut layouter: impl Layouter<F>,
     tensors: &Vec<AssignedTensor<F>>,
     constants: &HashMap<i64, CellRc<F>>,
     gadget_config: Rc<GadgetConfig>,
     layer_config: &LayerConfig,
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
-    let dived = self
-      .avg_forward(layouter, tensors, constants, gadget_config, layer_config)
-      .unwrap();
-
     let inp = &tensors[0];
-    // TODO: refactor this
-    let out_xy = MaxPool2DChip::shape(inp, layer_config);
-    let out_shape = vec![1, out_xy.0, out_xy.1, inp.shape()[3]];
-    println!("out_shape: {:?}", out_shape);
+    assert_eq!(inp.ndim(), 4);
+    let (batch, h, w, c) = (
+      inp.shape()[0],
+      inp.shape()[1],
+      inp.shape()[2],
+      inp.shape()[3],
+    );
+    let config = Self::param_vec_to_config::<F>(&layer_config.layer_params, h, w);
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let (ph, pw) = config.padding;
+    let windows_h = Self::windows(
+      h,
+      config.filter.0,
+      config.stride.0,
+      ph,
+      config.count_include_pad,
+    );
+    let windows_w = Self::windows(
+      w,
+      config.filter.1,
+      config.stride.1,
+      pw,
+      config.count_include_pad,
+    );
+
+    // Sum the windows, skipping the padded zeros
+    let adder_chip = AdderChip::<F>::construct(gadget_config.clone());
+    let mut sums = vec![];
+    let mut divs = vec![];
+    for b in 0..batch {
+      for (xs, count_h) in windows_h.iter() {
+        for (ys, count_w) in windows_w.iter() {
+          for k in 0..c {
+            let mut window = vec![zero];
+            for x in xs.iter() {
+              for y in ys.iter() {
+                window.push(inp[[b, *x, *y, k]].as_ref());
+              }
+            }
+            let sum = adder_chip.forward(
+              layouter.namespace(|| "avg pool 2d sum"),
+              &vec![window],
+              &vec![zero],
+            )?;
+            sums.push(sum[0].clone());
+            divs.push((count_h * count_w) as i64);
+          }
+        }
+      }
+    }
+
+    // Divide the sums, grouped by their divisor
+    let div_cells = ResizeBilinearChip::assign_weights(
+      layouter.namespace(|| "avg pool 2d divisors"),
+      divs.clone(),
+      gadget_config.clone(),
+    )?;
+    let mut groups: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (i, div) in divs.iter().enumerate() {
+      groups.entry(*div).or_default().push(i);
+    }
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let mut outp: Vec<Option<AssignedCell<F, F>>> = vec![None; sums.len()];
+    for (div, idxes) in groups.iter() {
+      let group = idxes.iter().map(|i| &sums[*i]).collect::<Vec<_>>();
+      let dived = var_div_chip.forward(
+        layouter.namespace(|| "avg pool 2d div"),
+        &vec![group],
+        &vec![zero, div_cells.get(div).unwrap()],
+      )?;
+      for (i, cell) in idxes.iter().zip(dived.into_iter()) {
+        outp[*i] = Some(cell);
+      }
+    }
 
-    let out = Array::from_shape_vec(IxDyn(&out_shape), dived).unwrap();
-    Ok(vec![out])
+    let outp = outp
+      .into_iter()
+      .map(|x| Rc::new(x.unwrap()))
+      .collect::<Vec<_>>();
+    let out_shape = vec![batch, windows_h.len(), windows_w.len(), c];
+    let outp = Array::from_shape_vec(IxDyn(&out_shape), outp).unwrap();
+    Ok(vec![outp])
   }
 }
 
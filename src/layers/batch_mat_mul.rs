@@ -6,6 +6,7 @@ use ndarray::{Array, Axis, IxDyn};
 use crate::{
   gadgets::gadget::{GadgetConfig, GadgetType},
   layers::fully_connected::FullyConnectedConfig,
+  utils::helpers::broadcast_shape,
 };
 
 use super::{
@@ -13,6 +14,8 @@ use super::{
   layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
 };
 
+// Params: [adj_x, adj_y]. The inputs are (..., m, k) and (..., k, n), possibly adjointed, and the
+// leading batch axes are broadcast.
 pub struct BatchMatMulChip {}
 
 impl<F: PrimeField> Layer<F> for BatchMatMulChip {
@@ -24,27 +27,46 @@ impl<F: PrimeField> Layer<F> for BatchMatMulChip {
     gadget_config: Rc<GadgetConfig>,
     layer_config: &LayerConfig,
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
-    let inp1 = &tensors[0];
-    let inp2 = &tensors[1];
-    println!("inp1: {:?}", inp1.shape());
-    println!("inp2: {:?}", inp2.shape());
+    let adj_x = layer_config.layer_params[0] == 1;
+    let adj_y = layer_config.layer_params[1] == 1;
 
-    assert_eq!(inp1.ndim(), 3);
-    assert_eq!(inp2.ndim(), 3);
-    assert_eq!(inp1.shape()[0], inp2.shape()[0]);
+    // The batch axes are broadcast against each other and flattened
+    let (inp1, inp2) = (&tensors[0], &tensors[1]);
+    assert!(inp1.ndim() >= 2);
+    assert!(inp2.ndim() >= 2);
+    let mat1 = &inp1.shape()[inp1.ndim() - 2..];
+    let mat2 = &inp2.shape()[inp2.ndim() - 2..];
+    let batch_shape = broadcast_shape(
+      &inp1.shape()[..inp1.ndim() - 2],
+      &inp2.shape()[..inp2.ndim() - 2],
+    );
+    let batch = batch_shape.iter().product::<usize>();
+    let expand = |inp: &AssignedTensor<F>, mat: &[usize]| {
+      let shape = batch_shape
+        .iter()
+        .chain(mat.iter())
+        .cloned()
+        .collect::<Vec<_>>();
+      let inp = inp.broadcast(IxDyn(&shape)).unwrap().to_owned();
+      inp.into_shape(IxDyn(&[batch, mat[0], mat[1]])).unwrap()
+    };
+    let inp1 = expand(inp1, mat1);
+    let inp2 = expand(inp2, mat2);
 
-    let adj_y = layer_config.layer_params[1] == 1;
-    if adj_y {
-      assert_eq!(inp1.shape()[2], inp2.shape()[2]);
+    let (m, k) = if adj_x {
+      (mat1[1], mat1[0])
     } else {
-      assert_eq!(inp1.shape()[2], inp2.shape()[1]);
-    }
-
-    let out_shape = if adj_y {
-      vec![inp1.shape()[0], inp1.shape()[1], inp2.shape()[1]]
+      (mat1[0], mat1[1])
+    };
+    let n = if adj_y {
+      assert_eq!(k, mat2[1]);
+      mat2[0]
     } else {
-      vec![inp1.shape()[0], inp1.shape()[1], inp2.shape()[2]]
+      assert_eq!(k, mat2[0]);
+      mat2[1]
     };
+    let mut out_shape = batch_shape.clone();
+    out_shape.extend([m, n]);
 
     let fc_chip = FullyConnectedChip::<F> {
       _marker: PhantomData,
@@ -52,20 +74,20 @@ impl<F: PrimeField> Layer<F> for BatchMatMulChip {
     };
 
     let mut outp: Vec<CellRc<F>> = vec![];
-    for i in 0..inp1.shape()[0] {
-      let inp1_slice = inp1.index_axis(Axis(0), i).to_owned();
-      // Due to tensorflow BS, transpose the "weights"
-      let inp2_slice = if adj_y {
-        inp2.index_axis(Axis(0), i).to_owned()
+    for i in 0..batch {
+      // The transposes only reorder the cells
+      let inp1_slice = if adj_x {
+        inp1.index_axis(Axis(0), i).t().to_owned()
       } else {
-        inp2.index_axis(Axis(0), i).t().to_owned()
+        inp1.index_axis(Axis(0), i).to_owned()
       };
-      println!("inp1_slice: {:?}", inp1_slice.shape());
-      println!("inp2_slice: {:?}", inp2_slice.shape());
+      // The fully connected layer takes the "weights" as [n, k] with transpose_b, or [k, n]
+      // without it
+      let inp2_slice = inp2.index_axis(Axis(0), i).to_owned();
       // Batch MM doesn't have a fused activation, so insert it here
       // TODO: consider putting this in the converter?
       let tmp_config = LayerConfig {
-        layer_params: vec![0],
+        layer_params: vec![0, adj_y as i64],
         ..layer_config.clone()
       };
       let outp_slice = fc_chip.forward(
@@ -88,6 +110,7 @@ impl GadgetConsumer for BatchMatMulChip {
     vec![
       GadgetType::Adder,
       GadgetType::DotProduct,
+      GadgetType::Shift,
       GadgetType::VarDivRound,
       GadgetType::InputLookup,
     ]
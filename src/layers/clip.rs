@@ -0,0 +1,103 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    add_pairs::AddPairsChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    max::MaxChip,
+    sub_pairs::SubPairsChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::layer::{Layer, LayerConfig};
+
+// Params: [min, max], already scaled by the scale factor
+pub struct ClipChip {}
+
+impl<F: PrimeField> Layer<F> for ClipChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let min_val = layer_config.layer_params[0];
+    let max_val = layer_config.layer_params[1];
+    assert!(min_val <= max_val);
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let to_field = |x: i64| {
+      let bias = 1 << 60;
+      F::from((x + bias) as u64) - F::from(bias as u64)
+    };
+    let bounds = layouter.assign_region(
+      || "clip bounds",
+      |mut region| {
+        let mut bounds = vec![];
+        for (i, val) in [min_val, max_val].iter().enumerate() {
+          let cell = region.assign_fixed(
+            || format!("clip bound {}", i),
+            gadget_config.fixed_columns[0],
+            i,
+            || Value::known(to_field(*val)),
+          )?;
+          bounds.push(cell);
+        }
+        Ok(bounds)
+      },
+    )?;
+
+    let inp_flat = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let lower = vec![&bounds[0]; inp_flat.len()];
+    let upper = vec![&bounds[1]; inp_flat.len()];
+
+    // min(y, max) = y + max - max(y, max)
+    let max_chip = MaxChip::<F>::construct(gadget_config.clone());
+    let clipped_below =
+      max_chip.pairwise_max(layouter.namespace(|| "clip below"), &inp_flat, &lower)?;
+    let clipped_below = clipped_below.iter().collect::<Vec<_>>();
+    let upper_max =
+      max_chip.pairwise_max(layouter.namespace(|| "clip above"), &clipped_below, &upper)?;
+    let upper_max = upper_max.iter().collect::<Vec<_>>();
+
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config.clone());
+    let diff = sub_pairs_chip.forward(
+      layouter.namespace(|| "clip sub"),
+      &vec![clipped_below, upper_max],
+      &vec![zero],
+    )?;
+    let diff = diff.iter().collect::<Vec<_>>();
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config.clone());
+    let outp = add_pairs_chip.forward(
+      layouter.namespace(|| "clip add"),
+      &vec![diff, upper],
+      &vec![zero],
+    )?;
+
+    let outp = outp.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let outp = Array::from_shape_vec(IxDyn(inp.shape()), outp).unwrap();
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for ClipChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Max,
+      GadgetType::AddPairs,
+      GadgetType::SubPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,233 @@
+use std::{collections::HashMap, marker::PhantomData, rc::Rc};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    bias_div_round_relu6::BiasDivRoundRelu6Chip,
+    dot_prod::DotProductChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    nonlinear::relu::ReluChip,
+  },
+  layers::{conv2d::PaddingEnum, shape::pad::pad},
+};
+
+use super::layer::{ActivationType, AssignedTensor, GadgetConsumer, Layer, LayerConfig};
+
+pub struct Conv1DConfig {
+  pub padding: PaddingEnum,
+  pub activation: ActivationType,
+  pub stride: usize,
+}
+
+pub struct Conv1DChip<F: PrimeField> {
+  pub config: LayerConfig,
+  pub _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> Conv1DChip<F> {
+  pub fn param_vec_to_config(layer_params: Vec<i64>) -> Conv1DConfig {
+    let padding = match layer_params[0] {
+      0 => PaddingEnum::Same,
+      1 => PaddingEnum::Valid,
+      _ => panic!("Invalid padding"),
+    };
+    let activation = match layer_params[1] {
+      0 => ActivationType::None,
+      1 => ActivationType::Relu,
+      3 => ActivationType::Relu6,
+      _ => panic!("Invalid activation type"),
+    };
+    let stride = layer_params[2] as usize;
+    Conv1DConfig {
+      padding,
+      activation,
+      stride,
+    }
+  }
+
+  pub fn out_len(l: usize, s: usize, k: usize, padding: PaddingEnum) -> usize {
+    match padding {
+      PaddingEnum::Same => (l + s - 1) / s,
+      PaddingEnum::Valid => (l - k) / s + 1,
+    }
+  }
+
+  pub fn get_padding(l: usize, s: usize, k: usize) -> (usize, usize) {
+    let ol = Self::out_len(l, s, k, PaddingEnum::Same);
+    let p = ((ol as i64 - 1) * s as i64 + k as i64 - l as i64).max(0) as usize;
+    (p / 2, p - p / 2)
+  }
+
+  // Input: (B, L, C), weights: (O, K, C), biases: (O)
+  // Returns one (input window, weights, bias) triple per output cell, in (B, L, O) order
+  pub fn splat<G: Clone>(
+    &self,
+    tensors: &Vec<Array<Rc<G>, IxDyn>>,
+    zero: Rc<G>,
+  ) -> (Vec<Vec<Rc<G>>>, Vec<Vec<Rc<G>>>, Vec<Rc<G>>) {
+    assert!(tensors.len() <= 3);
+
+    let conv_config = &Self::param_vec_to_config(self.config.layer_params.clone());
+
+    let inp = &tensors[0];
+    let weights = &tensors[1];
+
+    // B, L, C
+    assert_eq!(inp.shape().len(), 3);
+    assert_eq!(weights.shape().len(), 3);
+    assert_eq!(inp.shape()[2], weights.shape()[2]);
+
+    let l = inp.shape()[1];
+    let k = weights.shape()[1];
+    let s = conv_config.stride;
+
+    let pl = if conv_config.padding == PaddingEnum::Same {
+      Self::get_padding(l, s, k)
+    } else {
+      (0, 0)
+    };
+    let padding = vec![[0, 0], [pl.0, pl.1], [0, 0]];
+    let inp_pad = pad(&inp, padding, &zero);
+
+    let ol = Self::out_len(l, s, k, conv_config.padding);
+
+    let mut inp_cells = vec![];
+    let mut weights_cells = vec![];
+    let mut biases_cells = vec![];
+    for batch in 0..inp.shape()[0] {
+      for i in 0..ol {
+        for chan_out in 0..weights.shape()[0] {
+          let mut inp_row = vec![];
+          let mut weight_row = vec![];
+          for ck in 0..k {
+            for ci in 0..weights.shape()[2] {
+              inp_row.push(inp_pad[[batch, i * s + ck, ci]].clone());
+              weight_row.push(weights[[chan_out, ck, ci]].clone());
+            }
+          }
+          inp_cells.push(inp_row);
+          weights_cells.push(weight_row);
+          if tensors.len() == 3 {
+            biases_cells.push(tensors[2][chan_out].clone());
+          } else {
+            biases_cells.push(zero.clone());
+          }
+        }
+      }
+    }
+
+    (inp_cells, weights_cells, biases_cells)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for Conv1DChip<F> {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, Rc<AssignedCell<F, F>>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let conv_config = &Self::param_vec_to_config(self.config.layer_params.clone());
+    let zero = constants.get(&0).unwrap();
+
+    let inp = &tensors[0];
+    let weights = &tensors[1];
+
+    let batch_size = inp.shape()[0];
+    let ol = Self::out_len(
+      inp.shape()[1],
+      conv_config.stride,
+      weights.shape()[1],
+      conv_config.padding,
+    );
+    let oc = weights.shape()[0];
+
+    let (splat_inp, splat_weights, splat_biases) = self.splat(tensors, zero.clone());
+
+    let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
+    let mut outp_flat = vec![];
+    for (inp_vec, weight_vec) in splat_inp.iter().zip(splat_weights.iter()) {
+      let inp_vec = inp_vec.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+      let weight_vec = weight_vec.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+      let vec_inputs = vec![inp_vec, weight_vec];
+      let constants = vec![zero.as_ref()];
+      let outp = dot_prod_chip
+        .forward(layouter.namespace(|| "conv1d dot_prod"), &vec_inputs, &constants)
+        .unwrap();
+      outp_flat.push(outp[0].clone());
+    }
+
+    let biases = splat_biases.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+
+    // Compute the bias + div + relu
+    let bdr_chip = BiasDivRoundRelu6Chip::<F>::construct(gadget_config.clone());
+    let tmp = vec![zero.as_ref()];
+    let outp_flat = outp_flat.iter().collect::<Vec<_>>();
+    let outp = bdr_chip
+      .forward(
+        layouter.namespace(|| "conv1d bias_div_relu"),
+        &vec![outp_flat, biases],
+        &tmp,
+      )
+      .unwrap();
+
+    // The bdr chip outputs interleaved [(relu'd, div'd), (relu'd, div'd), ...]
+    let outp = if conv_config.activation == ActivationType::Relu6 {
+      outp
+        .into_iter()
+        .step_by(2)
+        .map(|x| Rc::new(x))
+        .collect::<Vec<_>>()
+    } else if conv_config.activation == ActivationType::None {
+      outp
+        .into_iter()
+        .skip(1)
+        .step_by(2)
+        .map(|x| Rc::new(x))
+        .collect::<Vec<_>>()
+    } else if conv_config.activation == ActivationType::Relu {
+      let dived = outp.iter().skip(1).step_by(2).collect::<Vec<_>>();
+      let relu_chip = ReluChip::<F>::construct(gadget_config.clone());
+      let relu_outp = relu_chip
+        .forward(layouter.namespace(|| "conv1d relu"), &vec![dived], &tmp)
+        .unwrap();
+      relu_outp
+        .into_iter()
+        .map(|x| Rc::new(x))
+        .collect::<Vec<_>>()
+    } else {
+      panic!("Unsupported activation type");
+    };
+
+    let out_shape = vec![batch_size, ol, oc];
+    let outp = Array::from_shape_vec(IxDyn(&out_shape), outp).unwrap();
+
+    Ok(vec![outp])
+  }
+}
+
+impl<F: PrimeField> GadgetConsumer for Conv1DChip<F> {
+  fn used_gadgets(&self, layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    let conv_config = &Self::param_vec_to_config(layer_params.clone());
+    let mut outp = vec![
+      GadgetType::Adder,
+      GadgetType::DotProduct,
+      GadgetType::InputLookup,
+      GadgetType::BiasDivRoundRelu6,
+    ];
+
+    if conv_config.activation == ActivationType::Relu {
+      outp.push(GadgetType::Relu);
+    }
+
+    outp
+  }
+}
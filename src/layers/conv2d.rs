@@ -11,10 +11,10 @@ use ndarray::{Array, IxDyn};
 
 use crate::{
   gadgets::{
+    bias_div_floor_relu6::BiasDivFloorRelu6Chip,
     bias_div_round_relu6::BiasDivRoundRelu6Chip,
-    dot_prod::DotProductChip,
     gadget::{Gadget, GadgetConfig, GadgetType},
-    nonlinear::relu::ReluChip,
+    mac::MacChip,
   },
   layers::{
     fully_connected::{FullyConnectedChip, FullyConnectedConfig},
@@ -41,8 +41,14 @@ pub enum ConvLayerEnum {
 pub struct Conv2DConfig {
   pub conv_type: ConvLayerEnum,
   pub padding: PaddingEnum,
+  // The explicit ((top, bottom), (left, right)) padding before a valid convolution
+  pub explicit_padding: ((usize, usize), (usize, usize)),
   pub activation: ActivationType,
   pub stride: (usize, usize),
+  pub dilation: (usize, usize),
+  pub groups: usize,
+  // Floor instead of round after the division, as in the TFLite reference kernels
+  pub floor: bool,
 }
 
 pub struct Conv2DChip<F: PrimeField> {
@@ -58,11 +64,21 @@ impl<F: PrimeField> Conv2DChip<F> {
       1 => ConvLayerEnum::DepthwiseConv2D,
       _ => panic!("Invalid conv type"),
     };
+    // 2 is explicit padding, given as [top, bottom, left, right] after the groups
     let padding = match layer_params[1] {
       0 => PaddingEnum::Same,
-      1 => PaddingEnum::Valid,
+      1 | 2 => PaddingEnum::Valid,
       _ => panic!("Invalid padding"),
     };
+    let explicit_padding = if layer_params[1] == 2 {
+      let p = layer_params[8..12]
+        .iter()
+        .map(|x| *x as usize)
+        .collect::<Vec<_>>();
+      ((p[0], p[1]), (p[2], p[3]))
+    } else {
+      ((0, 0), (0, 0))
+    };
     let activation = match layer_params[2] {
       0 => ActivationType::None,
       1 => ActivationType::Relu,
@@ -70,14 +86,36 @@ impl<F: PrimeField> Conv2DChip<F> {
       _ => panic!("Invalid activation type"),
     };
     let stride = (layer_params[3] as usize, layer_params[4] as usize);
+    // Older models don't specify the dilation
+    let dilation = if layer_params.len() > 5 {
+      (layer_params[5] as usize, layer_params[6] as usize)
+    } else {
+      (1, 1)
+    };
+    let groups = if layer_params.len() > 7 {
+      layer_params[7] as usize
+    } else {
+      1
+    };
+    // 1 to floor, after the explicit padding slots (which are ignored unless the padding is 2)
+    let floor = layer_params.get(12) == Some(&1);
     Conv2DConfig {
       conv_type,
       padding,
+      explicit_padding,
       activation,
       stride,
+      dilation,
+      groups,
+      floor,
     }
   }
 
+  // The size of the kernel after inserting (dilation - 1) zeros between the taps
+  pub fn dilated_kernel(ch: usize, cw: usize, dilation: (usize, usize)) -> (usize, usize) {
+    ((ch - 1) * dilation.0 + 1, (cw - 1) * dilation.1 + 1)
+  }
+
   pub fn get_padding(
     h: usize,
     w: usize,
@@ -87,7 +125,7 @@ impl<F: PrimeField> Conv2DChip<F> {
     cj: usize,
   ) -> ((usize, usize), (usize, usize)) {
     let ph = if h % si == 0 {
-      (ci as i64 - sj as i64).max(0)
+      (ci as i64 - si as i64).max(0)
     } else {
       (ci as i64 - (h % si) as i64).max(0)
     } as usize;
@@ -99,6 +137,26 @@ impl<F: PrimeField> Conv2DChip<F> {
     ((ph / 2, ph - ph / 2), (pw / 2, pw - pw / 2))
   }
 
+  // The padding of the input and the output size, for a (dilated) kernel of size (ch, cw). SAME
+  // puts the extra padding at the bottom and right, as TFLite does
+  pub fn padded_out_hw(
+    conv_config: &Conv2DConfig,
+    h: usize,
+    w: usize,
+    ch: usize,
+    cw: usize,
+  ) -> (((usize, usize), (usize, usize)), (usize, usize)) {
+    let (si, sj) = conv_config.stride;
+    let (ph, pw) = if conv_config.padding == PaddingEnum::Same {
+      Self::get_padding(h, w, si, sj, ch, cw)
+    } else {
+      conv_config.explicit_padding
+    };
+    let oh = (h + ph.0 + ph.1 - ch) / si + 1;
+    let ow = (w + pw.0 + pw.1 - cw) / sj + 1;
+    ((ph, pw), (oh, ow))
+  }
+
   pub fn out_hw(
     h: usize,
     w: usize,
@@ -145,26 +203,24 @@ impl<F: PrimeField> Conv2DChip<F> {
     let h: usize = inp.shape()[1];
     let w: usize = inp.shape()[2];
 
-    let ch: usize = weights.shape()[1];
-    let cw: usize = weights.shape()[2];
+    let (ch, cw) = Self::dilated_kernel(
+      weights.shape()[1],
+      weights.shape()[2],
+      conv_config.dilation,
+    );
 
     let (si, sj) = conv_config.stride;
+    let (di, dj) = conv_config.dilation;
 
     // B, H, W, C
     assert_eq!(inp.shape().len(), 4);
 
-    let (ph, pw) = if conv_config.padding == PaddingEnum::Same {
-      Self::get_padding(h, w, si, sj, ch, cw)
-    } else {
-      ((0, 0), (0, 0))
-    };
+    let ((ph, pw), (oh, ow)) = Self::padded_out_hw(conv_config, h, w, ch, cw);
     // println!("Padding: {:?}", (ph, pw));
     let padding = vec![[0, 0], [ph.0, ph.1], [pw.0, pw.1], [0, 0]];
 
     let inp_pad = pad(&inp, padding, &zero);
 
-    let (oh, ow) = Self::out_hw(h, w, si, sj, ch, cw, conv_config.padding);
-
     let mut inp_cells = vec![];
     let mut weights_cells = vec![];
     let mut biases_cells = vec![];
@@ -192,8 +248,8 @@ impl<F: PrimeField> Conv2DChip<F> {
           for ci in 0..weights.shape()[1] {
             for cj in 0..weights.shape()[2] {
               for ck in 0..weights.shape()[3] {
-                let idx_i = i * si + ci;
-                let idx_j = j * sj + cj;
+                let idx_i = i * si + ci * di;
+                let idx_j = j * sj + cj * dj;
                 inp_cells[input_row_idx].push(inp_pad[[batch, idx_i, idx_j, ck]].clone());
               }
             }
@@ -239,16 +295,13 @@ impl<F: PrimeField> Conv2DChip<F> {
 
     let h: usize = input.shape()[1];
     let w: usize = input.shape()[2];
-    let ch: usize = weights.shape()[1];
-    let cw: usize = weights.shape()[2];
-    let (si, sj) = conv_config.stride;
-    let (oh, ow) = Self::out_hw(h, w, si, sj, ch, cw, conv_config.padding);
-
-    let (ph, pw) = if conv_config.padding == PaddingEnum::Same {
-      Self::get_padding(h, w, si, sj, ch, cw)
-    } else {
-      ((0, 0), (0, 0))
-    };
+    let (ch, cw) = Self::dilated_kernel(
+      weights.shape()[1],
+      weights.shape()[2],
+      conv_config.dilation,
+    );
+    let (di, dj) = conv_config.dilation;
+    let ((ph, pw), (oh, ow)) = Self::padded_out_hw(conv_config, h, w, ch, cw);
 
     let padding = vec![[0, 0], [ph.0, ph.1], [pw.0, pw.1], [0, 0]];
 
@@ -268,8 +321,8 @@ impl<F: PrimeField> Conv2DChip<F> {
 
           for ci in 0..weights.shape()[1] {
             for cj in 0..weights.shape()[2] {
-              let idx_i = i * strides.0 + ci;
-              let idx_j = j * strides.1 + cj;
+              let idx_i = i * strides.0 + ci * di;
+              let idx_j = j * strides.1 + cj * dj;
 
               inp_cells[row_idx].push(inp_pad[[0, idx_i, idx_j, chan_out]].clone());
               weight_cells[row_idx].push(weights[[0, ci, cj, chan_out]].clone());
@@ -283,6 +336,78 @@ impl<F: PrimeField> Conv2DChip<F> {
 
     (inp_cells, weight_cells, biases_cells)
   }
+
+  // Input: (B, H, W, C), weights: (O, C_H, C_W, C / G), biases: (O)
+  // Each output channel only sees the input channels of its group
+  pub fn splat_grouped<G: Clone>(
+    &self,
+    tensors: &Vec<Array<Rc<G>, IxDyn>>,
+    zero: Rc<G>,
+  ) -> (Vec<Vec<Rc<G>>>, Vec<Vec<Rc<G>>>, Vec<Rc<G>>) {
+    assert!(tensors.len() <= 3);
+
+    let conv_config = &Self::param_vec_to_config(self.config.layer_params.clone());
+
+    let inp = &tensors[0];
+    let weights = &tensors[1];
+
+    assert_eq!(inp.shape().len(), 4);
+    assert_eq!(weights.shape().len(), 4);
+
+    let groups = conv_config.groups;
+    let group_in = weights.shape()[3];
+    let group_out = weights.shape()[0] / groups;
+    assert_eq!(inp.shape()[3], group_in * groups);
+    assert_eq!(weights.shape()[0] % groups, 0);
+
+    let h: usize = inp.shape()[1];
+    let w: usize = inp.shape()[2];
+    let (ch, cw) = Self::dilated_kernel(
+      weights.shape()[1],
+      weights.shape()[2],
+      conv_config.dilation,
+    );
+    let (si, sj) = conv_config.stride;
+    let (di, dj) = conv_config.dilation;
+    let ((ph, pw), (oh, ow)) = Self::padded_out_hw(conv_config, h, w, ch, cw);
+    let padding = vec![[0, 0], [ph.0, ph.1], [pw.0, pw.1], [0, 0]];
+    let inp_pad = pad(&inp, padding, &zero);
+
+    let mut inp_cells = vec![];
+    let mut weight_cells = vec![];
+    let mut biases_cells = vec![];
+
+    for batch in 0..inp.shape()[0] {
+      for i in 0..oh {
+        for j in 0..ow {
+          for chan_out in 0..weights.shape()[0] {
+            let group = chan_out / group_out;
+            let mut inp_row = vec![];
+            let mut weight_row = vec![];
+            for ci in 0..weights.shape()[1] {
+              for cj in 0..weights.shape()[2] {
+                for ck in 0..group_in {
+                  let idx_i = i * si + ci * di;
+                  let idx_j = j * sj + cj * dj;
+                  inp_row.push(inp_pad[[batch, idx_i, idx_j, group * group_in + ck]].clone());
+                  weight_row.push(weights[[chan_out, ci, cj, ck]].clone());
+                }
+              }
+            }
+            inp_cells.push(inp_row);
+            weight_cells.push(weight_row);
+            if tensors.len() == 3 {
+              biases_cells.push(tensors[2][chan_out].clone());
+            } else {
+              biases_cells.push(zero.clone());
+            }
+          }
+        }
+      }
+    }
+
+    (inp_cells, weight_cells, biases_cells)
+  }
 }
 
 impl<F: PrimeField> Layer<F> for Conv2DChip<F> {
@@ -300,24 +425,25 @@ impl<F: PrimeField> Layer<F> for Conv2DChip<F> {
     let inp = &tensors[0];
     let weights = &tensors[1];
 
-    let (oh, ow) = Self::out_hw(
-      inp.shape()[1],
-      inp.shape()[2],
-      conv_config.stride.0,
-      conv_config.stride.1,
+    let (ch, cw) = Self::dilated_kernel(
       weights.shape()[1],
       weights.shape()[2],
-      conv_config.padding,
+      conv_config.dilation,
     );
+    let (_, (oh, ow)) =
+      Self::padded_out_hw(conv_config, inp.shape()[1], inp.shape()[2], ch, cw);
     let batch_size = inp.shape()[0];
 
+    let grouped = conv_config.conv_type == ConvLayerEnum::Conv2D && conv_config.groups > 1;
+
     let (splat_inp, splat_weights, splat_biases) = match conv_config.conv_type {
+      ConvLayerEnum::Conv2D if grouped => self.splat_grouped(tensors, zero.clone()),
       ConvLayerEnum::Conv2D => self.splat(tensors, zero.clone()),
       ConvLayerEnum::DepthwiseConv2D => self.splat_depthwise(tensors, zero.clone()),
     };
 
     let outp_flat: Vec<AssignedCell<F, F>> = match conv_config.conv_type {
-      ConvLayerEnum::Conv2D => {
+      ConvLayerEnum::Conv2D if !grouped => {
         let fc_chip = FullyConnectedChip::<F> {
           _marker: PhantomData,
           config: FullyConnectedConfig::construct(false),
@@ -337,13 +463,18 @@ impl<F: PrimeField> Layer<F> for Conv2DChip<F> {
         let weights_array =
           Array::from_shape_vec(IxDyn(&vec![out_channels, conv_size]), flattened_weights).unwrap();
 
+        // The "weights" are [n, k], so transpose_b is set
+        let fc_config = LayerConfig {
+          layer_params: vec![layer_config.layer_params[0], 1],
+          ..layer_config.clone()
+        };
         let outp_slice = fc_chip
           .forward(
             layouter.namespace(|| ""),
             &vec![weights_array, inp_array],
             constants,
             gadget_config.clone(),
-            layer_config,
+            &fc_config,
           )
           .unwrap();
 
@@ -354,18 +485,21 @@ impl<F: PrimeField> Layer<F> for Conv2DChip<F> {
           .collect::<Vec<_>>();
         outp_flat
       }
-      ConvLayerEnum::DepthwiseConv2D => {
+      ConvLayerEnum::Conv2D | ConvLayerEnum::DepthwiseConv2D => {
         // Do the dot products
-        let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
         let mut outp_flat = vec![];
         for (inp_vec, weight_vec) in splat_inp.iter().zip(splat_weights.iter()) {
           let inp_vec = inp_vec.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
           let weight_vec = weight_vec.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
           let vec_inputs = vec![inp_vec, weight_vec];
           let constants = vec![zero.as_ref()];
-          let outp = dot_prod_chip
-            .forward(layouter.namespace(|| "dot_prod"), &vec_inputs, &constants)
-            .unwrap();
+          let outp = MacChip::<F>::dot_product(
+            layouter.namespace(|| "dot_prod"),
+            gadget_config.clone(),
+            &vec_inputs,
+            &constants,
+          )
+          .unwrap();
           outp_flat.push(outp[0].clone());
         }
         // println!("outp_flat: {:?}", outp_flat.len());
@@ -379,47 +513,49 @@ impl<F: PrimeField> Layer<F> for Conv2DChip<F> {
       biases.push(bias.as_ref());
     }
 
-    // Compute the bias + div + relu
-    let bdr_chip = BiasDivRoundRelu6Chip::<F>::construct(gadget_config.clone());
+    // Compute the bias + div + activation. ReLU and ReLU6 each have their own variant of the gadget
     let tmp = vec![zero.as_ref()];
     let outp_flat = outp_flat.iter().map(|x| x).collect::<Vec<_>>();
-    let outp = bdr_chip
-      .forward(
-        layouter.namespace(|| "bias_div_relu"),
-        &vec![outp_flat, biases],
-        &tmp,
-      )
-      .unwrap();
-
-    // TODO: this is also horrible. The bdr chip outputs interleaved [(relu'd, div'd), (relu'd, div'd), ...]
-    // Uninterleave depending on whether or not we're doing the relu
-    let outp = if conv_config.activation == ActivationType::Relu6 {
-      outp
-        .into_iter()
-        .step_by(2)
-        .map(|x| Rc::new(x))
-        .collect::<Vec<_>>()
-    } else if conv_config.activation == ActivationType::None {
-      outp
-        .into_iter()
-        .skip(1)
-        .step_by(2)
-        .map(|x| Rc::new(x))
-        .collect::<Vec<_>>()
-    } else if conv_config.activation == ActivationType::Relu {
-      let dived = outp.iter().skip(1).step_by(2).collect::<Vec<_>>();
-      let relu_chip = ReluChip::<F>::construct(gadget_config.clone());
-      let relu_outp = relu_chip
-        .forward(layouter.namespace(|| "relu"), &vec![dived], &tmp)
-        .unwrap();
-      let relu_outp = relu_outp
-        .into_iter()
-        .map(|x| Rc::new(x))
-        .collect::<Vec<_>>();
-      relu_outp
+    let outp = if conv_config.floor {
+      let bdf_chip = if conv_config.activation == ActivationType::Relu {
+        BiasDivFloorRelu6Chip::<F>::construct_relu(gadget_config.clone())
+      } else {
+        BiasDivFloorRelu6Chip::<F>::construct(gadget_config.clone())
+      };
+      bdf_chip
+        .forward(
+          layouter.namespace(|| "bias_div_floor_relu"),
+          &vec![outp_flat, biases],
+          &tmp,
+        )
+        .unwrap()
     } else {
-      panic!("Unsupported activation type");
+      let bdr_chip = if conv_config.activation == ActivationType::Relu {
+        BiasDivRoundRelu6Chip::<F>::construct_relu(gadget_config.clone())
+      } else {
+        BiasDivRoundRelu6Chip::<F>::construct(gadget_config.clone())
+      };
+      bdr_chip
+        .forward(
+          layouter.namespace(|| "bias_div_relu"),
+          &vec![outp_flat, biases],
+          &tmp,
+        )
+        .unwrap()
+    };
+
+    // The bdr chip outputs interleaved [(activated, div'd), (activated, div'd), ...]
+    let skip = match conv_config.activation {
+      ActivationType::Relu | ActivationType::Relu6 => 0,
+      ActivationType::None => 1,
+      _ => panic!("Unsupported activation type"),
     };
+    let outp = outp
+      .into_iter()
+      .skip(skip)
+      .step_by(2)
+      .map(|x| Rc::new(x))
+      .collect::<Vec<_>>();
 
     let oc = match conv_config.conv_type {
       ConvLayerEnum::Conv2D => weights.shape()[0],
@@ -436,17 +572,21 @@ impl<F: PrimeField> Layer<F> for Conv2DChip<F> {
 impl<F: PrimeField> GadgetConsumer for Conv2DChip<F> {
   fn used_gadgets(&self, layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
     let conv_config = &Self::param_vec_to_config(layer_params.clone());
-    let mut outp = vec![
+    let bdr = match (
+      conv_config.activation == ActivationType::Relu,
+      conv_config.floor,
+    ) {
+      (true, false) => GadgetType::BiasDivRoundRelu,
+      (false, false) => GadgetType::BiasDivRoundRelu6,
+      (true, true) => GadgetType::BiasDivFloorRelu,
+      (false, true) => GadgetType::BiasDivFloorRelu6,
+    };
+
+    vec![
       GadgetType::Adder,
       GadgetType::DotProduct,
       GadgetType::InputLookup,
-      GadgetType::BiasDivRoundRelu6,
-    ];
-
-    if conv_config.activation == ActivationType::Relu {
-      outp.push(GadgetType::Relu);
-    }
-
-    outp
+      bdr,
+    ]
   }
 }
@@ -0,0 +1,100 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, Axis, IxDyn};
+
+use crate::{
+  gadgets::{
+    add_pairs::AddPairsChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::layer::{Layer, LayerConfig};
+
+// Params: [axis, exclusive, reverse]
+pub struct CumSumChip {}
+
+impl<F: PrimeField> Layer<F> for CumSumChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let params = &layer_config.layer_params;
+    let axis = if params[0] < 0 {
+      (params[0] + inp.ndim() as i64) as usize
+    } else {
+      params[0] as usize
+    };
+    let exclusive = params.len() > 1 && params[1] == 1;
+    let reverse = params.len() > 2 && params[2] == 1;
+    let zero = constants.get(&0).unwrap();
+
+    let len = inp.shape()[axis];
+    let order = if reverse {
+      (0..len).rev().collect::<Vec<_>>()
+    } else {
+      (0..len).collect::<Vec<_>>()
+    };
+
+    // Each step adds the next slice along the axis to the running sum of all lanes at once
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config.clone());
+    let mut running: Option<Vec<CellRc<F>>> = None;
+    let mut slices = vec![vec![]; len];
+    for idx in order {
+      let cur = inp.index_axis(Axis(axis), idx).iter().cloned().collect::<Vec<_>>();
+      let next = match &running {
+        None => cur,
+        Some(running) => {
+          let running = running.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+          let cur = cur.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+          add_pairs_chip
+            .forward(
+              layouter.namespace(|| "cum sum"),
+              &vec![running, cur],
+              &vec![zero.as_ref()],
+            )?
+            .into_iter()
+            .map(|x| Rc::new(x))
+            .collect::<Vec<_>>()
+        }
+      };
+
+      slices[idx] = if exclusive {
+        running.unwrap_or(vec![zero.clone(); next.len()])
+      } else {
+        next.clone()
+      };
+      running = Some(next);
+    }
+
+    // Slices are stored along the axis, so move the axis to the front and back again
+    let mut front_shape = vec![len];
+    front_shape.extend(
+      inp
+        .shape()
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != axis)
+        .map(|(_, x)| *x),
+    );
+    let outp = Array::from_shape_vec(IxDyn(&front_shape), slices.concat()).unwrap();
+    let mut perm = (1..inp.ndim()).collect::<Vec<_>>();
+    perm.insert(axis, 0);
+    let outp = outp.permuted_axes(IxDyn(&perm)).as_standard_layout().to_owned();
+
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for CumSumChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![GadgetType::AddPairs]
+  }
+}
@@ -5,35 +5,81 @@ use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error}
 use crate::{
   gadgets::gadget::{convert_to_u64, GadgetConfig},
   layers::{
-    arithmetic::{add::AddChip, div_var::DivVarChip, mul::MulChip, sub::SubChip},
+    arithmetic::{
+      add::AddChip,
+      compare::{EqualChip, GreaterChip, LessChip},
+      div::DivChip,
+      div_var::DivVarChip,
+      logical::{LogicalAndChip, LogicalNotChip, LogicalOrChip},
+      max::MaximumChip,
+      min::MinChip,
+      mul::MulChip,
+      sub::SubChip,
+    },
     batch_mat_mul::BatchMatMulChip,
     div_fixed::DivFixedChip,
+    einsum::EinsumChip,
+    exp::ExpChip,
     fully_connected::{FullyConnectedChip, FullyConnectedConfig},
+    gelu::GeluChip,
+    gru::GRUChip,
+    hardmax::HardmaxChip,
+    l2_norm::L2NormChip,
+    log::LogChip,
+    log_softmax::LogSoftmaxChip,
     logistic::LogisticChip,
+    lrn::LRNChip,
     max_pool_2d::MaxPool2DChip,
     mean::MeanChip,
+    mish::MishChip,
+    multi_head_attention::MultiHeadAttentionChip,
+    non_max_suppression::NonMaxSuppressionChip,
     noop::NoopChip,
+    piecewise_linear::PiecewiseLinearChip,
     pow::PowChip,
+    reciprocal::ReciprocalChip,
+    reduce_max::ReduceMaxChip,
+    reduce_sum::ReduceSumChip,
+    requantize::RequantizeChip,
+    resize_bilinear::ResizeBilinearChip,
+    roi_align::ROIAlignChip,
     rsqrt::RsqrtChip,
+    scaled_dot_product_attention::ScaledDotProductAttentionChip,
+    select::SelectChip,
     shape::{
-      broadcast::BroadcastChip, concatenation::ConcatenationChip, mask_neg_inf::MaskNegInfChip,
+      batch_to_space::BatchToSpaceChip, broadcast::BroadcastChip, concatenation::ConcatenationChip,
+      depth_to_space::DepthToSpaceChip, flatten::FlattenChip, mask_neg_inf::MaskNegInfChip,
       pack::PackChip, pad::PadChip, permute::PermuteChip, reshape::ReshapeChip,
-      resize_nn::ResizeNNChip, rotate::RotateChip, slice::SliceChip, split::SplitChip,
-      transpose::TransposeChip,
+      resize_nn::ResizeNNChip, rotate::RotateChip, slice::SliceChip,
+      space_to_batch::SpaceToBatchChip, space_to_depth::SpaceToDepthChip, split::SplitChip,
+      tile::TileChip, transpose::TransposeChip, unpack::UnpackChip,
     },
+    sin::SinChip,
     softmax::SoftmaxChip,
+    softplus::SoftplusChip,
     sqrt::SqrtChip,
     square::SquareChip,
     squared_diff::SquaredDiffChip,
     tanh::TanhChip,
+    top_k::TopKChip,
     update::UpdateChip,
+    upsample::UpsampleChip,
   },
   utils::helpers::print_assigned_arr,
 };
 
 use super::{
+  abs::AbsChip,
+  arg_max::ArgMaxChip,
+  assert_close::AssertCloseChip,
   avg_pool_2d::AvgPool2DChip,
+  clip::ClipChip,
+  conv1d::Conv1DChip,
   conv2d::Conv2DChip,
+  conv3d::Conv3DChip,
+  cos::CosChip,
+  cum_sum::CumSumChip,
+  custom_nonlinear::CustomNonlinearChip,
   layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig, LayerType},
 };
 
@@ -98,6 +144,36 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Abs => {
+          let abs_chip = AbsChip {};
+          abs_chip.forward(
+            layouter.namespace(|| "dag abs"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::ArgMax => {
+          let arg_max_chip = ArgMaxChip {};
+          arg_max_chip.forward(
+            layouter.namespace(|| "dag arg max"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::AssertClose => {
+          let assert_close_chip = AssertCloseChip {};
+          assert_close_chip.forward(
+            layouter.namespace(|| "dag assert close"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::AvgPool2D => {
           let avg_pool_2d_chip = AvgPool2DChip {};
           avg_pool_2d_chip.forward(
@@ -108,6 +184,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::BatchToSpace => {
+          let batch_to_space_chip = BatchToSpaceChip {};
+          batch_to_space_chip.forward(
+            layouter.namespace(|| "dag batch to space"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::MaxPool2D => {
           let max_pool_2d_chip = MaxPool2DChip {
             marker: PhantomData::<F>,
@@ -140,6 +226,19 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Conv1D => {
+          let conv_1d_chip = Conv1DChip {
+            config: layer_config.clone(),
+            _marker: PhantomData,
+          };
+          conv_1d_chip.forward(
+            layouter.namespace(|| "dag conv 1d"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Conv2D => {
           let conv_2d_chip = Conv2DChip {
             config: layer_config.clone(),
@@ -153,6 +252,61 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Conv3D => {
+          let conv_3d_chip = Conv3DChip {
+            config: layer_config.clone(),
+            _marker: PhantomData,
+          };
+          conv_3d_chip.forward(
+            layouter.namespace(|| "dag conv 3d"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Cos => {
+          let cos_chip = CosChip {};
+          cos_chip.forward(
+            layouter.namespace(|| "dag cos"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::CumSum => {
+          let cum_sum_chip = CumSumChip {};
+          cum_sum_chip.forward(
+            layouter.namespace(|| "dag cum sum"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::CustomNonlinear => {
+          let custom_nonlinear_chip = CustomNonlinearChip {
+            custom_functions: gadget_config.custom_functions.clone(),
+          };
+          custom_nonlinear_chip.forward(
+            layouter.namespace(|| "dag custom nonlinear"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::DepthToSpace => {
+          let depth_to_space_chip = DepthToSpaceChip {};
+          depth_to_space_chip.forward(
+            layouter.namespace(|| "dag depth to space"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::DivFixed => {
           let div_fixed_chip = DivFixedChip {};
           div_fixed_chip.forward(
@@ -163,6 +317,56 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Einsum => {
+          let einsum_chip = EinsumChip {};
+          einsum_chip.forward(
+            layouter.namespace(|| "dag einsum"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Equal => {
+          let equal_chip = EqualChip {};
+          equal_chip.forward(
+            layouter.namespace(|| "dag equal"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Exp => {
+          let exp_chip = ExpChip {};
+          exp_chip.forward(
+            layouter.namespace(|| "dag exp"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Flatten => {
+          let flatten_chip = FlattenChip {};
+          flatten_chip.forward(
+            layouter.namespace(|| "dag flatten"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Div => {
+          let div_chip = DivChip {};
+          div_chip.forward(
+            layouter.namespace(|| "dag div"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::DivVar => {
           let div_var_chip = DivVarChip {};
           div_var_chip.forward(
@@ -186,6 +390,86 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Gelu => {
+          let gelu_chip = GeluChip {};
+          gelu_chip.forward(
+            layouter.namespace(|| "dag gelu"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Greater => {
+          let greater_chip = GreaterChip {};
+          greater_chip.forward(
+            layouter.namespace(|| "dag greater"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::GRU => {
+          let gru_chip = GRUChip {};
+          gru_chip.forward(
+            layouter.namespace(|| "dag gru"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Hardmax => {
+          let hardmax_chip = HardmaxChip {};
+          hardmax_chip.forward(
+            layouter.namespace(|| "dag hardmax"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::L2Norm => {
+          let l2_norm_chip = L2NormChip {};
+          l2_norm_chip.forward(
+            layouter.namespace(|| "dag l2 norm"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Less => {
+          let less_chip = LessChip {};
+          less_chip.forward(
+            layouter.namespace(|| "dag less"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Log => {
+          let log_chip = LogChip {};
+          log_chip.forward(
+            layouter.namespace(|| "dag log"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::LogSoftmax => {
+          let log_softmax_chip = LogSoftmaxChip {};
+          log_softmax_chip.forward(
+            layouter.namespace(|| "dag log softmax"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Softmax => {
           let softmax_chip = SoftmaxChip {};
           softmax_chip.forward(
@@ -196,6 +480,36 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Softplus => {
+          let softplus_chip = SoftplusChip {};
+          softplus_chip.forward(
+            layouter.namespace(|| "dag softplus"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::SpaceToBatch => {
+          let space_to_batch_chip = SpaceToBatchChip {};
+          space_to_batch_chip.forward(
+            layouter.namespace(|| "dag space to batch"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::SpaceToDepth => {
+          let space_to_depth_chip = SpaceToDepthChip {};
+          space_to_depth_chip.forward(
+            layouter.namespace(|| "dag space to depth"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Mean => {
           let mean_chip = MeanChip {};
           mean_chip.forward(
@@ -206,6 +520,26 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Min => {
+          let min_chip = MinChip {};
+          min_chip.forward(
+            layouter.namespace(|| "dag min"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Mish => {
+          let mish_chip = MishChip {};
+          mish_chip.forward(
+            layouter.namespace(|| "dag mish"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Pad => {
           let pad_chip = PadChip {};
           pad_chip.forward(
@@ -216,6 +550,18 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::PiecewiseLinear => {
+          let piecewise_linear_chip = PiecewiseLinearChip {
+            custom_functions: gadget_config.custom_functions.clone(),
+          };
+          piecewise_linear_chip.forward(
+            layouter.namespace(|| "dag piecewise linear"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Permute => {
           let pad_chip = PermuteChip {};
           pad_chip.forward(
@@ -226,6 +572,36 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Reciprocal => {
+          let reciprocal_chip = ReciprocalChip {};
+          reciprocal_chip.forward(
+            layouter.namespace(|| "dag reciprocal"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::ReduceMax => {
+          let reduce_max_chip = ReduceMaxChip {};
+          reduce_max_chip.forward(
+            layouter.namespace(|| "dag reduce max"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::ReduceSum => {
+          let reduce_sum_chip = ReduceSumChip {};
+          reduce_sum_chip.forward(
+            layouter.namespace(|| "dag reduce sum"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::SquaredDifference => {
           let squared_diff_chip = SquaredDiffChip {};
           squared_diff_chip.forward(
@@ -246,6 +622,36 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::ScaledDotProductAttention => {
+          let scaled_dot_product_attention_chip = ScaledDotProductAttentionChip {};
+          scaled_dot_product_attention_chip.forward(
+            layouter.namespace(|| "dag scaled dot product attention"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Select => {
+          let select_chip = SelectChip {};
+          select_chip.forward(
+            layouter.namespace(|| "dag select"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::Sin => {
+          let sin_chip = SinChip {};
+          sin_chip.forward(
+            layouter.namespace(|| "dag sin"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Sqrt => {
           let sqrt_chip = SqrtChip {};
           sqrt_chip.forward(
@@ -256,6 +662,36 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::LogicalAnd => {
+          let logical_and_chip = LogicalAndChip {};
+          logical_and_chip.forward(
+            layouter.namespace(|| "dag logical and"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::LogicalNot => {
+          let logical_not_chip = LogicalNotChip {};
+          logical_not_chip.forward(
+            layouter.namespace(|| "dag logical not"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::LogicalOr => {
+          let logical_or_chip = LogicalOrChip {};
+          logical_or_chip.forward(
+            layouter.namespace(|| "dag logical or"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Logistic => {
           let logistic_chip = LogisticChip {};
           logistic_chip.forward(
@@ -266,6 +702,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::LRN => {
+          let lrn_chip = LRNChip {};
+          lrn_chip.forward(
+            layouter.namespace(|| "dag lrn"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Pow => {
           let pow_chip = PowChip {};
           pow_chip.forward(
@@ -286,6 +732,26 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Tile => {
+          let tile_chip = TileChip {};
+          tile_chip.forward(
+            layouter.namespace(|| "dag tile"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::TopK => {
+          let top_k_chip = TopKChip {};
+          top_k_chip.forward(
+            layouter.namespace(|| "dag top k"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Mul => {
           let mul_chip = MulChip {};
           mul_chip.forward(
@@ -296,6 +762,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::MultiHeadAttention => {
+          let multi_head_attention_chip = MultiHeadAttentionChip {};
+          multi_head_attention_chip.forward(
+            layouter.namespace(|| "dag multi head attention"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Sub => {
           let sub_chip = SubChip {};
           sub_chip.forward(
@@ -306,6 +782,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::NonMaxSuppression => {
+          let non_max_suppression_chip = NonMaxSuppressionChip {};
+          non_max_suppression_chip.forward(
+            layouter.namespace(|| "dag non max suppression"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Noop => {
           let noop_chip = NoopChip {};
           noop_chip.forward(
@@ -326,6 +812,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Unpack => {
+          let unpack_chip = UnpackChip {};
+          unpack_chip.forward(
+            layouter.namespace(|| "dag unpack"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Reshape => {
           let reshape_chip = ReshapeChip {};
           reshape_chip.forward(
@@ -336,6 +832,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Requantize => {
+          let requantize_chip = RequantizeChip {};
+          requantize_chip.forward(
+            layouter.namespace(|| "dag requantize"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::ResizeNN => {
           let resize_nn_chip = ResizeNNChip {};
           resize_nn_chip.forward(
@@ -346,6 +852,26 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::ROIAlign => {
+          let roi_align_chip = ROIAlignChip {};
+          roi_align_chip.forward(
+            layouter.namespace(|| "dag roi align"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
+        LayerType::ResizeBilinear => {
+          let resize_bilinear_chip = ResizeBilinearChip {};
+          resize_bilinear_chip.forward(
+            layouter.namespace(|| "dag resize bilinear"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Rotate => {
           let rotate_chip = RotateChip {};
           rotate_chip.forward(
@@ -366,6 +892,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Clip => {
+          let clip_chip = ClipChip {};
+          clip_chip.forward(
+            layouter.namespace(|| "dag clip"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Pack => {
           let pack_chip = PackChip {};
           pack_chip.forward(
@@ -396,6 +932,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Upsample => {
+          let upsample_chip = UpsampleChip {};
+          upsample_chip.forward(
+            layouter.namespace(|| "dag upsample"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Slice => {
           let slice_chip = SliceChip {};
           slice_chip.forward(
@@ -416,6 +962,16 @@ impl<F: PrimeField + Ord> DAGLayerChip<F> {
             &layer_config,
           )?
         }
+        LayerType::Max => {
+          let max_chip = MaximumChip {};
+          max_chip.forward(
+            layouter.namespace(|| "dag max"),
+            &vec_inps,
+            constants,
+            gadget_config.clone(),
+            &layer_config,
+          )?
+        }
         LayerType::Square => {
           let square_chip = SquareChip {};
           square_chip.forward(
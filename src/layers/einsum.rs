@@ -0,0 +1,132 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{indices, Array, IxDyn};
+
+use crate::gadgets::{
+  dot_prod::DotProductChip,
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  var_div::VarDivRoundChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// Two input einsum, e.g. bij,bjk->bik. Params: the subscripts of the first input, -1, the
+// subscripts of the second input, -1, the subscripts of the output, with a = 0, b = 1, etc. Every
+// subscript that is not in the output is summed over, so each output is a dot product.
+#[derive(Clone, Debug)]
+pub struct EinsumChip {}
+
+impl EinsumChip {
+  pub fn parse_subscripts(layer_params: &Vec<i64>) -> (Vec<i64>, Vec<i64>, Vec<i64>) {
+    let subscripts = layer_params
+      .split(|x| *x == -1)
+      .map(|x| x.to_vec())
+      .collect::<Vec<_>>();
+    assert_eq!(subscripts.len(), 3, "einsum only supports two inputs");
+    (
+      subscripts[0].clone(),
+      subscripts[1].clone(),
+      subscripts[2].clone(),
+    )
+  }
+}
+
+impl<F: PrimeField> Layer<F> for EinsumChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp1 = &tensors[0];
+    let inp2 = &tensors[1];
+    let (sub1, sub2, sub_out) = Self::parse_subscripts(&layer_config.layer_params);
+    assert_eq!(inp1.ndim(), sub1.len());
+    assert_eq!(inp2.ndim(), sub2.len());
+
+    let mut dims: HashMap<i64, usize> = HashMap::new();
+    for (sub, inp) in [(&sub1, inp1), (&sub2, inp2)] {
+      for (s, dim) in sub.iter().zip(inp.shape().iter()) {
+        assert_eq!(*dims.entry(*s).or_insert(*dim), *dim);
+      }
+    }
+
+    let mut summed = vec![];
+    for s in sub1.iter().chain(sub2.iter()) {
+      if !sub_out.contains(s) && !summed.contains(s) {
+        summed.push(*s);
+      }
+    }
+    let out_shape = sub_out
+      .iter()
+      .map(|s| *dims.get(s).unwrap())
+      .collect::<Vec<_>>();
+    let summed_shape = summed
+      .iter()
+      .map(|s| *dims.get(s).unwrap())
+      .collect::<Vec<_>>();
+
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+
+    let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
+    let mut dot_prods: Vec<AssignedCell<F, F>> = vec![];
+    for out_idx in indices(IxDyn(&out_shape)) {
+      let mut vals: HashMap<i64, usize> = HashMap::new();
+      for (i, s) in sub_out.iter().enumerate() {
+        vals.insert(*s, out_idx[i]);
+      }
+
+      let mut inp1_vec = vec![];
+      let mut inp2_vec = vec![];
+      for summed_idx in indices(IxDyn(&summed_shape)) {
+        for (i, s) in summed.iter().enumerate() {
+          vals.insert(*s, summed_idx[i]);
+        }
+        let idx1 = sub1.iter().map(|s| vals[s]).collect::<Vec<_>>();
+        let idx2 = sub2.iter().map(|s| vals[s]).collect::<Vec<_>>();
+        inp1_vec.push(inp1[IxDyn(&idx1)].as_ref());
+        inp2_vec.push(inp2[IxDyn(&idx2)].as_ref());
+      }
+
+      let outp = dot_prod_chip.forward(
+        layouter.namespace(|| "einsum dot prod"),
+        &vec![inp1_vec, inp2_vec],
+        &vec![zero],
+      )?;
+      dot_prods.push(outp[0].clone());
+    }
+
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let out = var_div_chip.forward(
+      layouter.namespace(|| "einsum rescale"),
+      &vec![dot_prods.iter().collect()],
+      &vec![zero, sf],
+    )?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(&out_shape), out).unwrap();
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for EinsumChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::DotProduct,
+      GadgetType::VarDivRound,
+      GadgetType::InputLookup,
+    ]
+  }
+}
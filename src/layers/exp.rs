@@ -0,0 +1,59 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  nonlinear::exp::ExpGadgetChip,
+  var_div::VarDivRoundChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+#[derive(Clone, Debug)]
+pub struct ExpChip {}
+
+impl<F: PrimeField> Layer<F> for ExpChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+
+    let exp_chip = ExpGadgetChip::<F>::construct(gadget_config.clone());
+    let vec_inps = vec![inp_vec];
+    let constants = vec![zero];
+    let out = exp_chip.forward(layouter.namespace(|| "exp chip"), &vec_inps, &constants)?;
+
+    // The gadget returns exp(x) * SF^2
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let out = out.iter().collect::<Vec<_>>();
+    let out = var_div_chip.forward(layouter.namespace(|| "exp div"), &vec![out], &vec![zero, sf])?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for ExpChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Exp,
+      GadgetType::VarDivRound,
+      GadgetType::InputLookup,
+    ]
+  }
+}
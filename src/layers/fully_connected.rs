@@ -10,9 +10,11 @@ use ndarray::{Array, ArrayView, Axis, IxDyn};
 use crate::{
   gadgets::{
     add_pairs::AddPairsChip,
-    dot_prod::DotProductChip,
+    bias_div_floor_relu6::BiasDivFloorRelu6Chip,
+    bias_div_round_relu6::BiasDivRoundRelu6Chip,
     gadget::{Gadget, GadgetConfig, GadgetType},
-    nonlinear::relu::ReluChip,
+    mac::MacChip,
+    shift::ShiftChip,
     var_div::VarDivRoundChip,
   },
   layers::layer::ActivationType,
@@ -104,9 +106,15 @@ impl<F: PrimeField> FullyConnectedChip<F> {
     match activation {
       0 => ActivationType::None,
       1 => ActivationType::Relu,
+      3 => ActivationType::Relu6,
       _ => panic!("Unsupported activation type for fully connected"),
     }
   }
+
+  // params[2] is 1 to floor instead of round after the division
+  fn get_floor(&self, layer_params: &Vec<i64>) -> bool {
+    layer_params.get(2) == Some(&1)
+  }
 }
 
 impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
@@ -120,6 +128,7 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
     assert!(tensors.len() <= 3);
     let activation = self.get_activation(&layer_config.layer_params);
+    let floor = self.get_floor(&layer_config.layer_params);
 
     let input = &tensors[0];
     let ndim = input.ndim();
@@ -128,7 +137,13 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
     } else {
       input.index_axis(Axis(0), 0)
     };
-    let weight = &tensors[1].t().into_owned();
+    // The weights are [out, in] as in TFLite unless transpose_b is 0
+    let transpose_b = layer_config.layer_params.get(1).cloned().unwrap_or(1) == 1;
+    let weight = &if transpose_b {
+      tensors[1].t().into_owned()
+    } else {
+      tensors[1].clone()
+    };
     let zero = constants.get(&0).unwrap().as_ref();
 
     // Compute and assign the result
@@ -149,7 +164,6 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
     let r1 = Self::random_vector(constants, mm_result.shape()[0]).unwrap();
     let r2 = Self::random_vector(constants, mm_result.shape()[1]).unwrap();
 
-    let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
     let r1_ref = r1.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
     let r2_ref = r2.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
 
@@ -161,25 +175,25 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
     for i in 0..mm_result.shape()[1] {
       let tmp = mm_result.index_axis(Axis(1), i);
       let mm_ci = tmp.iter().collect::<Vec<_>>();
-      let r1_res_i = dot_prod_chip
-        .forward(
-          layouter.namespace(|| format!("r1_res_{}", i)),
-          &vec![mm_ci, r1_ref.clone()],
-          &vec![zero],
-        )
-        .unwrap();
+      let r1_res_i = MacChip::<F>::dot_product(
+        layouter.namespace(|| format!("r1_res_{}", i)),
+        gadget_config.clone(),
+        &vec![mm_ci, r1_ref.clone()],
+        &vec![zero],
+      )
+      .unwrap();
       r1_res.push(r1_res_i[0].clone());
     }
 
     // Compute r1 * result * r2
     let r1_res_ref = r1_res.iter().collect::<Vec<_>>();
-    let r1_res_r2 = dot_prod_chip
-      .forward(
-        layouter.namespace(|| "r1_res_r2"),
-        &vec![r1_res_ref, r2_ref.clone()],
-        &vec![zero],
-      )
-      .unwrap();
+    let r1_res_r2 = MacChip::<F>::dot_product(
+      layouter.namespace(|| "r1_res_r2"),
+      gadget_config.clone(),
+      &vec![r1_res_ref, r2_ref.clone()],
+      &vec![zero],
+    )
+    .unwrap();
     let r1_res_r2 = r1_res_r2[0].clone();
     // println!("r1_res_r2: {:?}", r1_res_r2);
 
@@ -190,13 +204,13 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
     for i in 0..input.shape()[1] {
       let tmp = input.index_axis(Axis(1), i);
       let input_ci = tmp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
-      let r1_input_i = dot_prod_chip
-        .forward(
-          layouter.namespace(|| format!("r1_input_{}", i)),
-          &vec![input_ci, r1_ref.clone()],
-          &vec![zero],
-        )
-        .unwrap();
+      let r1_input_i = MacChip::<F>::dot_product(
+        layouter.namespace(|| format!("r1_input_{}", i)),
+        gadget_config.clone(),
+        &vec![input_ci, r1_ref.clone()],
+        &vec![zero],
+      )
+      .unwrap();
       r1_input.push(r1_input_i[0].clone());
     }
 
@@ -205,26 +219,26 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
     for i in 0..weight.shape()[0] {
       let tmp = weight.index_axis(Axis(0), i);
       let weight_ci = tmp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
-      let weight_r2_i = dot_prod_chip
-        .forward(
-          layouter.namespace(|| format!("weight_r2_{}", i)),
-          &vec![weight_ci, r2_ref.clone()],
-          &vec![zero],
-        )
-        .unwrap();
+      let weight_r2_i = MacChip::<F>::dot_product(
+        layouter.namespace(|| format!("weight_r2_{}", i)),
+        gadget_config.clone(),
+        &vec![weight_ci, r2_ref.clone()],
+        &vec![zero],
+      )
+      .unwrap();
       weight_r2.push(weight_r2_i[0].clone());
     }
 
     // Compute (r1 * input) * (weight * r2)
     let r1_input_ref = r1_input.iter().collect::<Vec<_>>();
     let weight_r2_ref = weight_r2.iter().collect::<Vec<_>>();
-    let r1_inp_weight_r2 = dot_prod_chip
-      .forward(
-        layouter.namespace(|| "r1_inp_weight_r2"),
-        &vec![r1_input_ref, weight_r2_ref],
-        &vec![zero],
-      )
-      .unwrap();
+    let r1_inp_weight_r2 = MacChip::<F>::dot_product(
+      layouter.namespace(|| "r1_inp_weight_r2"),
+      gadget_config.clone(),
+      &vec![r1_input_ref, weight_r2_ref],
+      &vec![zero],
+    )
+    .unwrap();
 
     let r1_inp_weight_r2 = r1_inp_weight_r2[0].clone();
     // println!("r1_inp_weight_r2: {:?}", r1_inp_weight_r2);
@@ -248,20 +262,78 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
       .unwrap();
 
     let shape = [mm_result.shape()[0], mm_result.shape()[1]];
-    let final_result_flat = if self.config.normalize {
+    // Flooring without an activation also goes through the fused gadget
+    let fused = self.config.normalize && (activation != ActivationType::None || floor);
+    let final_result_flat = if fused {
+      // The division, bias and activation are fused, with one gadget variant per activation
       let mm_flat = mm_result.iter().collect::<Vec<_>>();
-      let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
-      let sf = constants
-        .get(&(gadget_config.scale_factor as i64))
-        .unwrap()
-        .as_ref();
-      let mm_div = var_div_chip
-        .forward(
-          layouter.namespace(|| "mm_div"),
-          &vec![mm_flat],
-          &vec![zero, sf],
-        )
-        .unwrap();
+      let bias = if tensors.len() == 3 {
+        tensors[2].broadcast(IxDyn(&shape)).unwrap().to_owned()
+      } else {
+        Array::from_elem(IxDyn(&shape), Rc::new(zero.clone()))
+      };
+      let bias = bias.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+      let outp = if floor {
+        let bdf_chip = if activation == ActivationType::Relu {
+          BiasDivFloorRelu6Chip::<F>::construct_relu(gadget_config.clone())
+        } else {
+          BiasDivFloorRelu6Chip::<F>::construct(gadget_config.clone())
+        };
+        bdf_chip
+          .forward(
+            layouter.namespace(|| "mm_bias_div_floor_act"),
+            &vec![mm_flat, bias],
+            &vec![zero],
+          )
+          .unwrap()
+      } else {
+        let bdr_chip = if activation == ActivationType::Relu {
+          BiasDivRoundRelu6Chip::<F>::construct_relu(gadget_config.clone())
+        } else {
+          BiasDivRoundRelu6Chip::<F>::construct(gadget_config.clone())
+        };
+        bdr_chip
+          .forward(
+            layouter.namespace(|| "mm_bias_div_act"),
+            &vec![mm_flat, bias],
+            &vec![zero],
+          )
+          .unwrap()
+      };
+
+      // The outputs are interleaved [(activated, div'd), ...]
+      let skip = if activation == ActivationType::None {
+        1
+      } else {
+        0
+      };
+      outp
+        .into_iter()
+        .skip(skip)
+        .step_by(2)
+        .map(|x| Rc::new(x))
+        .collect::<Vec<_>>()
+    } else if self.config.normalize {
+      let mm_flat = mm_result.iter().collect::<Vec<_>>();
+      let mm_div = if gadget_config.used_gadgets.contains(&GadgetType::Shift) {
+        let shift_chip = ShiftChip::<F>::construct(gadget_config.clone());
+        shift_chip
+          .forward(layouter.namespace(|| "mm_div"), &vec![mm_flat], &vec![zero])
+          .unwrap()
+      } else {
+        let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+        let sf = constants
+          .get(&(gadget_config.scale_factor as i64))
+          .unwrap()
+          .as_ref();
+        var_div_chip
+          .forward(
+            layouter.namespace(|| "mm_div"),
+            &vec![mm_flat],
+            &vec![zero, sf],
+          )
+          .unwrap()
+      };
 
       let mm_div = if tensors.len() == 3 {
         let bias = tensors[2].broadcast(shape.clone()).unwrap();
@@ -280,19 +352,6 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
         mm_div
       };
 
-      let mm_div = if activation == ActivationType::Relu {
-        let relu_chip = ReluChip::<F>::construct(gadget_config.clone());
-        let mm_div = mm_div.iter().collect::<Vec<_>>();
-        let vec_inputs = vec![mm_div];
-        relu_chip
-          .forward(layouter.namespace(|| "relu"), &vec_inputs, &vec![zero])
-          .unwrap()
-      } else if activation == ActivationType::None {
-        mm_div
-      } else {
-        panic!("Unsupported activation type");
-      };
-
       mm_div.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>()
     } else {
       mm_result
@@ -309,16 +368,24 @@ impl<F: PrimeField> Layer<F> for FullyConnectedChip<F> {
 impl<F: PrimeField> GadgetConsumer for FullyConnectedChip<F> {
   fn used_gadgets(&self, layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
     let activation = self.get_activation(&layer_params);
+    let floor = self.get_floor(&layer_params);
     let mut outp = vec![
       GadgetType::Adder,
       GadgetType::AddPairs,
       GadgetType::DotProduct,
+      GadgetType::Shift,
       GadgetType::VarDivRound,
       GadgetType::InputLookup,
     ];
-    match activation {
-      ActivationType::Relu => outp.push(GadgetType::Relu),
-      ActivationType::None => (),
+    match (activation, floor) {
+      (ActivationType::Relu, false) => outp.push(GadgetType::BiasDivRoundRelu),
+      (ActivationType::Relu6, false) => outp.push(GadgetType::BiasDivRoundRelu6),
+      (ActivationType::None, false) => (),
+      (ActivationType::Relu, true) => outp.push(GadgetType::BiasDivFloorRelu),
+      // Without an activation, the div'd outputs of the ReLU6 variant are used
+      (ActivationType::Relu6 | ActivationType::None, true) => {
+        outp.push(GadgetType::BiasDivFloorRelu6)
+      }
       _ => panic!("Unsupported activation type"),
     }
     outp
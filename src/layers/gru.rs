@@ -0,0 +1,269 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, Axis, IxDyn};
+
+use crate::gadgets::{
+  add_pairs::AddPairsChip,
+  dot_prod::DotProductChip,
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  mul_pairs::MulPairsChip,
+  nonlinear::{logistic::LogisticGadgetChip, tanh::TanhGadgetChip},
+  sub_pairs::SubPairsChip,
+  var_div::VarDivRoundChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// Inputs: [x (batch, time, in), kernel (3 * hidden, in), recurrent kernel (3 * hidden, hidden),
+// optional bias (3 * hidden)], with the gates in the Keras order (update, reset, candidate).
+// Params: [return_sequences]. The initial state is zero and the reset gate is applied before the
+// recurrent kernel (reset_after = False):
+//   z = sigmoid(W_z x + U_z h + b_z), r = sigmoid(W_r x + U_r h + b_r)
+//   h' = tanh(W_h x + U_h (r * h) + b_h), h = z * h + (1 - z) * h' = h' + z * (h - h')
+#[derive(Clone, Debug)]
+pub struct GRUChip {}
+
+impl GRUChip {
+  // Computes the rows of the matrix times the vector, rescaled by the scale factor
+  fn mat_vec<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    mat: &Vec<Vec<&AssignedCell<F, F>>>,
+    inp: &Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    sf: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
+    let mut dot_prods = vec![];
+    for row in mat.iter() {
+      let outp = dot_prod_chip.forward(
+        layouter.namespace(|| "gru dot prod"),
+        &vec![row.clone(), inp.clone()],
+        &vec![zero],
+      )?;
+      dot_prods.push(outp[0].clone());
+    }
+
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config);
+    var_div_chip.forward(
+      layouter.namespace(|| "gru mat vec rescale"),
+      &vec![dot_prods.iter().collect()],
+      &vec![zero, sf],
+    )
+  }
+
+  // Elementwise product, rescaled by the scale factor
+  fn mul_rescale<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    b: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    sf: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let prod =
+      mul_pairs_chip.forward(layouter.namespace(|| "gru mul"), &vec![a, b], &vec![zero])?;
+
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config);
+    var_div_chip.forward(
+      layouter.namespace(|| "gru mul rescale"),
+      &vec![prod.iter().collect()],
+      &vec![zero, sf],
+    )
+  }
+
+  fn add<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    b: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config);
+    add_pairs_chip.forward(layouter.namespace(|| "gru add"), &vec![a, b], &vec![zero])
+  }
+}
+
+impl<F: PrimeField> Layer<F> for GRUChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let kernel = &tensors[1];
+    let recurrent_kernel = &tensors[2];
+    let return_sequences = layer_config.layer_params.get(0).cloned().unwrap_or(0) == 1;
+
+    assert_eq!(inp.ndim(), 3);
+    let (batch_size, time_steps) = (inp.shape()[0], inp.shape()[1]);
+    let hidden = recurrent_kernel.shape()[1];
+    assert_eq!(kernel.shape(), &[3 * hidden, inp.shape()[2]]);
+    assert_eq!(recurrent_kernel.shape(), &[3 * hidden, hidden]);
+
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+    let bias = if tensors.len() > 3 {
+      tensors[3].iter().map(|x| x.as_ref()).collect::<Vec<_>>()
+    } else {
+      vec![zero; 3 * hidden]
+    };
+
+    let rows = |mat: &AssignedTensor<F>, start: usize, end: usize| {
+      (start..end)
+        .map(|i| {
+          mat
+            .index_axis(Axis(0), i)
+            .iter()
+            .map(|x| x.as_ref())
+            .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+    };
+    let kernel_rows = rows(kernel, 0, 3 * hidden);
+    let recurrent_zr = rows(recurrent_kernel, 0, 2 * hidden);
+    let recurrent_h = rows(recurrent_kernel, 2 * hidden, 3 * hidden);
+
+    let logistic_chip = LogisticGadgetChip::<F>::construct(gadget_config.clone());
+    let tanh_chip = TanhGadgetChip::<F>::construct(gadget_config.clone());
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config.clone());
+
+    let mut outp = vec![];
+    for b in 0..batch_size {
+      let mut h: Vec<AssignedCell<F, F>> = vec![zero.clone(); hidden];
+      for t in 0..time_steps {
+        let x = (0..inp.shape()[2])
+          .map(|i| inp[[b, t, i]].as_ref())
+          .collect::<Vec<_>>();
+
+        let wx = Self::mat_vec(
+          layouter.namespace(|| "gru wx"),
+          &kernel_rows,
+          &x,
+          zero,
+          sf,
+          gadget_config.clone(),
+        )?;
+        let wx_b = Self::add(
+          layouter.namespace(|| "gru wx bias"),
+          wx.iter().collect(),
+          bias.clone(),
+          zero,
+          gadget_config.clone(),
+        )?;
+
+        let uh_zr = Self::mat_vec(
+          layouter.namespace(|| "gru uh zr"),
+          &recurrent_zr,
+          &h.iter().collect(),
+          zero,
+          sf,
+          gadget_config.clone(),
+        )?;
+        let zr = Self::add(
+          layouter.namespace(|| "gru zr"),
+          wx_b[0..2 * hidden].iter().collect(),
+          uh_zr.iter().collect(),
+          zero,
+          gadget_config.clone(),
+        )?;
+        let zr = logistic_chip.forward(
+          layouter.namespace(|| "gru zr sigmoid"),
+          &vec![zr.iter().collect()],
+          &vec![zero],
+        )?;
+        let (z, r) = zr.split_at(hidden);
+
+        let rh = Self::mul_rescale(
+          layouter.namespace(|| "gru rh"),
+          r.iter().collect(),
+          h.iter().collect(),
+          zero,
+          sf,
+          gadget_config.clone(),
+        )?;
+        let uh_h = Self::mat_vec(
+          layouter.namespace(|| "gru uh h"),
+          &recurrent_h,
+          &rh.iter().collect(),
+          zero,
+          sf,
+          gadget_config.clone(),
+        )?;
+        let cand = Self::add(
+          layouter.namespace(|| "gru candidate"),
+          wx_b[2 * hidden..].iter().collect(),
+          uh_h.iter().collect(),
+          zero,
+          gadget_config.clone(),
+        )?;
+        let cand = tanh_chip.forward(
+          layouter.namespace(|| "gru candidate tanh"),
+          &vec![cand.iter().collect()],
+          &vec![zero],
+        )?;
+
+        let diff = sub_pairs_chip.forward(
+          layouter.namespace(|| "gru diff"),
+          &vec![h.iter().collect(), cand.iter().collect()],
+          &vec![zero],
+        )?;
+        let z_diff = Self::mul_rescale(
+          layouter.namespace(|| "gru z diff"),
+          z.iter().collect(),
+          diff.iter().collect(),
+          zero,
+          sf,
+          gadget_config.clone(),
+        )?;
+        h = Self::add(
+          layouter.namespace(|| "gru h"),
+          cand.iter().collect(),
+          z_diff.iter().collect(),
+          zero,
+          gadget_config.clone(),
+        )?;
+
+        if return_sequences || t == time_steps - 1 {
+          outp.extend(h.iter().map(|x| Rc::new(x.clone())));
+        }
+      }
+    }
+
+    let out_shape = if return_sequences {
+      vec![batch_size, time_steps, hidden]
+    } else {
+      vec![batch_size, hidden]
+    };
+    let outp = Array::from_shape_vec(IxDyn(&out_shape), outp).unwrap();
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for GRUChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::AddPairs,
+      GadgetType::DotProduct,
+      GadgetType::Logistic,
+      GadgetType::MulPairs,
+      GadgetType::SubPairs,
+      GadgetType::Tanh,
+      GadgetType::VarDivRound,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::Axis;
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  mul_pairs::MulPairsChip,
+};
+
+use super::{
+  arg_max::ArgMaxChip,
+  layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+};
+
+// Outputs the one-hot vector of the maximum along an axis, scaled by the scale factor. Ties go to
+// the first index. Params: [axis], which defaults to the last axis.
+pub struct HardmaxChip {}
+
+impl<F: PrimeField> Layer<F> for HardmaxChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let axis = layer_config.layer_params.get(0).cloned().unwrap_or(-1);
+    let axis = (if axis < 0 {
+      axis + inp.ndim() as i64
+    } else {
+      axis
+    }) as usize;
+
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+
+    let mut slices = vec![];
+    for lane in inp.lanes(Axis(axis)) {
+      slices.push(lane.iter().map(|x| x.as_ref()).collect::<Vec<_>>());
+    }
+    let one_hots = ArgMaxChip::one_hots(
+      layouter.namespace(|| "hardmax one hots"),
+      &slices,
+      constants,
+      gadget_config.clone(),
+    )?;
+
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let flat_one_hots = one_hots.iter().flatten().collect::<Vec<_>>();
+    let scaled = mul_pairs_chip.forward(
+      layouter.namespace(|| "hardmax scale"),
+      &vec![flat_one_hots, vec![sf; inp.len()]],
+      &vec![zero],
+    )?;
+
+    // Write the slices back in the order they were taken
+    let n = inp.shape()[axis];
+    let mut outp = inp.clone();
+    for (mut lane, vals) in outp.lanes_mut(Axis(axis)).into_iter().zip(scaled.chunks(n)) {
+      for (cell, val) in lane.iter_mut().zip(vals.iter()) {
+        *cell = Rc::new(val.clone());
+      }
+    }
+
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for HardmaxChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::DotProduct,
+      GadgetType::Max,
+      GadgetType::MulPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,104 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  adder::AdderChip,
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  max::MaxChip,
+  mul_pairs::MulPairsChip,
+  sqrt_big::SqrtBigChip,
+  square::SquareGadgetChip,
+  var_div_big3::VarDivRoundBig3Chip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// x / sqrt(sum x^2) over the last axis. The sum of squares is at scale sf^2, so its square root is
+// at scale sf. The norm is clamped to at least one so that all zero inputs map to zero.
+#[derive(Clone, Debug)]
+pub struct L2NormChip {}
+
+impl<F: PrimeField> Layer<F> for L2NormChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let zero = constants.get(&0).unwrap().as_ref();
+    let one = constants.get(&1).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+
+    let square_chip = SquareGadgetChip::<F>::construct(gadget_config.clone());
+    let adder_chip = AdderChip::<F>::construct(gadget_config.clone());
+    let sqrt_big_chip = SqrtBigChip::<F>::construct(gadget_config.clone());
+    let max_chip = MaxChip::<F>::construct(gadget_config.clone());
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let var_div_big_chip = VarDivRoundBig3Chip::<F>::construct(gadget_config.clone());
+
+    let dim = inp.shape()[inp.ndim() - 1];
+    let inp_flat = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+
+    let mut outp = vec![];
+    for row in inp_flat.chunks(dim) {
+      let row = row.to_vec();
+      let sq = square_chip.forward(
+        layouter.namespace(|| "l2 norm square"),
+        &vec![row.clone()],
+        &vec![zero],
+      )?;
+      let sum = adder_chip.forward(
+        layouter.namespace(|| "l2 norm sum"),
+        &vec![sq.iter().collect()],
+        &vec![zero],
+      )?;
+      let norm = sqrt_big_chip.forward(
+        layouter.namespace(|| "l2 norm sqrt"),
+        &vec![vec![&sum[0]]],
+        &vec![zero],
+      )?;
+      let norm = max_chip.forward(
+        layouter.namespace(|| "l2 norm clamp"),
+        &vec![vec![&norm[0], one]],
+        &vec![zero],
+      )?;
+
+      let scaled = mul_pairs_chip.forward(
+        layouter.namespace(|| "l2 norm scale"),
+        &vec![row, vec![sf; dim]],
+        &vec![zero],
+      )?;
+      let dived = var_div_big_chip.forward(
+        layouter.namespace(|| "l2 norm div"),
+        &vec![scaled.iter().collect()],
+        &vec![zero, &norm[0]],
+      )?;
+      outp.extend(dived.into_iter().map(|x| Rc::new(x)));
+    }
+
+    let outp = Array::from_shape_vec(IxDyn(inp.shape()), outp).unwrap();
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for L2NormChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::Max,
+      GadgetType::MulPairs,
+      GadgetType::Square,
+      GadgetType::SqrtBig,
+      GadgetType::VarDivRoundBig3,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -12,39 +12,89 @@ use crate::gadgets::gadget::{GadgetConfig, GadgetType};
 #[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
 pub enum LayerType {
   Add,
+  Abs,
+  ArgMax,
+  AssertClose,
   AvgPool2D,
+  BatchToSpace,
   BatchMatMul,
   Broadcast,
   Concatenation,
+  Clip,
+  Conv1D,
   Conv2D,
+  Conv3D,
+  Cos,
+  CumSum,
+  CustomNonlinear,
+  DepthToSpace,
+  Div,
   DivVar,
   DivFixed,
+  Einsum,
+  Equal,
+  Exp,
+  Flatten,
   FullyConnected,
+  Gelu,
+  Greater,
+  GRU,
+  Hardmax,
+  L2Norm,
+  Less,
+  Log,
+  LogSoftmax,
+  LogicalAnd,
+  LogicalNot,
+  LogicalOr,
   Logistic,
+  LRN,
   MaskNegInf,
+  Max,
   MaxPool2D,
   Mean,
+  Min,
+  Mish,
   Mul,
+  MultiHeadAttention,
+  NonMaxSuppression,
   #[default]
   Noop,
   Pack,
   Pad,
+  PiecewiseLinear,
   Pow,
   Permute,
+  Reciprocal,
+  ReduceMax,
+  ReduceSum,
+  Requantize,
   Reshape,
+  ResizeBilinear,
   ResizeNN,
+  ROIAlign,
   Rotate,
   Rsqrt,
+  ScaledDotProductAttention,
+  Select,
+  Sin,
   Slice,
   Softmax,
+  Softplus,
+  SpaceToBatch,
+  SpaceToDepth,
   Split,
   Sqrt,
   Square,
   SquaredDifference,
   Sub,
   Tanh,
+  Tile,
+  TopK,
   Transpose,
+  Unpack,
   Update,
+  Upsample,
 }
 
 // NOTE: This is the same order as the TFLite schema
@@ -0,0 +1,126 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::Axis;
+
+use crate::gadgets::{
+  adder::AdderChip,
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  max::MaxChip,
+  nonlinear::{exp::ExpGadgetChip, ln::LnGadgetChip},
+  sub_pairs::SubPairsChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// log_softmax(x) = (x - max) - ln(sum(exp(x - max))). Params: [axis] (default: the last axis). The
+// sum of the exps is a fixed point value, so it must be in the range of the Ln table.
+#[derive(Clone, Debug)]
+pub struct LogSoftmaxChip {}
+
+impl LogSoftmaxChip {
+  pub fn log_softmax_flat<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    constants: &HashMap<i64, CellRc<F>>,
+    inp_flat: Vec<&AssignedCell<F, F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let exp_chip = ExpGadgetChip::<F>::construct(gadget_config.clone());
+    let ln_chip = LnGadgetChip::<F>::construct(gadget_config.clone());
+    let adder_chip = AdderChip::<F>::construct(gadget_config.clone());
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config.clone());
+    let max_chip = MaxChip::<F>::construct(gadget_config.clone());
+
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    // Subtract the max
+    let max = max_chip.forward(
+      layouter.namespace(|| "max"),
+      &vec![inp_flat.clone()],
+      &vec![zero],
+    )?;
+    let max_flat = vec![&max[0]; inp_flat.len()];
+    let sub = sub_pairs_chip.forward(
+      layouter.namespace(|| "sub"),
+      &vec![inp_flat, max_flat],
+      &vec![zero],
+    )?;
+    let sub = sub.iter().collect::<Vec<_>>();
+
+    // ln of the sum of the exps
+    let exp_slice = exp_chip.forward(
+      layouter.namespace(|| "exp"),
+      &vec![sub.clone()],
+      &vec![zero],
+    )?;
+    let sum = adder_chip.forward(
+      layouter.namespace(|| "sum"),
+      &vec![exp_slice.iter().collect()],
+      &vec![zero],
+    )?;
+    let ln_sum = ln_chip.forward(
+      layouter.namespace(|| "ln"),
+      &vec![vec![&sum[0]]],
+      &vec![zero],
+    )?;
+
+    let ln_flat = vec![&ln_sum[0]; sub.len()];
+    sub_pairs_chip.forward(
+      layouter.namespace(|| "sub ln"),
+      &vec![sub, ln_flat],
+      &vec![zero],
+    )
+  }
+}
+
+impl<F: PrimeField> Layer<F> for LogSoftmaxChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let axis = layer_config.layer_params.get(0).cloned().unwrap_or(-1);
+    let axis = if axis < 0 {
+      axis + inp.ndim() as i64
+    } else {
+      axis
+    };
+
+    let mut outp = inp.clone();
+    for (i, mut lane) in outp.lanes_mut(Axis(axis as usize)).into_iter().enumerate() {
+      let inp_flat = lane.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+      let out = Self::log_softmax_flat(
+        layouter.namespace(|| format!("log softmax {}", i)),
+        constants,
+        inp_flat,
+        gadget_config.clone(),
+      )?;
+      for (cell, val) in lane.iter_mut().zip(out.into_iter()) {
+        *cell = Rc::new(val);
+      }
+    }
+
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for LogSoftmaxChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Exp,
+      GadgetType::Ln,
+      GadgetType::Adder,
+      GadgetType::Max,
+      GadgetType::SubPairs,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,163 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  add_pairs::AddPairsChip,
+  adder::AdderChip,
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  mul_pairs::MulPairsChip,
+  nonlinear::pow::PowGadgetChip,
+  square::SquareGadgetChip,
+  var_div::VarDivRoundChip,
+  var_div_big3::VarDivRoundBig3Chip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// Local response normalization over the last axis:
+//   out = x / (bias + alpha * sum_{|j - i| <= radius} x_j^2)^beta
+// Params: [radius, bias num, bias den, alpha num, alpha den, beta num, beta den]. The power is
+// computed with the Pow lookup, so its exponent (-beta) must match any fractional Pow in the model.
+#[derive(Clone, Debug)]
+pub struct LRNChip {}
+
+impl LRNChip {
+  pub fn pow_exponent(layer_params: &Vec<i64>) -> f64 {
+    -(layer_params[5] as f64) / (layer_params[6] as f64)
+  }
+}
+
+impl<F: PrimeField> Layer<F> for LRNChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let params = &layer_config.layer_params;
+    let radius = params[0] as usize;
+    let (bias_num, bias_den) = (params[1], params[2]);
+    let (alpha_num, alpha_den) = (params[3], params[4]);
+    assert!(bias_num > 0 && bias_den > 0 && alpha_num >= 0 && alpha_den > 0);
+
+    let sf = gadget_config.scale_factor as i64;
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf_cell = constants.get(&sf).unwrap().as_ref();
+
+    // The sum of squares is at scale sf^2, so the denominator at scale sf is
+    //   (alpha num * bias den * sum + bias num * alpha den * sf^2) / (alpha den * bias den * sf)
+    let (mul_cell, add_cell, div_cell) = layouter.assign_region(
+      || "lrn coefficients",
+      |mut region| {
+        let vals = [
+          alpha_num * bias_den,
+          bias_num * alpha_den * sf * sf,
+          alpha_den * bias_den * sf,
+        ];
+        let cells = vals
+          .iter()
+          .enumerate()
+          .map(|(i, val)| {
+            region.assign_fixed(
+              || format!("lrn coefficient {}", i),
+              gadget_config.fixed_columns[0],
+              i,
+              || Value::known(F::from(*val as u64)),
+            )
+          })
+          .collect::<Result<Vec<_>, _>>()?;
+        Ok((cells[0].clone(), cells[1].clone(), cells[2].clone()))
+      },
+    )?;
+
+    let square_chip = SquareGadgetChip::<F>::construct(gadget_config.clone());
+    let adder_chip = AdderChip::<F>::construct(gadget_config.clone());
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config.clone());
+    let var_div_big_chip = VarDivRoundBig3Chip::<F>::construct(gadget_config.clone());
+    let pow_chip = PowGadgetChip::<F>::construct(gadget_config.clone());
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+
+    let depth = inp.shape()[inp.ndim() - 1];
+    let inp_flat = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let squares = square_chip.forward(
+      layouter.namespace(|| "lrn square"),
+      &vec![inp_flat.clone()],
+      &vec![zero],
+    )?;
+
+    let mut sums = vec![];
+    for (row_idx, row) in squares.chunks(depth).enumerate() {
+      for i in 0..depth {
+        let start = i.saturating_sub(radius);
+        let end = (i + radius + 1).min(depth);
+        let sum = adder_chip.forward(
+          layouter.namespace(|| format!("lrn window sum {} {}", row_idx, i)),
+          &vec![row[start..end].iter().collect()],
+          &vec![zero],
+        )?;
+        sums.push(sum[0].clone());
+      }
+    }
+
+    let scaled = mul_pairs_chip.forward(
+      layouter.namespace(|| "lrn alpha"),
+      &vec![sums.iter().collect(), vec![&mul_cell; sums.len()]],
+      &vec![zero],
+    )?;
+    let shifted = add_pairs_chip.forward(
+      layouter.namespace(|| "lrn bias"),
+      &vec![scaled.iter().collect(), vec![&add_cell; scaled.len()]],
+      &vec![zero],
+    )?;
+    let denom = var_div_big_chip.forward(
+      layouter.namespace(|| "lrn denominator"),
+      &vec![shifted.iter().collect()],
+      &vec![zero, &div_cell],
+    )?;
+
+    let inv_pow = pow_chip.forward(
+      layouter.namespace(|| "lrn pow"),
+      &vec![denom.iter().collect()],
+      &vec![zero],
+    )?;
+    let prod = mul_pairs_chip.forward(
+      layouter.namespace(|| "lrn mul"),
+      &vec![inp_flat, inv_pow.iter().collect()],
+      &vec![zero],
+    )?;
+    let out = var_div_chip.forward(
+      layouter.namespace(|| "lrn rescale"),
+      &vec![prod.iter().collect()],
+      &vec![zero, sf_cell],
+    )?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for LRNChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::AddPairs,
+      GadgetType::MulPairs,
+      GadgetType::Pow,
+      GadgetType::Square,
+      GadgetType::VarDivRound,
+      GadgetType::VarDivRoundBig3,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -5,71 +5,48 @@ use halo2_proofs::{
   halo2curves::ff::PrimeField,
   plonk::Error,
 };
-use ndarray::{Array, Axis, IxDyn};
+use ndarray::{Array, IxDyn};
 
 use crate::gadgets::gadget::{GadgetConfig, GadgetType};
 
 use super::{
   averager::Averager,
   layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+  reduce_sum::ReduceSumChip,
 };
 
+// Params: the axes to average over, in any order and possibly negative. The averages are reshaped
+// to the output shape, which may keep or drop the reduced axes (and other unit axes).
 pub struct MeanChip {}
 
 impl MeanChip {
-  pub fn get_keep_axis(&self, layer_config: &LayerConfig) -> usize {
+  pub fn get_axes(&self, layer_config: &LayerConfig) -> Vec<usize> {
     let inp_shape = &layer_config.inp_shapes[0];
     let out_shape = &layer_config.out_shapes[0];
-    assert_eq!(inp_shape[0], 1);
-    assert_eq!(out_shape[0], 1);
-
-    // Skip the batch axis
-    let mut keep_axes = (1..inp_shape.len()).collect::<Vec<_>>();
-    for mean_axis in layer_config.layer_params.iter() {
-      keep_axes.retain(|&x| x != *mean_axis as usize);
-    }
-    assert_eq!(keep_axes.len(), 1);
-    keep_axes[0]
-
-    /*
-    let mut num_same = 0;
-    let mut keep_axis: i64 = -1;
-    for i in 1..inp_shape.len() {
-      if inp_shape[i] == out_shape[i] {
-        keep_axis = i as i64;
-        num_same += 1;
-      }
-    }
+    let ndim = inp_shape.len() as i64;
+    let mut axes = layer_config
+      .layer_params
+      .iter()
+      .map(|axis| (if *axis < 0 { axis + ndim } else { *axis }) as usize)
+      .collect::<Vec<_>>();
+    axes.sort();
+    axes.dedup();
 
-    if keep_axis == -1 {
-      panic!("All axes are different");
-    }
-    if num_same > 1 {
-      panic!("More than one axis is the same");
-    }
-    keep_axis as usize
-    */
+    let num_outputs = inp_shape
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| !axes.contains(i))
+      .map(|(_, x)| *x)
+      .product::<usize>();
+    assert_eq!(out_shape.iter().product::<usize>(), num_outputs);
+    axes
   }
 }
 
 impl<F: PrimeField> Averager<F> for MeanChip {
   fn splat(&self, input: &AssignedTensor<F>, layer_config: &LayerConfig) -> Vec<Vec<CellRc<F>>> {
-    // Only support batch size = 1
-    assert_eq!(input.shape()[0], 1);
-    // Only support batch + 2D, summing over one axis
-    // assert_eq!(input.shape().len(), 3);
-    let keep_axis = self.get_keep_axis(layer_config);
-
-    let mut splat = vec![];
-    for i in 0..input.shape()[keep_axis] {
-      let mut tmp = vec![];
-      for x in input.index_axis(Axis(keep_axis), i).iter() {
-        tmp.push(x.clone());
-      }
-      splat.push(tmp);
-    }
-
-    splat
+    let axes = self.get_axes(layer_config);
+    ReduceSumChip {}.splat(input, &axes)
   }
 
   fn get_div_val(
@@ -80,13 +57,8 @@ impl<F: PrimeField> Averager<F> for MeanChip {
     layer_config: &LayerConfig,
   ) -> Result<AssignedCell<F, F>, Error> {
     let inp = &tensors[0];
-    let keep_axis = self.get_keep_axis(layer_config);
-    let mut div = 1;
-    for i in 0..inp.shape().len() {
-      if i != keep_axis {
-        div *= inp.shape()[i];
-      }
-    }
+    let axes = self.get_axes(layer_config);
+    let div = axes.iter().map(|i| inp.shape()[*i]).product::<usize>();
 
     let div = F::from(div as u64);
     // FIXME: put this in the fixed column
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  mul_pairs::MulPairsChip,
+  nonlinear::{softplus::SoftplusGadgetChip, tanh::TanhGadgetChip},
+  var_div::VarDivRoundChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// x * tanh(softplus(x))
+#[derive(Clone, Debug)]
+pub struct MishChip {}
+
+impl<F: PrimeField> Layer<F> for MishChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+
+    let softplus_chip = SoftplusGadgetChip::<F>::construct(gadget_config.clone());
+    let softplus = softplus_chip.forward(
+      layouter.namespace(|| "mish softplus"),
+      &vec![inp_vec.clone()],
+      &vec![zero],
+    )?;
+
+    let tanh_chip = TanhGadgetChip::<F>::construct(gadget_config.clone());
+    let tanh = tanh_chip.forward(
+      layouter.namespace(|| "mish tanh"),
+      &vec![softplus.iter().collect()],
+      &vec![zero],
+    )?;
+
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let out = mul_pairs_chip.forward(
+      layouter.namespace(|| "mish mul"),
+      &vec![inp_vec, tanh.iter().collect()],
+      &vec![zero],
+    )?;
+
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let out = var_div_chip.forward(
+      layouter.namespace(|| "mish div"),
+      &vec![out.iter().collect()],
+      &vec![zero, sf],
+    )?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for MishChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Softplus,
+      GadgetType::Tanh,
+      GadgetType::MulPairs,
+      GadgetType::VarDivRound,
+      GadgetType::InputLookup,
+    ]
+  }
+}
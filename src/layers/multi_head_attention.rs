@@ -0,0 +1,246 @@
+use std::{collections::HashMap, marker::PhantomData, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{s, Array, Axis, IxDyn};
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  var_div::VarDivRoundChip,
+};
+
+use super::{
+  fully_connected::{FullyConnectedChip, FullyConnectedConfig},
+  layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+  softmax::SoftmaxChip,
+};
+
+// Inputs: [query (seq_q, d), key and value (seq_k, d), w_q, w_k, w_v, w_o, optional b_q, b_k, b_v,
+// b_o], with the weights as [out, in] and an optional leading batch dimension of 1.
+// Params: [num_heads, optional mask], where the mask is encoded as for Softmax (ndim, shape, then
+// the values, 1 = masked) and is broadcast to (seq_q, seq_k).
+#[derive(Clone, Debug)]
+pub struct MultiHeadAttentionChip {}
+
+impl MultiHeadAttentionChip {
+  pub fn decode_mask(layer_params: &[i64], shape: &[usize]) -> Array<i64, IxDyn> {
+    if layer_params.len() == 0 {
+      return Array::zeros(IxDyn(shape));
+    }
+    let mask_ndim = layer_params[0] as usize;
+    let mask_shape = layer_params[1..mask_ndim + 1]
+      .iter()
+      .map(|x| *x as usize)
+      .collect::<Vec<_>>();
+    let mask = layer_params[mask_ndim + 1..].to_vec();
+    let mask = Array::from_shape_vec(IxDyn(&mask_shape), mask).unwrap();
+    mask.broadcast(IxDyn(shape)).unwrap().to_owned()
+  }
+
+  fn matmul<F: PrimeField>(
+    layouter: impl Layouter<F>,
+    tensors: Vec<AssignedTensor<F>>,
+    transpose_b: bool,
+    normalize: bool,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<AssignedTensor<F>, Error> {
+    let fc_chip = FullyConnectedChip::<F> {
+      _marker: PhantomData,
+      config: FullyConnectedConfig::construct(normalize),
+    };
+    let fc_config = LayerConfig {
+      layer_params: vec![0, transpose_b as i64],
+      ..LayerConfig::default()
+    };
+    let outp = fc_chip.forward(layouter, &tensors, constants, gadget_config, &fc_config)?;
+    Ok(outp[0].clone())
+  }
+
+  // softmax(q k^T / sqrt(d) + mask) v for q (seq_q, d), k (seq_k, d), v (seq_k, d_v). The scores
+  // are divided by sf * sqrt(d) at once instead of being rescaled and then scaled.
+  pub fn scaled_dot_product_attention<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    q: &AssignedTensor<F>,
+    k: &AssignedTensor<F>,
+    v: &AssignedTensor<F>,
+    mask: &Array<i64, IxDyn>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<AssignedTensor<F>, Error> {
+    let (seq_q, d) = (q.shape()[0], q.shape()[1]);
+    let seq_k = k.shape()[0];
+    assert_eq!(k.shape()[1], d);
+    assert_eq!(v.shape()[0], seq_k);
+    assert_eq!(mask.shape(), &[seq_q, seq_k]);
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let scores = Self::matmul(
+      layouter.namespace(|| "attention scores"),
+      vec![q.clone(), k.clone()],
+      true,
+      false,
+      constants,
+      gadget_config.clone(),
+    )?;
+
+    let div = (gadget_config.scale_factor as f64 * (d as f64).sqrt()).round() as u64;
+    let div = layouter.assign_region(
+      || "attention scale",
+      |mut region| {
+        region.assign_fixed(
+          || "attention scale",
+          gadget_config.fixed_columns[0],
+          0,
+          || Value::known(F::from(div)),
+        )
+      },
+    )?;
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let scores = var_div_chip.forward(
+      layouter.namespace(|| "attention scores scale"),
+      &vec![scores.iter().map(|x| x.as_ref()).collect()],
+      &vec![zero, &div],
+    )?;
+
+    let mut probs = vec![];
+    for i in 0..seq_q {
+      let row = scores[i * seq_k..(i + 1) * seq_k]
+        .iter()
+        .collect::<Vec<_>>();
+      let mask_row = mask.slice(s![i, ..]).iter().cloned().collect::<Vec<_>>();
+      let row = SoftmaxChip::softmax_flat(
+        layouter.namespace(|| format!("attention softmax {}", i)),
+        constants,
+        row,
+        gadget_config.clone(),
+        &mask_row,
+      )?;
+      probs.extend(row.into_iter().map(|x| Rc::new(x)));
+    }
+    let probs = Array::from_shape_vec(IxDyn(&[seq_q, seq_k]), probs).unwrap();
+
+    Self::matmul(
+      layouter.namespace(|| "attention values"),
+      vec![probs, v.clone()],
+      false,
+      true,
+      constants,
+      gadget_config,
+    )
+  }
+}
+
+impl<F: PrimeField> Layer<F> for MultiHeadAttentionChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    assert!(tensors.len() == 6 || tensors.len() == 10);
+    let squeeze = |x: &AssignedTensor<F>| {
+      if x.ndim() == 3 {
+        assert_eq!(x.shape()[0], 1);
+        x.index_axis(Axis(0), 0).to_owned()
+      } else {
+        x.clone()
+      }
+    };
+    let query = squeeze(&tensors[0]);
+    let key_value = squeeze(&tensors[1]);
+    let num_heads = layer_config.layer_params[0] as usize;
+    let (seq_q, d) = (query.shape()[0], query.shape()[1]);
+    let seq_k = key_value.shape()[0];
+    assert_eq!(d % num_heads, 0);
+    let head_dim = d / num_heads;
+    let mask = Self::decode_mask(&layer_config.layer_params[1..], &[seq_q, seq_k]);
+
+    // The projections of the whole sequences are done at once, and the heads are views of them
+    let fc_inputs = |inp: &AssignedTensor<F>, idx: usize| {
+      let mut fc_tensors = vec![inp.clone(), tensors[idx].clone()];
+      if tensors.len() == 10 {
+        fc_tensors.push(tensors[idx + 4].clone());
+      }
+      fc_tensors
+    };
+    let q = Self::matmul(
+      layouter.namespace(|| "mha q"),
+      fc_inputs(&query, 2),
+      true,
+      true,
+      constants,
+      gadget_config.clone(),
+    )?;
+    let k = Self::matmul(
+      layouter.namespace(|| "mha k"),
+      fc_inputs(&key_value, 3),
+      true,
+      true,
+      constants,
+      gadget_config.clone(),
+    )?;
+    let v = Self::matmul(
+      layouter.namespace(|| "mha v"),
+      fc_inputs(&key_value, 4),
+      true,
+      true,
+      constants,
+      gadget_config.clone(),
+    )?;
+
+    let mut heads = vec![];
+    for h in 0..num_heads {
+      let cols = h * head_dim..(h + 1) * head_dim;
+      let head = Self::scaled_dot_product_attention(
+        layouter.namespace(|| format!("mha head {}", h)),
+        &q.slice(s![.., cols.clone()]).to_owned().into_dyn(),
+        &k.slice(s![.., cols.clone()]).to_owned().into_dyn(),
+        &v.slice(s![.., cols]).to_owned().into_dyn(),
+        &mask,
+        constants,
+        gadget_config.clone(),
+      )?;
+      heads.push(head);
+    }
+    let concat = Array::from_shape_fn(IxDyn(&[seq_q, d]), |idx| {
+      heads[idx[1] / head_dim][[idx[0], idx[1] % head_dim]].clone()
+    });
+
+    let outp = Self::matmul(
+      layouter.namespace(|| "mha out"),
+      fc_inputs(&concat, 5),
+      true,
+      true,
+      constants,
+      gadget_config.clone(),
+    )?;
+    let outp = if tensors[0].ndim() == 3 {
+      outp.into_shape(IxDyn(&[1, seq_q, d])).unwrap()
+    } else {
+      outp
+    };
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for MultiHeadAttentionChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::AddPairs,
+      GadgetType::DotProduct,
+      GadgetType::Exp,
+      GadgetType::Max,
+      GadgetType::SubPairs,
+      GadgetType::VarDivRound,
+      GadgetType::VarDivRoundBig3,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,582 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    add_pairs::AddPairsChip,
+    adder::AdderChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    greater_than::GreaterThanChip,
+    mul_pairs::MulPairsChip,
+    sub_pairs::SubPairsChip,
+    var_div::VarDivRoundChip,
+  },
+  utils::helpers::convert_pos_int,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// Greedy non-max suppression. Inputs: [boxes (N, 4) as [y1, x1, y2, x2] with y1 <= y2 and
+// x1 <= x2, scores (N)], with an optional leading batch dimension of 1. Params: [max output size,
+// IoU threshold, score threshold], with the thresholds scaled by the scale factor. Outputs the
+// boxes and scores with the entries that are not selected set to zero.
+//
+// The prover supplies a bit k_i for every box. Box j beats box i if it has a higher score, or an
+// equal score and a lower index. The circuit checks that
+//   k_i = (score_i > score threshold) * (no j with k_j = 1 beats i and has IoU(i, j) > threshold)
+// which has a unique solution (by induction in the order of the scores), the result of the greedy
+// algorithm. A box is selected if k_i = 1 and fewer than max output size kept boxes beat it.
+#[derive(Clone, Debug)]
+pub struct NonMaxSuppressionChip {}
+
+impl NonMaxSuppressionChip {
+  fn gather<'a, T>(vals: &Vec<&'a T>, idxes: &Vec<usize>) -> Vec<&'a T> {
+    idxes.iter().map(|i| vals[*i]).collect()
+  }
+
+  fn gt<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    b: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let greater_than_chip = GreaterThanChip::<F>::construct(gadget_config);
+    greater_than_chip.forward(layouter.namespace(|| "nms gt"), &vec![a, b], &vec![zero])
+  }
+
+  fn add<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    b: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let add_pairs_chip = AddPairsChip::<F>::construct(gadget_config);
+    add_pairs_chip.forward(layouter.namespace(|| "nms add"), &vec![a, b], &vec![zero])
+  }
+
+  fn sub<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    b: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config);
+    sub_pairs_chip.forward(layouter.namespace(|| "nms sub"), &vec![a, b], &vec![zero])
+  }
+
+  fn mul<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    b: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config);
+    mul_pairs_chip.forward(layouter.namespace(|| "nms mul"), &vec![a, b], &vec![zero])
+  }
+
+  fn rescale<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    sf: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config);
+    var_div_chip.forward(
+      layouter.namespace(|| "nms rescale"),
+      &vec![a],
+      &vec![zero, sf],
+    )
+  }
+
+  // Returns (max(a, b), min(a, b)) as (b + g * (a - b), a - g * (a - b)) with g = a > b
+  fn max_min<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    b: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<(Vec<AssignedCell<F, F>>, Vec<AssignedCell<F, F>>), Error> {
+    let g = Self::gt(
+      layouter.namespace(|| "nms max min gt"),
+      a.clone(),
+      b.clone(),
+      zero,
+      gadget_config.clone(),
+    )?;
+    let diff = Self::sub(
+      layouter.namespace(|| "nms max min diff"),
+      a.clone(),
+      b.clone(),
+      zero,
+      gadget_config.clone(),
+    )?;
+    let g_diff = Self::mul(
+      layouter.namespace(|| "nms max min mul"),
+      g.iter().collect(),
+      diff.iter().collect(),
+      zero,
+      gadget_config.clone(),
+    )?;
+    let max = Self::add(
+      layouter.namespace(|| "nms max"),
+      b,
+      g_diff.iter().collect(),
+      zero,
+      gadget_config.clone(),
+    )?;
+    let min = Self::sub(
+      layouter.namespace(|| "nms min"),
+      a,
+      g_diff.iter().collect(),
+      zero,
+      gadget_config,
+    )?;
+    Ok((max, min))
+  }
+
+  fn relu<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    a: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let g = Self::gt(
+      layouter.namespace(|| "nms relu gt"),
+      a.clone(),
+      vec![zero; a.len()],
+      zero,
+      gadget_config.clone(),
+    )?;
+    Self::mul(
+      layouter.namespace(|| "nms relu mul"),
+      g.iter().collect(),
+      a,
+      zero,
+      gadget_config,
+    )
+  }
+}
+
+impl<F: PrimeField> Layer<F> for NonMaxSuppressionChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let scores = &tensors[1];
+    let n = scores.len();
+    let boxes = tensors[0].clone().into_shape(IxDyn(&[n, 4])).unwrap();
+    let max_output_size = layer_config.layer_params[0];
+    let iou_threshold = layer_config.layer_params[1];
+    let score_threshold = layer_config.layer_params[2];
+    assert!(max_output_size > 0);
+    assert!(iou_threshold >= 0);
+
+    let zero = constants.get(&0).unwrap().as_ref();
+    let one = constants.get(&1).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+    let gc = gadget_config.clone();
+
+    let (iou_cell, score_cell, max_rank_cell) = layouter.assign_region(
+      || "nms thresholds",
+      |mut region| {
+        let bias = 1 << 60;
+        let vals = [iou_threshold, score_threshold, max_output_size - 1];
+        let cells = vals
+          .iter()
+          .enumerate()
+          .map(|(i, val)| {
+            region.assign_fixed(
+              || format!("nms threshold {}", i),
+              gadget_config.fixed_columns[0],
+              i,
+              || Value::known(F::from((val + bias) as u64) - F::from(bias as u64)),
+            )
+          })
+          .collect::<Result<Vec<_>, _>>()?;
+        Ok((cells[0].clone(), cells[1].clone(), cells[2].clone()))
+      },
+    )?;
+
+    let scores_vec = scores.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let coord = |c: usize| (0..n).map(|i| boxes[[i, c]].as_ref()).collect::<Vec<_>>();
+    let (y1, x1, y2, x2) = (coord(0), coord(1), coord(2), coord(3));
+
+    let height = Self::sub(
+      layouter.namespace(|| "nms h"),
+      y2.clone(),
+      y1.clone(),
+      zero,
+      gc.clone(),
+    )?;
+    let width = Self::sub(
+      layouter.namespace(|| "nms w"),
+      x2.clone(),
+      x1.clone(),
+      zero,
+      gc.clone(),
+    )?;
+    let area = Self::mul(
+      layouter.namespace(|| "nms area"),
+      height.iter().collect(),
+      width.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let area = Self::rescale(
+      layouter.namespace(|| "nms area rescale"),
+      area.iter().collect(),
+      zero,
+      sf,
+      gc.clone(),
+    )?;
+    let area = area.iter().collect::<Vec<_>>();
+
+    // The IoU is symmetric, so it is only computed for i < j
+    let pairs = (0..n)
+      .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+      .collect::<Vec<_>>();
+    let num_pairs = pairs.len();
+    let idx_i = pairs.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+    let idx_j = pairs.iter().map(|(_, j)| *j).collect::<Vec<_>>();
+
+    let (yy1, _) = Self::max_min(
+      layouter.namespace(|| "nms yy1"),
+      Self::gather(&y1, &idx_i),
+      Self::gather(&y1, &idx_j),
+      zero,
+      gc.clone(),
+    )?;
+    let (xx1, _) = Self::max_min(
+      layouter.namespace(|| "nms xx1"),
+      Self::gather(&x1, &idx_i),
+      Self::gather(&x1, &idx_j),
+      zero,
+      gc.clone(),
+    )?;
+    let (_, yy2) = Self::max_min(
+      layouter.namespace(|| "nms yy2"),
+      Self::gather(&y2, &idx_i),
+      Self::gather(&y2, &idx_j),
+      zero,
+      gc.clone(),
+    )?;
+    let (_, xx2) = Self::max_min(
+      layouter.namespace(|| "nms xx2"),
+      Self::gather(&x2, &idx_i),
+      Self::gather(&x2, &idx_j),
+      zero,
+      gc.clone(),
+    )?;
+
+    let inter_h = Self::sub(
+      layouter.namespace(|| "nms inter h"),
+      yy2.iter().collect(),
+      yy1.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let inter_h = Self::relu(
+      layouter.namespace(|| "nms inter h relu"),
+      inter_h.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let inter_w = Self::sub(
+      layouter.namespace(|| "nms inter w"),
+      xx2.iter().collect(),
+      xx1.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let inter_w = Self::relu(
+      layouter.namespace(|| "nms inter w relu"),
+      inter_w.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let inter = Self::mul(
+      layouter.namespace(|| "nms inter"),
+      inter_h.iter().collect(),
+      inter_w.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let inter = Self::rescale(
+      layouter.namespace(|| "nms inter rescale"),
+      inter.iter().collect(),
+      zero,
+      sf,
+      gc.clone(),
+    )?;
+
+    let area_sum = Self::add(
+      layouter.namespace(|| "nms area sum"),
+      Self::gather(&area, &idx_i),
+      Self::gather(&area, &idx_j),
+      zero,
+      gc.clone(),
+    )?;
+    let union = Self::sub(
+      layouter.namespace(|| "nms union"),
+      area_sum.iter().collect(),
+      inter.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+
+    // IoU > threshold iff inter > threshold * union, which avoids dividing by the union
+    let threshold_union = Self::mul(
+      layouter.namespace(|| "nms threshold union"),
+      union.iter().collect(),
+      vec![&iou_cell; num_pairs],
+      zero,
+      gc.clone(),
+    )?;
+    let threshold_union = Self::rescale(
+      layouter.namespace(|| "nms threshold union rescale"),
+      threshold_union.iter().collect(),
+      zero,
+      sf,
+      gc.clone(),
+    )?;
+    let overlaps = Self::gt(
+      layouter.namespace(|| "nms overlaps"),
+      inter.iter().collect(),
+      threshold_union.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+
+    let j_beats_i = Self::gt(
+      layouter.namespace(|| "nms j beats i"),
+      Self::gather(&scores_vec, &idx_j),
+      Self::gather(&scores_vec, &idx_i),
+      zero,
+      gc.clone(),
+    )?;
+    let i_beats_j = Self::sub(
+      layouter.namespace(|| "nms i beats j"),
+      vec![one; num_pairs],
+      j_beats_i.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let valid = Self::gt(
+      layouter.namespace(|| "nms valid"),
+      scores_vec.clone(),
+      vec![&score_cell; n],
+      zero,
+      gc.clone(),
+    )?;
+
+    // Run the greedy algorithm to find the kept boxes
+    let is_one = |x: &AssignedCell<F, F>| convert_pos_int(x.value().map(|x| x.to_owned())) == 1;
+    let mut overlap_mat = vec![vec![false; n]; n];
+    for ((i, j), overlap) in pairs.iter().zip(overlaps.iter()) {
+      overlap_mat[*i][*j] = is_one(overlap);
+      overlap_mat[*j][*i] = is_one(overlap);
+    }
+    let score_vals = scores_vec
+      .iter()
+      .map(|x| convert_pos_int(x.value().map(|x| x.to_owned())))
+      .collect::<Vec<_>>();
+    let mut order = (0..n).collect::<Vec<_>>();
+    order.sort_by_key(|i| (-score_vals[*i], *i));
+    let mut kept_vals = vec![false; n];
+    for (pos, i) in order.iter().enumerate() {
+      let suppressed = order[..pos]
+        .iter()
+        .any(|j| kept_vals[*j] && overlap_mat[*i][*j]);
+      kept_vals[*i] = is_one(&valid[*i]) && !suppressed;
+    }
+
+    let kept = layouter.assign_region(
+      || "nms kept",
+      |mut region| {
+        let num_cols = gadget_config.columns.len();
+        let mut kept = vec![];
+        for (i, val) in kept_vals.iter().enumerate() {
+          let cell = region.assign_advice(
+            || "nms kept",
+            gadget_config.columns[i % num_cols],
+            i / num_cols,
+            || Value::known(if *val { F::ONE } else { F::ZERO }),
+          )?;
+          kept.push(cell);
+        }
+        Ok(kept)
+      },
+    )?;
+    let kept = kept.iter().collect::<Vec<_>>();
+
+    // For every pair, whether the kept box of the pair beats the other one (rank) and whether it
+    // also suppresses it
+    let kept_j_beats_i = Self::mul(
+      layouter.namespace(|| "nms kept j beats i"),
+      Self::gather(&kept, &idx_j),
+      j_beats_i.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let kept_i_beats_j = Self::mul(
+      layouter.namespace(|| "nms kept i beats j"),
+      Self::gather(&kept, &idx_i),
+      i_beats_j.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let j_suppresses_i = Self::mul(
+      layouter.namespace(|| "nms j suppresses i"),
+      kept_j_beats_i.iter().collect(),
+      overlaps.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let i_suppresses_j = Self::mul(
+      layouter.namespace(|| "nms i suppresses j"),
+      kept_i_beats_j.iter().collect(),
+      overlaps.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+
+    let mut beaten_by = vec![vec![zero]; n];
+    let mut suppressed_by = vec![vec![zero]; n];
+    for (p, (i, j)) in pairs.iter().enumerate() {
+      beaten_by[*i].push(&kept_j_beats_i[p]);
+      beaten_by[*j].push(&kept_i_beats_j[p]);
+      suppressed_by[*i].push(&j_suppresses_i[p]);
+      suppressed_by[*j].push(&i_suppresses_j[p]);
+    }
+
+    let adder_chip = AdderChip::<F>::construct(gadget_config.clone());
+    let mut ranks = vec![];
+    let mut num_suppressed = vec![];
+    for i in 0..n {
+      let rank = adder_chip.forward(
+        layouter.namespace(|| format!("nms rank {}", i)),
+        &vec![beaten_by[i].clone()],
+        &vec![zero],
+      )?;
+      ranks.push(rank[0].clone());
+      let count = adder_chip.forward(
+        layouter.namespace(|| format!("nms suppressed {}", i)),
+        &vec![suppressed_by[i].clone()],
+        &vec![zero],
+      )?;
+      num_suppressed.push(count[0].clone());
+    }
+
+    let suppressed = Self::gt(
+      layouter.namespace(|| "nms suppressed"),
+      num_suppressed.iter().collect(),
+      vec![zero; n],
+      zero,
+      gc.clone(),
+    )?;
+    let valid_suppressed = Self::mul(
+      layouter.namespace(|| "nms valid suppressed"),
+      valid.iter().collect(),
+      suppressed.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let expected = Self::sub(
+      layouter.namespace(|| "nms expected kept"),
+      valid.iter().collect(),
+      valid_suppressed.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    layouter.assign_region(
+      || "nms kept check",
+      |mut region| {
+        for (k, e) in kept.iter().zip(expected.iter()) {
+          region.constrain_equal(k.cell(), e.cell())?;
+        }
+        Ok(())
+      },
+    )?;
+
+    let over_limit = Self::gt(
+      layouter.namespace(|| "nms over limit"),
+      ranks.iter().collect(),
+      vec![&max_rank_cell; n],
+      zero,
+      gc.clone(),
+    )?;
+    let kept_over_limit = Self::mul(
+      layouter.namespace(|| "nms kept over limit"),
+      kept.clone(),
+      over_limit.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let selected = Self::sub(
+      layouter.namespace(|| "nms selected"),
+      kept,
+      kept_over_limit.iter().collect(),
+      zero,
+      gc.clone(),
+    )?;
+
+    let out_boxes = Self::mul(
+      layouter.namespace(|| "nms out boxes"),
+      boxes.iter().map(|x| x.as_ref()).collect(),
+      selected.iter().flat_map(|x| vec![x; 4]).collect(),
+      zero,
+      gc.clone(),
+    )?;
+    let out_scores = Self::mul(
+      layouter.namespace(|| "nms out scores"),
+      scores_vec,
+      selected.iter().collect(),
+      zero,
+      gc,
+    )?;
+
+    let out_boxes = out_boxes
+      .into_iter()
+      .map(|x| Rc::new(x))
+      .collect::<Vec<_>>();
+    let out_boxes = Array::from_shape_vec(IxDyn(tensors[0].shape()), out_boxes).unwrap();
+    let out_scores = out_scores
+      .into_iter()
+      .map(|x| Rc::new(x))
+      .collect::<Vec<_>>();
+    let out_scores = Array::from_shape_vec(IxDyn(scores.shape()), out_scores).unwrap();
+    Ok(vec![out_boxes, out_scores])
+  }
+}
+
+impl GadgetConsumer for NonMaxSuppressionChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::AddPairs,
+      GadgetType::GreaterThan,
+      GadgetType::MulPairs,
+      GadgetType::SubPairs,
+      GadgetType::VarDivRound,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,66 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  nonlinear::{custom::CustomFunctions, piecewise_linear::PiecewiseLinearGadgetChip},
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// Applies a registered piecewise linear function. Params: the name of the function as ASCII codes.
+#[derive(Clone, Debug)]
+pub struct PiecewiseLinearChip {
+  pub custom_functions: CustomFunctions,
+}
+
+impl PiecewiseLinearChip {
+  pub fn name(&self, layer_params: &Vec<i64>) -> &'static str {
+    let name = layer_params
+      .iter()
+      .map(|x| *x as u8 as char)
+      .collect::<String>();
+    self.custom_functions.piecewise_linear(&name).0
+  }
+}
+
+impl<F: PrimeField> Layer<F> for PiecewiseLinearChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let name = self.name(&layer_config.layer_params);
+    let pwl_chip = PiecewiseLinearGadgetChip::<F>::construct(gadget_config.clone(), name);
+    let vec_inps = vec![inp_vec];
+    let constants = vec![zero];
+    let out = pwl_chip.forward(
+      layouter.namespace(|| format!("piecewise linear {}", name)),
+      &vec_inps,
+      &constants,
+    )?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for PiecewiseLinearChip {
+  fn used_gadgets(&self, layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::PiecewiseLinear(self.name(&layer_params)),
+      GadgetType::InputLookup,
+    ]
+  }
+}
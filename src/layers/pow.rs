@@ -1,18 +1,110 @@
 use std::{collections::HashMap, rc::Rc, vec};
 
-use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
 use ndarray::{Array, IxDyn};
 
 use crate::gadgets::{
   gadget::{Gadget, GadgetConfig, GadgetType},
+  mul_pairs::MulPairsChip,
   nonlinear::pow::PowGadgetChip,
+  var_div::VarDivRoundChip,
 };
 
 use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
 
+// Params are [exponent numerator, exponent denominator]. A single param is an integer exponent.
+// Integer exponents are computed by repeated squaring, rescaling after every multiplication.
+// Fractional exponents use the Pow lookup, which supports one exponent per model.
 #[derive(Clone, Debug)]
 pub struct PowChip {}
 
+impl PowChip {
+  pub fn integer_exponent(params: &Vec<i64>) -> Option<i64> {
+    if params.len() < 2 || params[1] == 1 {
+      Some(params[0])
+    } else {
+      None
+    }
+  }
+
+  fn mul_rescale<F: PrimeField>(
+    &self,
+    mut layouter: impl Layouter<F>,
+    a: &Vec<AssignedCell<F, F>>,
+    b: &Vec<AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    sf: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+
+    let vec_inps = vec![a.iter().collect(), b.iter().collect()];
+    let out = mul_pairs_chip.forward(layouter.namespace(|| "pow mul"), &vec_inps, &vec![zero])?;
+    let out = out.iter().collect::<Vec<_>>();
+    var_div_chip.forward(layouter.namespace(|| "pow div"), &vec![out], &vec![zero, sf])
+  }
+
+  fn integer_forward<F: PrimeField>(
+    &self,
+    mut layouter: impl Layouter<F>,
+    inp: Vec<AssignedCell<F, F>>,
+    exponent: i64,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    assert!(exponent >= 0, "negative integer exponents are not supported");
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf = constants
+      .get(&(gadget_config.scale_factor as i64))
+      .unwrap()
+      .as_ref();
+
+    // x^0 = 1, which is the scale factor in fixed point
+    if exponent == 0 {
+      return Ok(inp.iter().map(|_| sf.clone()).collect());
+    }
+
+    let mut result: Option<Vec<AssignedCell<F, F>>> = None;
+    let mut base = inp;
+    let mut exponent = exponent;
+    let mut step = 0;
+    while exponent > 0 {
+      if exponent & 1 == 1 {
+        result = match result {
+          None => Some(base.clone()),
+          Some(result) => Some(self.mul_rescale(
+            layouter.namespace(|| format!("pow result {}", step)),
+            &result,
+            &base,
+            zero,
+            sf,
+            gadget_config.clone(),
+          )?),
+        };
+      }
+      exponent >>= 1;
+      if exponent > 0 {
+        base = self.mul_rescale(
+          layouter.namespace(|| format!("pow square {}", step)),
+          &base,
+          &base,
+          zero,
+          sf,
+          gadget_config.clone(),
+        )?;
+      }
+      step += 1;
+    }
+
+    Ok(result.unwrap())
+  }
+}
+
 impl<F: PrimeField> Layer<F> for PowChip {
   fn forward(
     &self,
@@ -20,16 +112,30 @@ impl<F: PrimeField> Layer<F> for PowChip {
     tensors: &Vec<AssignedTensor<F>>,
     constants: &HashMap<i64, CellRc<F>>,
     gadget_config: Rc<GadgetConfig>,
-    _layer_config: &LayerConfig,
+    layer_config: &LayerConfig,
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
     let inp = &tensors[0];
-    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
     let zero = constants.get(&0).unwrap().as_ref();
 
-    let pow_chip = PowGadgetChip::<F>::construct(gadget_config.clone());
-    let vec_inps = vec![inp_vec];
-    let constants = vec![zero];
-    let out = pow_chip.forward(layouter.namespace(|| "pow chip"), &vec_inps, &constants)?;
+    let out = match PowChip::integer_exponent(&layer_config.layer_params) {
+      Some(exponent) => {
+        let inp_vec = inp.iter().map(|x| x.as_ref().clone()).collect::<Vec<_>>();
+        self.integer_forward(
+          layouter.namespace(|| "pow integer"),
+          inp_vec,
+          exponent,
+          constants,
+          gadget_config.clone(),
+        )?
+      }
+      None => {
+        let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+        let pow_chip = PowGadgetChip::<F>::construct(gadget_config.clone());
+        let vec_inps = vec![inp_vec];
+        let constants = vec![zero];
+        pow_chip.forward(layouter.namespace(|| "pow chip"), &vec_inps, &constants)?
+      }
+    };
 
     let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
     let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
@@ -39,7 +145,14 @@ impl<F: PrimeField> Layer<F> for PowChip {
 }
 
 impl GadgetConsumer for PowChip {
-  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
-    vec![GadgetType::Pow, GadgetType::InputLookup]
+  fn used_gadgets(&self, layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    match PowChip::integer_exponent(&layer_params) {
+      Some(_) => vec![
+        GadgetType::MulPairs,
+        GadgetType::VarDivRound,
+        GadgetType::InputLookup,
+      ],
+      None => vec![GadgetType::Pow, GadgetType::InputLookup],
+    }
   }
 }
@@ -0,0 +1,74 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  var_div::VarDivRoundChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// c / x with rounding. Params: [c], already scaled by the scale factor, defaulting to 1. The
+// divisor is the last input so that DIV(c, x) can be mapped directly; it must be positive.
+#[derive(Clone, Debug)]
+pub struct ReciprocalChip {}
+
+impl<F: PrimeField> Layer<F> for ReciprocalChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = tensors.last().unwrap();
+    let sf = gadget_config.scale_factor as i64;
+    let numerator = layer_config.layer_params.get(0).cloned().unwrap_or(sf) * sf;
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let numerator = layouter.assign_region(
+      || "reciprocal numerator",
+      |mut region| {
+        let bias = 1 << 60;
+        region.assign_fixed(
+          || "reciprocal numerator",
+          gadget_config.fixed_columns[0],
+          0,
+          || Value::known(F::from((numerator + bias) as u64) - F::from(bias as u64)),
+        )
+      },
+    )?;
+
+    // The divisor is a single input of the gadget, so each distinct divisor cell is divided once
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let mut outs: HashMap<*const AssignedCell<F, F>, CellRc<F>> = HashMap::new();
+    let mut out = vec![];
+    for x in inp.iter() {
+      if !outs.contains_key(&Rc::as_ptr(x)) {
+        let recip = var_div_chip.forward(
+          layouter.namespace(|| "reciprocal div"),
+          &vec![vec![&numerator]],
+          &vec![zero, x.as_ref()],
+        )?;
+        outs.insert(Rc::as_ptr(x), Rc::new(recip[0].clone()));
+      }
+      out.push(outs.get(&Rc::as_ptr(x)).unwrap().clone());
+    }
+
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for ReciprocalChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![GadgetType::VarDivRound, GadgetType::InputLookup]
+  }
+}
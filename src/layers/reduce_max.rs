@@ -0,0 +1,68 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    max::MaxChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::{
+  layer::{Layer, LayerConfig},
+  reduce_sum::ReduceSumChip,
+};
+
+// Params: [keepdims, axes...]
+pub struct ReduceMaxChip {}
+
+impl<F: PrimeField> Layer<F> for ReduceMaxChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let keepdims = layer_config.layer_params[0] == 1;
+    // Same axis handling and grouping as ReduceSum
+    let reduce_sum_chip = ReduceSumChip {};
+    let axes = reduce_sum_chip.get_axes(layer_config);
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let splat = reduce_sum_chip.splat(inp, &axes);
+    let max_chip = MaxChip::<F>::construct(gadget_config.clone());
+    let mut outp = vec![];
+    for group in splat.iter() {
+      let group = group.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+      let max = max_chip.forward(
+        layouter.namespace(|| "reduce max"),
+        &vec![group],
+        &vec![zero],
+      )?;
+      outp.push(Rc::new(max[0].clone()));
+    }
+
+    let out_shape = inp
+      .shape()
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| keepdims || !axes.contains(i))
+      .map(|(i, x)| if axes.contains(&i) { 1 } else { *x })
+      .collect::<Vec<_>>();
+    let outp = Array::from_shape_vec(IxDyn(&out_shape), outp).unwrap();
+
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for ReduceMaxChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![GadgetType::Max, GadgetType::InputLookup]
+  }
+}
@@ -0,0 +1,96 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    adder::AdderChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::layer::{Layer, LayerConfig};
+
+// Params: [keepdims, axes...]
+pub struct ReduceSumChip {}
+
+impl ReduceSumChip {
+  pub fn get_axes(&self, layer_config: &LayerConfig) -> Vec<usize> {
+    let ndim = layer_config.inp_shapes[0].len() as i64;
+    layer_config.layer_params[1..]
+      .iter()
+      .map(|axis| (if *axis < 0 { axis + ndim } else { *axis }) as usize)
+      .collect()
+  }
+
+  // Groups the input cells by the output cell they are summed into, in output order
+  pub fn splat<G: Clone>(&self, inp: &Array<G, IxDyn>, axes: &Vec<usize>) -> Vec<Vec<G>> {
+    let reduced_shape = inp
+      .shape()
+      .iter()
+      .enumerate()
+      .map(|(i, x)| if axes.contains(&i) { 1 } else { *x })
+      .collect::<Vec<_>>();
+    let num_out = reduced_shape.iter().product::<usize>();
+
+    let mut splat = vec![vec![]; num_out];
+    for (idx, val) in inp.indexed_iter() {
+      let mut out_idx = 0;
+      for (i, dim) in reduced_shape.iter().enumerate() {
+        let x = if axes.contains(&i) { 0 } else { idx[i] };
+        out_idx = out_idx * dim + x;
+      }
+      splat[out_idx].push(val.clone());
+    }
+
+    splat
+  }
+}
+
+impl<F: PrimeField> Layer<F> for ReduceSumChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let keepdims = layer_config.layer_params[0] == 1;
+    let axes = self.get_axes(layer_config);
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let splat = self.splat(inp, &axes);
+    let adder_chip = AdderChip::<F>::construct(gadget_config.clone());
+    let mut outp = vec![];
+    for group in splat.iter() {
+      let group = group.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+      let sum = adder_chip.forward(
+        layouter.namespace(|| "reduce sum"),
+        &vec![group],
+        &vec![zero],
+      )?;
+      outp.push(Rc::new(sum[0].clone()));
+    }
+
+    let out_shape = inp
+      .shape()
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| keepdims || !axes.contains(i))
+      .map(|(i, x)| if axes.contains(&i) { 1 } else { *x })
+      .collect::<Vec<_>>();
+    let outp = Array::from_shape_vec(IxDyn(&out_shape), outp).unwrap();
+
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for ReduceSumChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![GadgetType::Adder]
+  }
+}
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  mul_pairs::MulPairsChip,
+  var_div::VarDivRoundChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// Changes the scale of a tensor from den to num: round(x * num / den). Params: [num, den], which
+// must be positive. The multiplication is skipped if num is 1.
+#[derive(Clone, Debug)]
+pub struct RequantizeChip {}
+
+impl<F: PrimeField> Layer<F> for RequantizeChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let num = layer_config.layer_params[0];
+    let den = layer_config.layer_params[1];
+    assert!(num > 0 && den > 0);
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let (num_cell, den_cell) = layouter.assign_region(
+      || "requantize ratio",
+      |mut region| {
+        let num_cell = region.assign_fixed(
+          || "requantize num",
+          gadget_config.fixed_columns[0],
+          0,
+          || Value::known(F::from(num as u64)),
+        )?;
+        let den_cell = region.assign_fixed(
+          || "requantize den",
+          gadget_config.fixed_columns[0],
+          1,
+          || Value::known(F::from(den as u64)),
+        )?;
+        Ok((num_cell, den_cell))
+      },
+    )?;
+
+    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let scaled = if num == 1 {
+      inp_vec.iter().map(|x| (*x).clone()).collect::<Vec<_>>()
+    } else {
+      let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+      mul_pairs_chip.forward(
+        layouter.namespace(|| "requantize mul"),
+        &vec![inp_vec.clone(), vec![&num_cell; inp_vec.len()]],
+        &vec![zero],
+      )?
+    };
+
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let out = var_div_chip.forward(
+      layouter.namespace(|| "requantize div"),
+      &vec![scaled.iter().collect()],
+      &vec![zero, &den_cell],
+    )?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for RequantizeChip {
+  fn used_gadgets(&self, layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    let mut outp = vec![GadgetType::VarDivRound, GadgetType::InputLookup];
+    if layer_params[0] != 1 {
+      outp.push(GadgetType::MulPairs);
+    }
+    outp
+  }
+}
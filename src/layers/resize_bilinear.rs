@@ -0,0 +1,193 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  dot_prod::DotProductChip,
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  var_div::VarDivRoundChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// NHWC, params: [align_corners, half_pixel_centers]; the output shape is taken from the layer.
+// Each output is the dot product of the four neighboring inputs with fixed interpolation weights,
+// which only depend on the shapes and are scaled by the scale factor.
+#[derive(Clone, Debug)]
+pub struct ResizeBilinearChip {}
+
+impl ResizeBilinearChip {
+  // For each output index along an axis: the two neighboring input indices and the weight of the
+  // second one, as in TFLite
+  pub fn interpolation(
+    inp_size: usize,
+    out_size: usize,
+    align_corners: bool,
+    half_pixel_centers: bool,
+  ) -> Vec<(usize, usize, f64)> {
+    let scale = if align_corners && out_size > 1 {
+      (inp_size - 1) as f64 / (out_size - 1) as f64
+    } else {
+      inp_size as f64 / out_size as f64
+    };
+
+    (0..out_size)
+      .map(|o| {
+        let pos = if half_pixel_centers {
+          (o as f64 + 0.5) * scale - 0.5
+        } else {
+          o as f64 * scale
+        };
+        let lo = pos.floor();
+        let frac = pos - lo;
+        let lo = (lo.max(0.) as usize).min(inp_size - 1);
+        let hi = (pos.ceil().max(0.) as usize).min(inp_size - 1);
+        (lo, hi, frac)
+      })
+      .collect()
+  }
+
+  // Assigns each distinct (possibly negative) weight to a fixed cell
+  pub fn assign_weights<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    mut weight_vals: Vec<i64>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<HashMap<i64, AssignedCell<F, F>>, Error> {
+    weight_vals.sort();
+    weight_vals.dedup();
+
+    layouter.assign_region(
+      || "bilinear weights",
+      |mut region| {
+        let bias = 1 << 60;
+        let mut cells = HashMap::new();
+        for (i, val) in weight_vals.iter().enumerate() {
+          let cell = region.assign_fixed(
+            || "bilinear weight",
+            gadget_config.fixed_columns[0],
+            i,
+            || Value::known(F::from((val + bias) as u64) - F::from(bias as u64)),
+          )?;
+          cells.insert(*val, cell);
+        }
+        Ok(cells)
+      },
+    )
+  }
+}
+
+impl<F: PrimeField> Layer<F> for ResizeBilinearChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let output_shape = layer_config.out_shapes[0].clone();
+    let align_corners = layer_config.layer_params.get(0).cloned().unwrap_or(0) == 1;
+    let half_pixel_centers = layer_config.layer_params.get(1).cloned().unwrap_or(0) == 1;
+    let sf = gadget_config.scale_factor as f64;
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf_cell = constants.get(&(sf as i64)).unwrap().as_ref();
+
+    assert_eq!(inp.ndim(), 4);
+    assert_eq!(inp.shape()[0], output_shape[0]);
+    assert_eq!(inp.shape()[3], output_shape[3]);
+
+    let interp_h = Self::interpolation(
+      inp.shape()[1],
+      output_shape[1],
+      align_corners,
+      half_pixel_centers,
+    );
+    let interp_w = Self::interpolation(
+      inp.shape()[2],
+      output_shape[2],
+      align_corners,
+      half_pixel_centers,
+    );
+
+    // The scaled weights of the four neighbors of each output pixel
+    let weights = |h: usize, w: usize| {
+      let (_, _, fh) = interp_h[h];
+      let (_, _, fw) = interp_w[w];
+      [
+        ((1. - fh) * (1. - fw) * sf).round() as i64,
+        ((1. - fh) * fw * sf).round() as i64,
+        (fh * (1. - fw) * sf).round() as i64,
+        (fh * fw * sf).round() as i64,
+      ]
+    };
+
+    let mut weight_vals = vec![];
+    for h in 0..output_shape[1] {
+      for w in 0..output_shape[2] {
+        weight_vals.extend(weights(h, w));
+      }
+    }
+    let weight_cells = Self::assign_weights(
+      layouter.namespace(|| "resize bilinear weights"),
+      weight_vals,
+      gadget_config.clone(),
+    )?;
+
+    let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
+    let mut dot_prods: Vec<AssignedCell<F, F>> = vec![];
+    for b in 0..output_shape[0] {
+      for h in 0..output_shape[1] {
+        let (h_lo, h_hi, _) = interp_h[h];
+        for w in 0..output_shape[2] {
+          let (w_lo, w_hi, _) = interp_w[w];
+          let weight_vec = weights(h, w)
+            .iter()
+            .map(|x| weight_cells.get(x).unwrap())
+            .collect::<Vec<_>>();
+          for c in 0..output_shape[3] {
+            let inp_vec = vec![
+              inp[[b, h_lo, w_lo, c]].as_ref(),
+              inp[[b, h_lo, w_hi, c]].as_ref(),
+              inp[[b, h_hi, w_lo, c]].as_ref(),
+              inp[[b, h_hi, w_hi, c]].as_ref(),
+            ];
+            let outp = dot_prod_chip.forward(
+              layouter.namespace(|| "resize bilinear dot prod"),
+              &vec![inp_vec, weight_vec.clone()],
+              &vec![zero],
+            )?;
+            dot_prods.push(outp[0].clone());
+          }
+        }
+      }
+    }
+
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let out = var_div_chip.forward(
+      layouter.namespace(|| "resize bilinear rescale"),
+      &vec![dot_prods.iter().collect()],
+      &vec![zero, sf_cell],
+    )?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(&output_shape), out).unwrap();
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for ResizeBilinearChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::DotProduct,
+      GadgetType::VarDivRound,
+      GadgetType::InputLookup,
+    ]
+  }
+}
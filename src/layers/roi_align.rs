@@ -0,0 +1,193 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  rc::Rc,
+  vec,
+};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  dot_prod::DotProductChip,
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  var_div::VarDivRoundChip,
+};
+
+use super::{
+  layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+  resize_bilinear::ResizeBilinearChip,
+};
+
+// ROIAlign on an NHWC feature map, following torchvision. Params: [pooled height, pooled width,
+// sampling ratio (<= 0 is adaptive), aligned, then (batch index, y1, x1, y2, x2) for every ROI],
+// with the corners scaled by the scale factor and already in feature map coordinates. The ROIs are
+// part of the circuit, so the bilinear weights are fixed: every output is the dot product of the
+// pixels of its bin with the sampling weights averaged over the bin, rescaled by the scale factor.
+// The output is (num ROIs, pooled height, pooled width, channels).
+#[derive(Clone, Debug)]
+pub struct ROIAlignChip {}
+
+impl ROIAlignChip {
+  // The neighbors and weights of a bilinear sample along an axis, or None if it is out of bounds
+  fn sample(pos: f64, size: usize) -> Option<[(usize, f64); 2]> {
+    if pos < -1. || pos > size as f64 {
+      return None;
+    }
+    let pos = pos.max(0.);
+    let lo = pos.floor() as usize;
+    if lo >= size - 1 {
+      return Some([(size - 1, 1.), (size - 1, 0.)]);
+    }
+    let frac = pos - lo as f64;
+    Some([(lo, 1. - frac), (lo + 1, frac)])
+  }
+
+  // The averaged sampling weight of every pixel (h, w) of a bin
+  pub fn bin_weights(
+    roi: [f64; 4],
+    bin: (usize, usize),
+    pooled: (usize, usize),
+    sampling_ratio: i64,
+    aligned: bool,
+    inp_size: (usize, usize),
+  ) -> BTreeMap<(usize, usize), f64> {
+    let [y1, x1, y2, x2] = roi;
+    // Without alignment, the ROIs are at least one pixel
+    let min_size = if aligned { 0. } else { 1. };
+    let bin_h = (y2 - y1).max(min_size) / pooled.0 as f64;
+    let bin_w = (x2 - x1).max(min_size) / pooled.1 as f64;
+    let (grid_h, grid_w) = if sampling_ratio > 0 {
+      (sampling_ratio as usize, sampling_ratio as usize)
+    } else {
+      (bin_h.ceil() as usize, bin_w.ceil() as usize)
+    };
+    let count = (grid_h * grid_w).max(1) as f64;
+
+    let mut weights = BTreeMap::new();
+    for iy in 0..grid_h {
+      let y = y1 + bin.0 as f64 * bin_h + (iy as f64 + 0.5) * bin_h / grid_h as f64;
+      for ix in 0..grid_w {
+        let x = x1 + bin.1 as f64 * bin_w + (ix as f64 + 0.5) * bin_w / grid_w as f64;
+        if let (Some(ys), Some(xs)) = (Self::sample(y, inp_size.0), Self::sample(x, inp_size.1)) {
+          for (h, wy) in ys.iter() {
+            for (w, wx) in xs.iter() {
+              *weights.entry((*h, *w)).or_insert(0.) += wy * wx / count;
+            }
+          }
+        }
+      }
+    }
+    weights
+  }
+}
+
+impl<F: PrimeField> Layer<F> for ROIAlignChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    assert_eq!(inp.ndim(), 4);
+    let params = &layer_config.layer_params;
+    let pooled = (params[0] as usize, params[1] as usize);
+    let sampling_ratio = params[2];
+    let aligned = params[3] == 1;
+    let rois = params[4..].chunks(5).collect::<Vec<_>>();
+    assert!(rois.iter().all(|roi| roi.len() == 5));
+
+    let sf = gadget_config.scale_factor as f64;
+    let zero = constants.get(&0).unwrap().as_ref();
+    let sf_cell = constants.get(&(sf as i64)).unwrap().as_ref();
+    let inp_size = (inp.shape()[1], inp.shape()[2]);
+    let channels = inp.shape()[3];
+    let offset = if aligned { 0.5 } else { 0. };
+
+    // The scaled weights of every bin of every ROI
+    let mut bins = vec![];
+    for roi in rois.iter() {
+      let corners = [
+        roi[1] as f64 / sf - offset,
+        roi[2] as f64 / sf - offset,
+        roi[3] as f64 / sf - offset,
+        roi[4] as f64 / sf - offset,
+      ];
+      for ph in 0..pooled.0 {
+        for pw in 0..pooled.1 {
+          let weights =
+            Self::bin_weights(corners, (ph, pw), pooled, sampling_ratio, aligned, inp_size)
+              .into_iter()
+              .map(|(pixel, weight)| (pixel, (weight * sf).round() as i64))
+              .filter(|(_, weight)| *weight != 0)
+              .collect::<Vec<_>>();
+          bins.push((roi[0] as usize, weights));
+        }
+      }
+    }
+
+    let weight_vals = bins
+      .iter()
+      .flat_map(|(_, weights)| weights.iter().map(|(_, weight)| *weight))
+      .collect::<Vec<_>>();
+    let weight_cells = ResizeBilinearChip::assign_weights(
+      layouter.namespace(|| "roi align weights"),
+      weight_vals,
+      gadget_config.clone(),
+    )?;
+
+    let dot_prod_chip = DotProductChip::<F>::construct(gadget_config.clone());
+    let mut dot_prods: Vec<AssignedCell<F, F>> = vec![];
+    for (b, weights) in bins.iter() {
+      let weight_vec = weights
+        .iter()
+        .map(|(_, weight)| weight_cells.get(weight).unwrap())
+        .collect::<Vec<_>>();
+      for c in 0..channels {
+        if weights.is_empty() {
+          dot_prods.push(zero.clone());
+          continue;
+        }
+        let inp_vec = weights
+          .iter()
+          .map(|((h, w), _)| inp[[*b, *h, *w, c]].as_ref())
+          .collect::<Vec<_>>();
+        let outp = dot_prod_chip.forward(
+          layouter.namespace(|| "roi align dot prod"),
+          &vec![inp_vec, weight_vec.clone()],
+          &vec![zero],
+        )?;
+        dot_prods.push(outp[0].clone());
+      }
+    }
+
+    let var_div_chip = VarDivRoundChip::<F>::construct(gadget_config.clone());
+    let out = var_div_chip.forward(
+      layouter.namespace(|| "roi align rescale"),
+      &vec![dot_prods.iter().collect()],
+      &vec![zero, sf_cell],
+    )?;
+
+    let out_shape = vec![rois.len(), pooled.0, pooled.1, channels];
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(&out_shape), out).unwrap();
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for ROIAlignChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::DotProduct,
+      GadgetType::VarDivRound,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, Axis, IxDyn};
+
+use crate::gadgets::gadget::GadgetConfig;
+
+use super::{
+  layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+  multi_head_attention::MultiHeadAttentionChip,
+};
+
+// Inputs: [q (..., seq_q, d), k (..., seq_k, d), v (..., seq_k, d_v)], where the leading dimensions
+// (e.g., batch and heads) must match. Params: optional mask, encoded as for Softmax and broadcast
+// to (seq_q, seq_k).
+#[derive(Clone, Debug)]
+pub struct ScaledDotProductAttentionChip {}
+
+impl<F: PrimeField> Layer<F> for ScaledDotProductAttentionChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let (q, k, v) = (&tensors[0], &tensors[1], &tensors[2]);
+    let ndim = q.ndim();
+    assert!(ndim >= 2);
+    assert_eq!(k.ndim(), ndim);
+    assert_eq!(v.ndim(), ndim);
+    assert_eq!(q.shape()[..ndim - 2], k.shape()[..ndim - 2]);
+    assert_eq!(q.shape()[..ndim - 2], v.shape()[..ndim - 2]);
+
+    let (seq_q, d) = (q.shape()[ndim - 2], q.shape()[ndim - 1]);
+    let seq_k = k.shape()[ndim - 2];
+    let d_v = v.shape()[ndim - 1];
+    let num_slices = q.shape()[..ndim - 2].iter().product::<usize>();
+    let mask = MultiHeadAttentionChip::decode_mask(&layer_config.layer_params, &[seq_q, seq_k]);
+
+    let q = q
+      .clone()
+      .into_shape(IxDyn(&[num_slices, seq_q, d]))
+      .unwrap();
+    let k = k
+      .clone()
+      .into_shape(IxDyn(&[num_slices, seq_k, d]))
+      .unwrap();
+    let v = v
+      .clone()
+      .into_shape(IxDyn(&[num_slices, seq_k, d_v]))
+      .unwrap();
+
+    let mut outp = vec![];
+    for i in 0..num_slices {
+      let slice = |x: &AssignedTensor<F>| x.index_axis(Axis(0), i).to_owned();
+      let out = MultiHeadAttentionChip::scaled_dot_product_attention(
+        layouter.namespace(|| format!("sdpa {}", i)),
+        &slice(&q),
+        &slice(&k),
+        &slice(&v),
+        &mask,
+        constants,
+        gadget_config.clone(),
+      )?;
+      outp.extend(out.into_iter());
+    }
+
+    let mut out_shape = tensors[0].shape()[..ndim - 2].to_vec();
+    out_shape.extend([seq_q, d_v]);
+    let outp = Array::from_shape_vec(IxDyn(&out_shape), outp).unwrap();
+    Ok(vec![outp])
+  }
+}
+
+impl GadgetConsumer for ScaledDotProductAttentionChip {
+  fn used_gadgets(&self, layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    MultiHeadAttentionChip {}.used_gadgets(layer_params)
+  }
+}
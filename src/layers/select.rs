@@ -0,0 +1,73 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    greater_than::GreaterThanChip,
+    mux::MuxChip,
+  },
+  utils::helpers::broadcast,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+// Inputs: [condition, a, b]. Computes c ? a : b with the mux gadget, where c = condition > 0, so
+// conditions from the comparison layers (0 or the scale factor) can be used directly.
+#[derive(Clone, Debug)]
+pub struct SelectChip {}
+
+impl<F: PrimeField> Layer<F> for SelectChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    assert_eq!(tensors.len(), 3);
+    let (inp_a, inp_b) = broadcast(&tensors[1], &tensors[2]);
+    let (cond, inp_a) = broadcast(&tensors[0], &inp_a);
+    let (cond, inp_b) = broadcast(&cond, &inp_b);
+    assert_eq!(cond.shape(), inp_a.shape());
+    assert_eq!(cond.shape(), inp_b.shape());
+
+    let zero = constants.get(&0).unwrap().as_ref();
+    let cond_vec = cond.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let a_vec = inp_a.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let b_vec = inp_b.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let zeros = vec![zero; cond_vec.len()];
+
+    let greater_than_chip = GreaterThanChip::<F>::construct(gadget_config.clone());
+    let cond = greater_than_chip.forward(
+      layouter.namespace(|| "select cond"),
+      &vec![cond_vec, zeros],
+      &vec![zero],
+    )?;
+
+    let mux_chip = MuxChip::<F>::construct(gadget_config.clone());
+    let out = mux_chip.forward(
+      layouter.namespace(|| "select mux"),
+      &vec![cond.iter().collect(), a_vec, b_vec],
+      &vec![zero],
+    )?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp_b.shape()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for SelectChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::GreaterThan,
+      GadgetType::Mux,
+      GadgetType::InputLookup,
+    ]
+  }
+}
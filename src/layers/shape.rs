@@ -1,5 +1,8 @@
+pub mod batch_to_space;
 pub mod broadcast;
 pub mod concatenation;
+pub mod depth_to_space;
+pub mod flatten;
 pub mod mask_neg_inf;
 pub mod pack;
 pub mod pad;
@@ -8,5 +11,9 @@ pub mod reshape;
 pub mod resize_nn;
 pub mod rotate;
 pub mod slice;
+pub mod space_to_batch;
+pub mod space_to_depth;
 pub mod split;
+pub mod tile;
 pub mod transpose;
+pub mod unpack;
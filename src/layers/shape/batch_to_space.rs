@@ -0,0 +1,69 @@
+use std::{collections::HashMap, rc::Rc};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::gadget::GadgetConfig,
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::super::layer::{Layer, LayerConfig};
+
+// NHWC, params: [block_h, block_w, crop_top, crop_bottom, crop_left, crop_right]. The inverse of
+// SpaceToBatch: the blocks are moved from the batch dimension back into the spatial dimensions,
+// which are then cropped.
+pub struct BatchToSpaceChip {}
+
+impl<F: PrimeField> Layer<F> for BatchToSpaceChip {
+  fn forward(
+    &self,
+    _layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    _constants: &HashMap<i64, CellRc<F>>,
+    _gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let params = layer_config
+      .layer_params
+      .iter()
+      .map(|x| *x as usize)
+      .collect::<Vec<_>>();
+    let (block_h, block_w) = (params[0], params[1]);
+    let (crop_top, crop_bottom, crop_left, crop_right) =
+      (params[2], params[3], params[4], params[5]);
+
+    assert_eq!(inp.ndim(), 4);
+    let (inp_batch, h, w, c) = (
+      inp.shape()[0],
+      inp.shape()[1],
+      inp.shape()[2],
+      inp.shape()[3],
+    );
+    assert_eq!(inp_batch % (block_h * block_w), 0);
+    let batch = inp_batch / (block_h * block_w);
+
+    let output_shape = vec![
+      batch,
+      h * block_h - crop_top - crop_bottom,
+      w * block_w - crop_left - crop_right,
+      c,
+    ];
+    let out = Array::from_shape_fn(IxDyn(&output_shape), |idx| {
+      let (b, oh, ow, oc) = (idx[0], idx[1], idx[2], idx[3]);
+      let (fh, fw) = (oh + crop_top, ow + crop_left);
+      let block = (fh % block_h) * block_w + fw % block_w;
+      inp[[block * batch + b, fh / block_h, fw / block_w, oc]].clone()
+    });
+
+    println!("BatchToSpace: {:?} -> {:?}", inp.shape(), output_shape);
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for BatchToSpaceChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![]
+  }
+}
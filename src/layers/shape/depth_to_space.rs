@@ -0,0 +1,55 @@
+use std::{collections::HashMap, rc::Rc};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::gadget::GadgetConfig,
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::super::layer::{Layer, LayerConfig};
+
+// NHWC, params: [block_size]. The inverse of SpaceToDepth: the channels are moved into
+// block_size x block_size spatial blocks, in the same order as TFLite.
+pub struct DepthToSpaceChip {}
+
+impl<F: PrimeField> Layer<F> for DepthToSpaceChip {
+  fn forward(
+    &self,
+    _layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    _constants: &HashMap<i64, CellRc<F>>,
+    _gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let block_size = layer_config.layer_params[0] as usize;
+
+    assert_eq!(inp.ndim(), 4);
+    let (batch, h, w, c) = (
+      inp.shape()[0],
+      inp.shape()[1],
+      inp.shape()[2],
+      inp.shape()[3],
+    );
+    assert_eq!(c % (block_size * block_size), 0);
+    let out_c = c / (block_size * block_size);
+
+    let output_shape = vec![batch, h * block_size, w * block_size, out_c];
+    let out = Array::from_shape_fn(IxDyn(&output_shape), |idx| {
+      let (b, oh, ow, oc) = (idx[0], idx[1], idx[2], idx[3]);
+      let block = (oh % block_size) * block_size + ow % block_size;
+      inp[[b, oh / block_size, ow / block_size, block * out_c + oc]].clone()
+    });
+
+    println!("DepthToSpace: {:?} -> {:?}", inp.shape(), output_shape);
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for DepthToSpaceChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![]
+  }
+}
@@ -0,0 +1,50 @@
+use std::{collections::HashMap, rc::Rc};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::gadget::GadgetConfig,
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::super::layer::{Layer, LayerConfig};
+
+// Params: [axis], defaulting to 1 as in ONNX. The dimensions before the axis are flattened into the
+// first output dimension and the rest into the second.
+pub struct FlattenChip {}
+
+impl<F: PrimeField> Layer<F> for FlattenChip {
+  fn forward(
+    &self,
+    _layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    _constants: &HashMap<i64, CellRc<F>>,
+    _gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let axis = layer_config.layer_params.get(0).cloned().unwrap_or(1);
+    let axis = if axis < 0 {
+      (axis + inp.ndim() as i64) as usize
+    } else {
+      axis as usize
+    };
+    assert!(axis <= inp.ndim());
+
+    let outer = inp.shape()[..axis].iter().product::<usize>();
+    let inner = inp.shape()[axis..].iter().product::<usize>();
+    let shape = vec![outer, inner];
+
+    println!("Flatten: {:?} -> {:?}", inp.shape(), shape);
+    let flat = inp.iter().map(|x| x.clone()).collect();
+    let out = Array::from_shape_vec(IxDyn(&shape), flat).unwrap();
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for FlattenChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![]
+  }
+}
@@ -10,8 +10,53 @@ use crate::{
 
 use super::super::layer::{Layer, LayerConfig};
 
+// Params: either an explicit mask (ndim, shape, then the values, 1 = masked) that is broadcast to
+// the input, or a negative mode followed by its arguments:
+//   -1, [offset]: causal mask over the last two axes, masking (i, j) when j > i + offset
+//   -2, lengths: padding mask over the last axis, masking j >= length, with one length per entry of
+//     the first axis (or a single length for all of them)
 pub struct MaskNegInfChip {}
 
+impl MaskNegInfChip {
+  pub const CAUSAL: i64 = -1;
+  pub const PADDING: i64 = -2;
+
+  pub fn generate_mask(layer_params: &[i64], shape: &[usize]) -> Array<i64, IxDyn> {
+    let ndim = shape.len();
+    match layer_params[0] {
+      Self::CAUSAL => {
+        assert!(ndim >= 2);
+        let offset = layer_params.get(1).cloned().unwrap_or(0);
+        Array::from_shape_fn(IxDyn(shape), |idx| {
+          (idx[ndim - 1] as i64 > idx[ndim - 2] as i64 + offset) as i64
+        })
+      }
+      Self::PADDING => {
+        let lengths = &layer_params[1..];
+        assert!(lengths.len() == 1 || lengths.len() == shape[0]);
+        Array::from_shape_fn(IxDyn(shape), |idx| {
+          let length = if lengths.len() == 1 {
+            lengths[0]
+          } else {
+            lengths[idx[0]]
+          };
+          (idx[ndim - 1] as i64 >= length) as i64
+        })
+      }
+      _ => {
+        let mask_ndim = layer_params[0] as usize;
+        let mask_shape = layer_params[1..mask_ndim + 1]
+          .iter()
+          .map(|x| *x as usize)
+          .collect::<Vec<_>>();
+        let mask_vec = layer_params[mask_ndim + 1..].to_vec();
+        let mask = Array::from_shape_vec(IxDyn(&mask_shape), mask_vec).unwrap();
+        mask.broadcast(IxDyn(shape)).unwrap().to_owned()
+      }
+    }
+  }
+}
+
 impl<F: PrimeField> Layer<F> for MaskNegInfChip {
   fn forward(
     &self,
@@ -22,16 +67,7 @@ impl<F: PrimeField> Layer<F> for MaskNegInfChip {
     layer_config: &LayerConfig,
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
     let inp = &tensors[0];
-    let mask_ndim = layer_config.layer_params[0] as usize;
-    let mask_shape = layer_config.layer_params[1..mask_ndim + 1]
-      .iter()
-      .map(|x| *x as usize)
-      .collect::<Vec<_>>();
-
-    let mask_vec = layer_config.layer_params[mask_ndim + 1..].to_vec();
-    let mask = Array::from_shape_vec(IxDyn(&mask_shape), mask_vec).unwrap();
-    let mask = mask.broadcast(inp.raw_dim()).unwrap();
-
+    let mask = Self::generate_mask(&layer_config.layer_params, inp.shape());
     let min_val = gadget_config.min_val;
     let min_val = constants.get(&min_val).unwrap().clone();
     let mut out_vec = vec![];
@@ -10,6 +10,8 @@ use crate::{
 
 use super::super::layer::{Layer, LayerConfig};
 
+// Stacks the inputs along a new axis. Params: [axis], where negative axes count from the end of the
+// output shape.
 pub struct PackChip {}
 
 impl<F: PrimeField> Layer<F> for PackChip {
@@ -21,10 +23,9 @@ impl<F: PrimeField> Layer<F> for PackChip {
     _gadget_config: Rc<GadgetConfig>,
     layer_config: &LayerConfig,
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
-    let axis = layer_config.layer_params[0] as usize;
-    if axis > 1 {
-      panic!("Pack only supports axis=0 or axis=1");
-    }
+    let axis = layer_config.layer_params[0];
+    let out_ndim = tensors[0].ndim() as i64 + 1;
+    let axis = (if axis < 0 { axis + out_ndim } else { axis }) as usize;
 
     let expanded = tensors
       .into_iter()
@@ -51,21 +51,81 @@ pub fn pad<G: Clone>(
   padded
 }
 
+// Pads with the values of the input instead of a constant. Reflect excludes the edge
+// (abc -> cb|abc|ba), symmetric includes it (abc -> ba|abc|cb), and edge repeats it
+// (abc -> aa|abc|cc).
+pub fn pad_mode<G>(
+  input: &Array<Rc<G>, IxDyn>,
+  padding: &Vec<[usize; 2]>,
+  mode: PadMode,
+) -> Array<Rc<G>, IxDyn> {
+  assert_eq!(input.ndim(), padding.len());
+  let padded_shape = input
+    .shape()
+    .iter()
+    .zip(padding.iter())
+    .map(|(ax_len, [pad_lo, pad_hi])| ax_len + pad_lo + pad_hi)
+    .collect::<Vec<_>>();
+
+  Array::from_shape_fn(IxDyn(&padded_shape), |idx| {
+    let inp_idx = (0..input.ndim())
+      .map(|ax| {
+        let len = input.shape()[ax] as i64;
+        let i = idx[ax] as i64 - padding[ax][0] as i64;
+        let i = match mode {
+          PadMode::Constant => panic!("constant padding does not read from the input"),
+          PadMode::Reflect if i < 0 => -i,
+          PadMode::Reflect if i >= len => 2 * (len - 1) - i,
+          PadMode::Symmetric if i < 0 => -i - 1,
+          PadMode::Symmetric if i >= len => 2 * len - 1 - i,
+          PadMode::Edge => i.clamp(0, len - 1),
+          _ => i,
+        };
+        assert!(0 <= i && i < len, "padding is larger than the input");
+        i as usize
+      })
+      .collect::<Vec<_>>();
+    input[IxDyn(&inp_idx)].clone()
+  })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PadMode {
+  Constant,
+  Reflect,
+  Symmetric,
+  Edge,
+}
+
 pub struct PadChip {}
 
 pub struct PadConfig {
   pub padding: Vec<[usize; 2]>,
+  pub mode: PadMode,
 }
 
 impl PadChip {
+  // Params: [lo, hi] for every axis, then optionally the mode (0 = constant, 1 = reflect,
+  // 2 = symmetric, 3 = edge)
   pub fn param_vec_to_config(layer_params: Vec<i64>) -> PadConfig {
-    assert!(layer_params.len() % 2 == 0);
+    let (layer_params, mode) = if layer_params.len() % 2 == 1 {
+      let mode = match layer_params[layer_params.len() - 1] {
+        0 => PadMode::Constant,
+        1 => PadMode::Reflect,
+        2 => PadMode::Symmetric,
+        3 => PadMode::Edge,
+        x => panic!("unsupported padding mode: {}", x),
+      };
+      (&layer_params[..layer_params.len() - 1], mode)
+    } else {
+      (&layer_params[..], PadMode::Constant)
+    };
 
     let padding = layer_params
       .chunks(2)
       .map(|chunk| [chunk[0] as usize, chunk[1] as usize])
       .collect();
-    PadConfig { padding }
+    PadConfig { padding, mode }
   }
 }
 
@@ -84,7 +144,10 @@ impl<F: PrimeField> Layer<F> for PadChip {
 
     let zero = constants.get(&0).unwrap().clone();
     let padding = PadChip::param_vec_to_config(layer_config.layer_params.clone());
-    let padded = pad(input, padding.padding, &zero);
+    let padded = match padding.mode {
+      PadMode::Constant => pad(input, padding.padding, &zero),
+      mode => pad_mode(input, &padding.padding, mode),
+    };
 
     Ok(vec![padded])
   }
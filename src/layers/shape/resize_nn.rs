@@ -10,9 +10,41 @@ use crate::{
 
 use super::super::layer::{Layer, LayerConfig};
 
+// NHWC, params: [align_corners, half_pixel_centers]; the output shape is taken from the layer.
 pub struct ResizeNNChip {}
 
-// TODO: this does not work in general
+impl ResizeNNChip {
+  // The input index for each output index along an axis, as in TFLite
+  pub fn nearest(
+    inp_size: usize,
+    out_size: usize,
+    align_corners: bool,
+    half_pixel_centers: bool,
+  ) -> Vec<usize> {
+    let scale = if align_corners && out_size > 1 {
+      (inp_size - 1) as f64 / (out_size - 1) as f64
+    } else {
+      inp_size as f64 / out_size as f64
+    };
+
+    (0..out_size)
+      .map(|o| {
+        let pos = if half_pixel_centers {
+          (o as f64 + 0.5) * scale
+        } else {
+          o as f64 * scale
+        };
+        let idx = if align_corners {
+          pos.round()
+        } else {
+          pos.floor()
+        };
+        (idx as usize).min(inp_size - 1)
+      })
+      .collect()
+  }
+}
+
 impl<F: PrimeField> Layer<F> for ResizeNNChip {
   fn forward(
     &self,
@@ -24,27 +56,30 @@ impl<F: PrimeField> Layer<F> for ResizeNNChip {
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
     let inp = &tensors[0];
     let output_shape = layer_config.out_shapes[0].clone();
+    let align_corners = layer_config.layer_params.get(0).cloned().unwrap_or(0) == 1;
+    let half_pixel_centers = layer_config.layer_params.get(1).cloned().unwrap_or(0) == 1;
 
     assert_eq!(inp.ndim(), 4);
-    assert_eq!(inp.shape()[0], 1);
+    assert_eq!(inp.shape()[0], output_shape[0]);
     assert_eq!(inp.shape()[3], output_shape[3]);
 
-    let mut flat = vec![];
-    // Do nearest neighbor interpolation over batch, h, w, c
-    // The interpolation is over h and w
-    for b in 0..inp.shape()[0] {
-      for h in 0..output_shape[1] {
-        let h_in = (h as f64 * (inp.shape()[1] as f64 / output_shape[1] as f64)) as usize;
-        for w in 0..output_shape[2] {
-          let w_in = (w as f64 * (inp.shape()[2] as f64 / output_shape[2] as f64)) as usize;
-          for c in 0..inp.shape()[3] {
-            flat.push(inp[[b, h_in, w_in, c]].clone());
-          }
-        }
-      }
-    }
-
-    let outp = Array::from_shape_vec(IxDyn(&output_shape), flat).unwrap();
+    let h_in = Self::nearest(
+      inp.shape()[1],
+      output_shape[1],
+      align_corners,
+      half_pixel_centers,
+    );
+    let w_in = Self::nearest(
+      inp.shape()[2],
+      output_shape[2],
+      align_corners,
+      half_pixel_centers,
+    );
+
+    // The interpolation is over h and w, so this only copies cells
+    let outp = Array::from_shape_fn(IxDyn(&output_shape), |idx| {
+      inp[[idx[0], h_in[idx[1]], w_in[idx[2]], idx[3]]].clone()
+    });
     Ok(vec![outp])
   }
 }
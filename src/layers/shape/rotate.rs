@@ -1,8 +1,7 @@
-// TODO: The implementation is not ideal.
-
 use std::{collections::HashMap, rc::Rc};
 
 use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
 
 use crate::{
   gadgets::gadget::GadgetConfig,
@@ -18,7 +17,7 @@ pub struct RotateChip {}
 // [1 2 3 4]
 // [5 6 7 8]
 //
-// params: [1] -- flip axis 1 only
+// params: [1] -- flip axis 1 only (negative axes count from the end)
 // output:
 // [4 3 2 1]
 // [8 7 6 5]
@@ -33,35 +32,27 @@ impl<F: PrimeField> Layer<F> for RotateChip {
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
     let inp = &tensors[0];
     let params = &layer_config.layer_params;
+    let ndim = inp.ndim();
 
-    assert!(inp.shape().len() == 4);
-
-    let mut flip = vec![false; 4];
+    let mut flip = vec![false; ndim];
     for p in params {
-      flip[*p as usize] = true;
+      let axis = if *p < 0 { *p + ndim as i64 } else { *p };
+      flip[axis as usize] = true;
     }
     let shape = inp.shape();
 
-    println!("Rotate: {:?} -> {:?}", inp.shape(), shape);
-
-    let mut out = inp.clone();
-
-    for i in 0..shape[0] {
-      for j in 0..shape[1] {
-        for k in 0..shape[2] {
-          for l in 0..shape[3] {
-            let [ix, jx, kx, lx]: [usize; 4] = [i, j, k, l]
-              .iter()
-              .enumerate()
-              .map(|(idx, x)| if flip[idx] { shape[idx] - 1 - *x } else { *x })
-              .collect::<Vec<_>>()
-              .try_into()
-              .unwrap();
-            out[[ix, jx, kx, lx]] = inp[[i, j, k, l]].clone();
+    let out = Array::from_shape_fn(IxDyn(shape), |idx| {
+      let inp_idx = (0..ndim)
+        .map(|i| {
+          if flip[i] {
+            shape[i] - 1 - idx[i]
+          } else {
+            idx[i]
           }
-        }
-      }
-    }
+        })
+        .collect::<Vec<_>>();
+      inp[IxDyn(&inp_idx)].clone()
+    });
 
     Ok(vec![out])
   }
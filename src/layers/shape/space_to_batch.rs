@@ -0,0 +1,76 @@
+use std::{collections::HashMap, rc::Rc};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::gadget::GadgetConfig,
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::super::layer::{Layer, LayerConfig};
+
+// NHWC, params: [block_h, block_w, pad_top, pad_bottom, pad_left, pad_right]. The spatial
+// dimensions are zero padded and then split into blocks that are moved into the batch dimension.
+pub struct SpaceToBatchChip {}
+
+impl<F: PrimeField> Layer<F> for SpaceToBatchChip {
+  fn forward(
+    &self,
+    _layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    _gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let params = layer_config
+      .layer_params
+      .iter()
+      .map(|x| *x as usize)
+      .collect::<Vec<_>>();
+    let (block_h, block_w) = (params[0], params[1]);
+    let (pad_top, pad_bottom, pad_left, pad_right) = (params[2], params[3], params[4], params[5]);
+    let zero = constants.get(&0).unwrap();
+
+    assert_eq!(inp.ndim(), 4);
+    let (batch, h, w, c) = (
+      inp.shape()[0],
+      inp.shape()[1],
+      inp.shape()[2],
+      inp.shape()[3],
+    );
+    let padded_h = h + pad_top + pad_bottom;
+    let padded_w = w + pad_left + pad_right;
+    assert_eq!(padded_h % block_h, 0);
+    assert_eq!(padded_w % block_w, 0);
+
+    let output_shape = vec![
+      batch * block_h * block_w,
+      padded_h / block_h,
+      padded_w / block_w,
+      c,
+    ];
+    let out = Array::from_shape_fn(IxDyn(&output_shape), |idx| {
+      let (ob, oh, ow, oc) = (idx[0], idx[1], idx[2], idx[3]);
+      let b = ob % batch;
+      let block = ob / batch;
+      let ph = oh * block_h + block / block_w;
+      let pw = ow * block_w + block % block_w;
+      if ph < pad_top || ph >= pad_top + h || pw < pad_left || pw >= pad_left + w {
+        zero.clone()
+      } else {
+        inp[[b, ph - pad_top, pw - pad_left, oc]].clone()
+      }
+    });
+
+    println!("SpaceToBatch: {:?} -> {:?}", inp.shape(), output_shape);
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for SpaceToBatchChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![]
+  }
+}
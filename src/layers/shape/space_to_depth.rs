@@ -0,0 +1,61 @@
+use std::{collections::HashMap, rc::Rc};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::gadget::GadgetConfig,
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::super::layer::{Layer, LayerConfig};
+
+// NHWC, params: [block_size]. Each block_size x block_size spatial block is moved into the
+// channels, in the same order as TFLite.
+pub struct SpaceToDepthChip {}
+
+impl<F: PrimeField> Layer<F> for SpaceToDepthChip {
+  fn forward(
+    &self,
+    _layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    _constants: &HashMap<i64, CellRc<F>>,
+    _gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let block_size = layer_config.layer_params[0] as usize;
+
+    assert_eq!(inp.ndim(), 4);
+    let (batch, h, w, c) = (
+      inp.shape()[0],
+      inp.shape()[1],
+      inp.shape()[2],
+      inp.shape()[3],
+    );
+    assert_eq!(h % block_size, 0);
+    assert_eq!(w % block_size, 0);
+
+    let output_shape = vec![
+      batch,
+      h / block_size,
+      w / block_size,
+      c * block_size * block_size,
+    ];
+    let out = Array::from_shape_fn(IxDyn(&output_shape), |idx| {
+      let (b, oh, ow, oc) = (idx[0], idx[1], idx[2], idx[3]);
+      let block = oc / c;
+      let (bh, bw) = (block / block_size, block % block_size);
+      inp[[b, oh * block_size + bh, ow * block_size + bw, oc % c]].clone()
+    });
+
+    println!("SpaceToDepth: {:?} -> {:?}", inp.shape(), output_shape);
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for SpaceToDepthChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![]
+  }
+}
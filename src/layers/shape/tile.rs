@@ -0,0 +1,56 @@
+use std::{collections::HashMap, rc::Rc};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::gadget::GadgetConfig,
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::super::layer::{Layer, LayerConfig};
+
+// Params: the number of repetitions along each axis
+pub struct TileChip {}
+
+impl<F: PrimeField> Layer<F> for TileChip {
+  fn forward(
+    &self,
+    _layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    _constants: &HashMap<i64, CellRc<F>>,
+    _gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let multiples = layer_config
+      .layer_params
+      .iter()
+      .map(|x| *x as usize)
+      .collect::<Vec<_>>();
+    assert_eq!(inp.ndim(), multiples.len());
+
+    let output_shape = inp
+      .shape()
+      .iter()
+      .zip(multiples.iter())
+      .map(|(dim, mult)| dim * mult)
+      .collect::<Vec<_>>();
+
+    let out = Array::from_shape_fn(IxDyn(&output_shape), |idx| {
+      let inp_idx = (0..idx.ndim())
+        .map(|i| idx[i] % inp.shape()[i])
+        .collect::<Vec<_>>();
+      inp[IxDyn(&inp_idx)].clone()
+    });
+
+    println!("Tile: {:?} -> {:?}", inp.shape(), output_shape);
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for TileChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![]
+  }
+}
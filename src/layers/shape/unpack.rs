@@ -0,0 +1,46 @@
+use std::{collections::HashMap, rc::Rc};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+use ndarray::Axis;
+
+use crate::{
+  gadgets::gadget::{GadgetConfig, GadgetType},
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+};
+
+use super::super::layer::{Layer, LayerConfig};
+
+// The inverse of Pack: outputs every slice along an axis, with that axis removed.
+// Params: [axis], where negative axes count from the end.
+pub struct UnpackChip {}
+
+impl<F: PrimeField> Layer<F> for UnpackChip {
+  fn forward(
+    &self,
+    _layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    _constants: &HashMap<i64, CellRc<F>>,
+    _gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let axis = layer_config.layer_params[0];
+    let axis = (if axis < 0 {
+      axis + inp.ndim() as i64
+    } else {
+      axis
+    }) as usize;
+
+    let out = inp
+      .axis_iter(Axis(axis))
+      .map(|x| x.to_owned())
+      .collect::<Vec<_>>();
+    Ok(out)
+  }
+}
+
+impl GadgetConsumer for UnpackChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<GadgetType> {
+    vec![]
+  }
+}
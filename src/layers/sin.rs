@@ -0,0 +1,93 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType},
+  nonlinear::sin::SinGadgetChip,
+  var_mod::VarModChip,
+};
+
+use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+
+#[derive(Clone, Debug)]
+pub struct SinChip {}
+
+impl SinChip {
+  // Reduces x modulo round(2 * pi * sf), so that the lookup stays within the table for any x. The
+  // period is rounded to the scale factor, so the phase drifts by at most 0.5 / sf per period.
+  pub fn reduce_period<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    inp: Vec<&AssignedCell<F, F>>,
+    zero: &AssignedCell<F, F>,
+    gadget_config: Rc<GadgetConfig>,
+  ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+    let period = (2. * std::f64::consts::PI * gadget_config.scale_factor as f64).round() as u64;
+    assert!(period as i64 <= gadget_config.max_val, "2 pi is out of the table range");
+    let period = layouter.assign_region(
+      || "sin period",
+      |mut region| {
+        region.assign_fixed(
+          || "sin period",
+          gadget_config.fixed_columns[0],
+          0,
+          || Value::known(F::from(period)),
+        )
+      },
+    )?;
+
+    // The mod chip outputs the quotients followed by the remainders
+    let num_inps = inp.len();
+    let var_mod_chip = VarModChip::<F>::construct(gadget_config.clone());
+    let out = var_mod_chip.forward(
+      layouter.namespace(|| "sin period mod"),
+      &vec![inp],
+      &vec![zero, &period],
+    )?;
+    Ok(out[num_inps..].to_vec())
+  }
+}
+
+impl<F: PrimeField> Layer<F> for SinChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    _layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let zero = constants.get(&0).unwrap().as_ref();
+
+    let inp_vec = Self::reduce_period(
+      layouter.namespace(|| "sin reduce"),
+      inp_vec,
+      zero,
+      gadget_config.clone(),
+    )?;
+    let inp_vec = inp_vec.iter().collect::<Vec<_>>();
+
+    let sin_chip = SinGadgetChip::<F>::construct(gadget_config.clone());
+    let vec_inps = vec![inp_vec];
+    let constants = vec![zero];
+    let out = sin_chip.forward(layouter.namespace(|| "sin chip"), &vec_inps, &constants)?;
+
+    let out = out.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let out = Array::from_shape_vec(IxDyn(inp.shape()), out).unwrap();
+
+    Ok(vec![out])
+  }
+}
+
+impl GadgetConsumer for SinChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![GadgetType::Sin, GadgetType::VarMod, GadgetType::InputLookup]
+  }
+}
@@ -5,7 +5,7 @@ use halo2_proofs::{
   halo2curves::ff::PrimeField,
   plonk::Error,
 };
-use ndarray::{s, Array, IxDyn};
+use ndarray::{Array, Axis, IxDyn};
 
 use crate::gadgets::{
   adder::AdderChip,
@@ -16,7 +16,10 @@ use crate::gadgets::{
   var_div_big3::VarDivRoundBig3Chip,
 };
 
-use super::layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig};
+use super::{
+  layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+  shape::mask_neg_inf::MaskNegInfChip,
+};
 
 #[derive(Clone, Debug)]
 pub struct SoftmaxChip {}
@@ -113,6 +116,40 @@ impl SoftmaxChip {
 
     Ok(dived)
   }
+
+  // Params: an optional mask in any of the MaskNegInf encodings, optionally followed by the axis
+  // (default: the last axis). The axis can only follow an explicit or causal mask, since a padding
+  // mask takes the rest of the params.
+  pub fn decode_params(layer_params: &[i64], shape: &[usize]) -> (Array<i64, IxDyn>, usize) {
+    let (mask_len, axis) = match layer_params.len() {
+      0 => (0, -1),
+      1 => (0, layer_params[0]),
+      _ => {
+        let mask_len = match layer_params[0] {
+          MaskNegInfChip::CAUSAL => 2,
+          MaskNegInfChip::PADDING => layer_params.len(),
+          mask_ndim => {
+            let mask_ndim = mask_ndim as usize;
+            let mask_size = layer_params[1..mask_ndim + 1].iter().product::<i64>() as usize;
+            1 + mask_ndim + mask_size
+          }
+        };
+        (mask_len, layer_params.get(mask_len).cloned().unwrap_or(-1))
+      }
+    };
+
+    let mask = if mask_len == 0 {
+      Array::zeros(IxDyn(shape))
+    } else {
+      MaskNegInfChip::generate_mask(&layer_params[..mask_len], shape)
+    };
+    let axis = if axis < 0 {
+      axis + shape.len() as i64
+    } else {
+      axis
+    };
+    (mask, axis as usize)
+  }
 }
 
 impl<F: PrimeField> Layer<F> for SoftmaxChip {
@@ -125,75 +162,25 @@ impl<F: PrimeField> Layer<F> for SoftmaxChip {
     layer_config: &LayerConfig,
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
     let inp = &tensors[0];
-    assert!(inp.ndim() == 2 || inp.ndim() == 3 || inp.ndim() == 4);
-    if inp.ndim() == 4 {
-      assert_eq!(inp.shape()[0], 1);
-    }
-
-    let inp_shape = inp.shape().iter().map(|x| *x).collect::<Vec<_>>();
-    let mask = if layer_config.layer_params.len() == 0 {
-      Array::from_shape_fn(IxDyn(&inp_shape), |_| 0)
-    } else {
-      let mask_shape_len = layer_config.layer_params[0] as usize;
-      let mask_shape = layer_config.layer_params[1..(1 + mask_shape_len)]
-        .iter()
-        .map(|x| *x as usize)
-        .collect::<Vec<_>>();
-      let mask = layer_config.layer_params[(1 + mask_shape_len)..].to_vec();
-      let mask = Array::from_shape_vec(IxDyn(&mask_shape), mask).unwrap();
-      let mask = mask.broadcast(IxDyn(&inp_shape)).unwrap().to_owned();
-      mask
-    };
-
-    let shape = if inp.ndim() == 2 || inp.ndim() == 3 {
-      inp.shape().iter().map(|x| *x).collect::<Vec<_>>()
-    } else {
-      vec![inp.shape()[1], inp.shape()[2], inp.shape()[3]]
-    };
-    let inp = inp.to_owned().into_shape(shape.clone()).unwrap();
-    let mask = mask.into_shape(shape.clone()).unwrap();
-
-    let mut outp = vec![];
-    if inp.ndim() == 2 {
-      for i in 0..shape[0] {
-        let inp_slice = inp.slice(s![i, ..]);
-        let inp_flat = inp_slice.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
-        let mask_slice = mask.slice(s![i, ..]);
-        let mask_flat = mask_slice.iter().map(|x| *x as i64).collect::<Vec<_>>();
-        let dived = Self::softmax_flat(
-          layouter.namespace(|| format!("softmax {}", i)),
-          constants,
-          inp_flat,
-          gadget_config.clone(),
-          &mask_flat,
-        )
-        .unwrap();
-        outp.extend(dived);
+    let (mask, axis) = Self::decode_params(&layer_config.layer_params, inp.shape());
+
+    let mut outp = inp.clone();
+    let lanes = outp.lanes_mut(Axis(axis)).into_iter();
+    for (i, (mut lane, mask_lane)) in lanes.zip(mask.lanes(Axis(axis))).enumerate() {
+      let inp_flat = lane.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+      let mask_flat = mask_lane.iter().map(|x| *x).collect::<Vec<_>>();
+      let dived = Self::softmax_flat(
+        layouter.namespace(|| format!("softmax {}", i)),
+        constants,
+        inp_flat,
+        gadget_config.clone(),
+        &mask_flat,
+      )?;
+      for (cell, val) in lane.iter_mut().zip(dived.into_iter()) {
+        *cell = Rc::new(val);
       }
-    } else if inp.ndim() == 3 {
-      for i in 0..shape[0] {
-        for j in 0..shape[1] {
-          let inp_slice = inp.slice(s![i, j, ..]);
-          let inp_flat = inp_slice.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
-          let mask_slice = mask.slice(s![i, j, ..]);
-          let mask_flat = mask_slice.iter().map(|x| *x as i64).collect::<Vec<_>>();
-          let dived = Self::softmax_flat(
-            layouter.namespace(|| format!("softmax {} {}", i, j)),
-            constants,
-            inp_flat,
-            gadget_config.clone(),
-            &mask_flat,
-          )
-          .unwrap();
-          outp.extend(dived);
-        }
-      }
-    } else {
-      panic!("Not implemented");
     }
 
-    let outp = outp.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
-    let outp = Array::from_shape_vec(IxDyn(inp.shape()), outp).unwrap();
     Ok(vec![outp])
   }
 }
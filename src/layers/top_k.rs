@@ -0,0 +1,202 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{
+  circuit::{Layouter, Value},
+  halo2curves::ff::PrimeField,
+  plonk::Error,
+};
+use ndarray::{Array, IxDyn};
+
+use crate::{
+  gadgets::{
+    adder::AdderChip,
+    gadget::{Gadget, GadgetConfig, GadgetType},
+    mul_pairs::MulPairsChip,
+    nonlinear::relu::ReluChip,
+    sub_pairs::SubPairsChip,
+  },
+  layers::layer::{AssignedTensor, CellRc, GadgetConsumer},
+  utils::helpers::convert_pos_int,
+};
+
+use super::layer::{Layer, LayerConfig};
+
+// Reveals the top-k entries of the input and the sum of the remaining entries.
+// Outputs: the input with all but the top-k entries zeroed out, and the sum of the rest (shape [1])
+//
+// The prover supplies a 0/1 mask with k ones and a threshold t. The circuit checks that every
+// selected entry is >= t and every unselected entry is <= t.
+pub struct TopKChip {}
+
+impl TopKChip {
+  pub fn get_mask(vals: &Vec<i128>, k: usize) -> (Vec<i128>, i128) {
+    let mut idxes = (0..vals.len()).collect::<Vec<_>>();
+    idxes.sort_by(|a, b| vals[*b].cmp(&vals[*a]));
+    let mut mask = vec![0; vals.len()];
+    for idx in idxes.iter().take(k) {
+      mask[*idx] = 1;
+    }
+    (mask, vals[idxes[k - 1]])
+  }
+}
+
+impl<F: PrimeField> Layer<F> for TopKChip {
+  fn forward(
+    &self,
+    mut layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let k = layer_config.layer_params[0] as usize;
+    assert!(k > 0 && k <= inp.len());
+
+    let zero = constants.get(&0).unwrap().as_ref();
+    let one = constants.get(&1).unwrap().as_ref();
+
+    let inp_vec = inp.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let vals = inp_vec
+      .iter()
+      .map(|x| convert_pos_int(x.value().map(|x| x.to_owned())))
+      .collect::<Vec<_>>();
+    let (mask, threshold) = Self::get_mask(&vals, k);
+
+    let to_field = |x: i128| {
+      let bias = 1 << 60;
+      F::from((x + bias) as u64) - F::from(bias as u64)
+    };
+    let (mask, threshold) = layouter.assign_region(
+      || "top k witness",
+      |mut region| {
+        let num_cols = gadget_config.columns.len();
+        let mut mask_cells = vec![];
+        for (i, m) in mask.iter().enumerate() {
+          let cell = region.assign_advice(
+            || "top k mask",
+            gadget_config.columns[i % num_cols],
+            i / num_cols,
+            || Value::known(to_field(*m)),
+          )?;
+          mask_cells.push(cell);
+        }
+        let threshold = region.assign_advice(
+          || "top k threshold",
+          gadget_config.columns[mask.len() % num_cols],
+          mask.len() / num_cols,
+          || Value::known(to_field(threshold)),
+        )?;
+        Ok((mask_cells, threshold))
+      },
+    )?;
+    let mask = mask.iter().collect::<Vec<_>>();
+    let n = mask.len();
+
+    let adder_chip = AdderChip::<F>::construct(gadget_config.clone());
+    let mul_pairs_chip = MulPairsChip::<F>::construct(gadget_config.clone());
+    let sub_pairs_chip = SubPairsChip::<F>::construct(gadget_config.clone());
+    let relu_chip = ReluChip::<F>::construct(gadget_config.clone());
+
+    // The mask is binary
+    let mask_sq = mul_pairs_chip.forward(
+      layouter.namespace(|| "top k mask sq"),
+      &vec![mask.clone(), mask.clone()],
+      &vec![zero],
+    )?;
+    // The mask has exactly k ones
+    let mask_sum = adder_chip.forward(
+      layouter.namespace(|| "top k mask sum"),
+      &vec![mask.clone()],
+      &vec![zero],
+    )?;
+    let k_cell = adder_chip.forward(
+      layouter.namespace(|| "top k k"),
+      &vec![vec![one; k]],
+      &vec![zero],
+    )?;
+
+    // mask * (x - t) >= 0 and (1 - mask) * (t - x) >= 0
+    let ts = vec![&threshold; n];
+    let x_minus_t = sub_pairs_chip.forward(
+      layouter.namespace(|| "top k x - t"),
+      &vec![inp_vec.clone(), ts.clone()],
+      &vec![zero],
+    )?;
+    let t_minus_x = sub_pairs_chip.forward(
+      layouter.namespace(|| "top k t - x"),
+      &vec![ts, inp_vec.clone()],
+      &vec![zero],
+    )?;
+    let not_mask = sub_pairs_chip.forward(
+      layouter.namespace(|| "top k not mask"),
+      &vec![vec![one; n], mask.clone()],
+      &vec![zero],
+    )?;
+    let sel_bound = mul_pairs_chip.forward(
+      layouter.namespace(|| "top k selected bound"),
+      &vec![mask.clone(), x_minus_t.iter().collect()],
+      &vec![zero],
+    )?;
+    let unsel_bound = mul_pairs_chip.forward(
+      layouter.namespace(|| "top k unselected bound"),
+      &vec![not_mask.iter().collect(), t_minus_x.iter().collect()],
+      &vec![zero],
+    )?;
+    let bounds = sel_bound.iter().chain(unsel_bound.iter()).collect::<Vec<_>>();
+    let relu_bounds = relu_chip.forward(
+      layouter.namespace(|| "top k relu"),
+      &vec![bounds.clone()],
+      &vec![zero],
+    )?;
+
+    layouter.assign_region(
+      || "top k equality check",
+      |mut region| {
+        for (m, m_sq) in mask.iter().zip(mask_sq.iter()) {
+          region.constrain_equal(m.cell(), m_sq.cell())?;
+        }
+        region.constrain_equal(mask_sum[0].cell(), k_cell[0].cell())?;
+        for (bound, relu_bound) in bounds.iter().zip(relu_bounds.iter()) {
+          region.constrain_equal(bound.cell(), relu_bound.cell())?;
+        }
+        Ok(())
+      },
+    )?;
+
+    // Zero out everything but the top k and sum the rest
+    let selected = mul_pairs_chip.forward(
+      layouter.namespace(|| "top k selected"),
+      &vec![mask.clone(), inp_vec.clone()],
+      &vec![zero],
+    )?;
+    let rest = mul_pairs_chip.forward(
+      layouter.namespace(|| "top k rest"),
+      &vec![not_mask.iter().collect(), inp_vec],
+      &vec![zero],
+    )?;
+    let rest_sum = adder_chip.forward(
+      layouter.namespace(|| "top k rest sum"),
+      &vec![rest.iter().collect()],
+      &vec![zero],
+    )?;
+
+    let selected = selected.into_iter().map(|x| Rc::new(x)).collect::<Vec<_>>();
+    let selected = Array::from_shape_vec(inp.raw_dim(), selected).unwrap();
+    let rest_sum = Array::from_shape_vec(IxDyn(&[1]), vec![Rc::new(rest_sum[0].clone())]).unwrap();
+
+    Ok(vec![selected, rest_sum])
+  }
+}
+
+impl GadgetConsumer for TopKChip {
+  fn used_gadgets(&self, _layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    vec![
+      GadgetType::Adder,
+      GadgetType::MulPairs,
+      GadgetType::SubPairs,
+      GadgetType::Relu,
+      GadgetType::InputLookup,
+    ]
+  }
+}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, rc::Rc, vec};
+
+use halo2_proofs::{circuit::Layouter, halo2curves::ff::PrimeField, plonk::Error};
+
+use crate::gadgets::gadget::GadgetConfig;
+
+use super::{
+  layer::{AssignedTensor, CellRc, GadgetConsumer, Layer, LayerConfig},
+  resize_bilinear::ResizeBilinearChip,
+  shape::resize_nn::ResizeNNChip,
+};
+
+// NHWC, params: [mode, half_pixel_centers, scale_n, scale_h, scale_w, scale_c], where mode is 0
+// for nearest and 1 for bilinear and the scales are multiplied by the scale factor. The output
+// size along each axis is floor(size * scale), as in ONNX Resize. Only h and w can be resized.
+#[derive(Clone, Debug)]
+pub struct UpsampleChip {}
+
+impl UpsampleChip {
+  pub fn output_shape(
+    inp_shape: &[usize],
+    layer_params: &Vec<i64>,
+    scale_factor: u64,
+  ) -> Vec<usize> {
+    let scales = &layer_params[2..];
+    assert_eq!(inp_shape.len(), scales.len());
+    inp_shape
+      .iter()
+      .zip(scales.iter())
+      .map(|(size, scale)| (*size as i64 * scale / scale_factor as i64) as usize)
+      .collect()
+  }
+}
+
+impl<F: PrimeField> Layer<F> for UpsampleChip {
+  fn forward(
+    &self,
+    layouter: impl Layouter<F>,
+    tensors: &Vec<AssignedTensor<F>>,
+    constants: &HashMap<i64, CellRc<F>>,
+    gadget_config: Rc<GadgetConfig>,
+    layer_config: &LayerConfig,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let inp = &tensors[0];
+    let params = &layer_config.layer_params;
+    let output_shape = Self::output_shape(inp.shape(), params, gadget_config.scale_factor);
+    assert_eq!(output_shape[0], inp.shape()[0]);
+    assert_eq!(output_shape[3], inp.shape()[3]);
+
+    let resize_config = LayerConfig {
+      layer_params: vec![0, params[1]],
+      out_shapes: vec![output_shape],
+      ..layer_config.clone()
+    };
+    match params[0] {
+      0 => ResizeNNChip {}.forward(layouter, tensors, constants, gadget_config, &resize_config),
+      1 => {
+        ResizeBilinearChip {}.forward(layouter, tensors, constants, gadget_config, &resize_config)
+      }
+      _ => panic!("unsupported upsample mode {}", params[0]),
+    }
+  }
+}
+
+impl GadgetConsumer for UpsampleChip {
+  fn used_gadgets(&self, layer_params: Vec<i64>) -> Vec<crate::gadgets::gadget::GadgetType> {
+    match layer_params[0] {
+      0 => ResizeNNChip {}.used_gadgets(layer_params),
+      1 => ResizeBilinearChip {}.used_gadgets(layer_params),
+      _ => panic!("unsupported upsample mode {}", layer_params[0]),
+    }
+  }
+}
@@ -2,6 +2,7 @@
 
 pub mod commitments;
 pub mod gadgets;
+pub mod interface;
 pub mod layers;
 pub mod model;
 pub mod utils;
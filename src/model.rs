@@ -7,11 +7,15 @@ use std::{
 
 use halo2_proofs::{
   circuit::{Layouter, SimpleFloorPlanner, Value},
-  halo2curves::ff::{FromUniformBytes, PrimeField},
+  halo2curves::{
+    bn256,
+    ff::{FromUniformBytes, PrimeField},
+    pasta,
+  },
   plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
 };
 use lazy_static::lazy_static;
-use ndarray::{Array, IxDyn};
+use ndarray::{Array, Axis, IxDyn};
 use num_bigint::BigUint;
 
 use crate::{
@@ -23,14 +27,28 @@ use crate::{
   gadgets::{
     add_pairs::AddPairsChip,
     adder::AdderChip,
+    bias_div_floor_relu6::BiasDivFloorRelu6Chip,
     bias_div_round_relu6::BiasDivRoundRelu6Chip,
+    bit_decomposition::BitDecompositionChip,
     dot_prod::DotProductChip,
-    gadget::{Gadget, GadgetConfig, GadgetType},
+    gadget::{Gadget, GadgetConfig, GadgetType, DEFAULT_RESERVED_ROWS},
+    greater_than::GreaterThanChip,
     input_lookup::InputLookupChip,
+    mac::MacChip,
     max::MaxChip,
     mul_pairs::MulPairsChip,
+    mux::MuxChip,
+    nonlinear::custom::{CustomFunctions, CustomNonlinearGadgetChip},
+    nonlinear::erf::ErfGadgetChip,
+    nonlinear::piecewise_linear::PiecewiseLinearGadgetChip,
+    nonlinear::gelu::GeluGadgetChip,
+    nonlinear::softplus::SoftplusGadgetChip,
+    nonlinear::{cos::CosGadgetChip, ln::LnGadgetChip, sign::SignChip, sin::SinGadgetChip},
     nonlinear::{exp::ExpGadgetChip, pow::PowGadgetChip, relu::ReluChip, tanh::TanhGadgetChip},
     nonlinear::{logistic::LogisticGadgetChip, rsqrt::RsqrtGadgetChip, sqrt::SqrtGadgetChip},
+    range_check::RangeCheckChip,
+    shift::ShiftChip,
+    sign_bit::SignBitChip,
     sqrt_big::SqrtBigChip,
     square::SquareGadgetChip,
     squared_diff::SquaredDiffGadgetChip,
@@ -39,42 +57,90 @@ use crate::{
     var_div::VarDivRoundChip,
     var_div_big::VarDivRoundBigChip,
     var_div_big3::VarDivRoundBig3Chip,
+    var_mod::VarModChip,
   },
   layers::{
-    arithmetic::{add::AddChip, div_var::DivVarChip, mul::MulChip, sub::SubChip},
+    abs::AbsChip,
+    arg_max::ArgMaxChip,
+    arithmetic::{
+      add::AddChip,
+      compare::{EqualChip, GreaterChip, LessChip},
+      div::DivChip,
+      div_var::DivVarChip,
+      logical::{LogicalAndChip, LogicalNotChip, LogicalOrChip},
+      max::MaximumChip,
+      min::MinChip,
+      mul::MulChip,
+      sub::SubChip,
+    },
+    assert_close::AssertCloseChip,
     avg_pool_2d::AvgPool2DChip,
     batch_mat_mul::BatchMatMulChip,
+    clip::ClipChip,
+    conv1d::Conv1DChip,
     conv2d::Conv2DChip,
+    conv3d::Conv3DChip,
+    cos::CosChip,
+    cum_sum::CumSumChip,
+    custom_nonlinear::CustomNonlinearChip,
     dag::{DAGLayerChip, DAGLayerConfig},
+    div_fixed::DivFixedChip,
+    einsum::EinsumChip,
+    exp::ExpChip,
     fully_connected::{FullyConnectedChip, FullyConnectedConfig},
+    gelu::GeluChip,
+    gru::GRUChip,
+    hardmax::HardmaxChip,
+    l2_norm::L2NormChip,
     layer::{AssignedTensor, CellRc, GadgetConsumer, LayerConfig, LayerType},
+    log::LogChip,
+    log_softmax::LogSoftmaxChip,
     logistic::LogisticChip,
+    lrn::LRNChip,
     max_pool_2d::MaxPool2DChip,
     mean::MeanChip,
+    mish::MishChip,
+    multi_head_attention::MultiHeadAttentionChip,
+    non_max_suppression::NonMaxSuppressionChip,
     noop::NoopChip,
+    piecewise_linear::PiecewiseLinearChip,
     pow::PowChip,
+    reciprocal::ReciprocalChip,
+    reduce_max::ReduceMaxChip,
+    reduce_sum::ReduceSumChip,
+    requantize::RequantizeChip,
+    resize_bilinear::ResizeBilinearChip,
+    roi_align::ROIAlignChip,
     rsqrt::RsqrtChip,
+    scaled_dot_product_attention::ScaledDotProductAttentionChip,
+    select::SelectChip,
     shape::{
-      broadcast::BroadcastChip, concatenation::ConcatenationChip, mask_neg_inf::MaskNegInfChip,
+      batch_to_space::BatchToSpaceChip, broadcast::BroadcastChip, concatenation::ConcatenationChip,
+      depth_to_space::DepthToSpaceChip, flatten::FlattenChip, mask_neg_inf::MaskNegInfChip,
       pack::PackChip, pad::PadChip, permute::PermuteChip, reshape::ReshapeChip,
-      resize_nn::ResizeNNChip, rotate::RotateChip, slice::SliceChip, split::SplitChip,
-      transpose::TransposeChip,
+      resize_nn::ResizeNNChip, rotate::RotateChip, slice::SliceChip,
+      space_to_batch::SpaceToBatchChip, space_to_depth::SpaceToDepthChip, split::SplitChip,
+      tile::TileChip, transpose::TransposeChip, unpack::UnpackChip,
     },
+    sin::SinChip,
     softmax::SoftmaxChip,
+    softplus::SoftplusChip,
     sqrt::SqrtChip,
     square::SquareChip,
     squared_diff::SquaredDiffChip,
     tanh::TanhChip,
+    top_k::TopKChip,
     update::UpdateChip,
+    upsample::UpsampleChip,
   },
   utils::{
+    checks::check_model,
     helpers::{convert_to_bigint, RAND_START_IDX},
-    loader::{load_model_msgpack, ModelMsgpack},
+    loader::{commit_before_idxes, load_model_msgpack_mode, LoaderMode, ModelMsgpack},
   },
 };
 
 lazy_static! {
-  pub static ref GADGET_CONFIG: Mutex<GadgetConfig> = Mutex::new(GadgetConfig::default());
   pub static ref PUBLIC_VALS: Mutex<Vec<BigUint>> = Mutex::new(vec![]);
 }
 
@@ -82,15 +148,24 @@ lazy_static! {
 pub struct ModelCircuit<F: PrimeField> {
   pub used_gadgets: Arc<BTreeSet<GadgetType>>,
   pub dag_config: DAGLayerConfig,
-  pub tensors: BTreeMap<i64, Array<F, IxDyn>>,
+  // Unknown in the circuit without witnesses
+  pub tensors: BTreeMap<i64, Array<Value<F>, IxDyn>>,
   pub commit_before: Vec<Vec<i64>>,
   pub commit_after: Vec<Vec<i64>>,
   pub k: usize,
   pub bits_per_elem: usize,
   pub inp_idxes: Vec<i64>,
   pub num_random: i64,
+  pub metadata: Vec<F>,
+  pub reveal_examples: Vec<i64>,
+  // Passed to configure as the circuit params
+  pub gadget_config: GadgetConfig,
 }
 
+// Concrete circuits for the curves used by the KZG (BN256) and IPA (Vesta) backends
+pub type ModelCircuitBn256 = ModelCircuit<bn256::Fr>;
+pub type ModelCircuitVesta = ModelCircuit<pasta::Fp>;
+
 #[derive(Clone, Debug)]
 pub struct ModelConfig<F: PrimeField + Ord + FromUniformBytes<64>> {
   pub gadget_config: Rc<GadgetConfig>,
@@ -99,12 +174,103 @@ pub struct ModelConfig<F: PrimeField + Ord + FromUniformBytes<64>> {
   pub _marker: PhantomData<F>,
 }
 
+pub fn match_layer(x: &str) -> Option<LayerType> {
+  let layer_type = match x {
+    "AveragePool2D" => LayerType::AvgPool2D,
+    "BatchToSpace" => LayerType::BatchToSpace,
+    "Add" => LayerType::Add,
+    "Abs" => LayerType::Abs,
+    "ArgMax" => LayerType::ArgMax,
+    "AssertClose" => LayerType::AssertClose,
+    "BatchMatMul" => LayerType::BatchMatMul,
+    "Broadcast" => LayerType::Broadcast,
+    "Concatenation" => LayerType::Concatenation,
+    "Clip" => LayerType::Clip,
+    "Conv1D" => LayerType::Conv1D,
+    "Conv2D" => LayerType::Conv2D,
+    "Conv3D" => LayerType::Conv3D,
+    "Cos" => LayerType::Cos,
+    "CumSum" => LayerType::CumSum,
+    "CustomNonlinear" => LayerType::CustomNonlinear,
+    "DepthToSpace" => LayerType::DepthToSpace,
+    "Div" => LayerType::DivFixed, // TODO: rename to DivFixed
+    "Einsum" => LayerType::Einsum,
+    "Equal" => LayerType::Equal,
+    "Exp" => LayerType::Exp,
+    "Flatten" => LayerType::Flatten,
+    "DivElementwise" => LayerType::Div,
+    "DivVar" => LayerType::DivVar,
+    "FullyConnected" => LayerType::FullyConnected,
+    "Gelu" => LayerType::Gelu,
+    "Greater" => LayerType::Greater,
+    "GRU" => LayerType::GRU,
+    "Hardmax" => LayerType::Hardmax,
+    "L2Norm" => LayerType::L2Norm,
+    "Less" => LayerType::Less,
+    "Log" => LayerType::Log,
+    "LogSoftmax" => LayerType::LogSoftmax,
+    "LogicalAnd" => LayerType::LogicalAnd,
+    "LogicalNot" => LayerType::LogicalNot,
+    "LogicalOr" => LayerType::LogicalOr,
+    "Logistic" => LayerType::Logistic,
+    "LRN" => LayerType::LRN,
+    "MaskNegInf" => LayerType::MaskNegInf,
+    "Maximum" => LayerType::Max,
+    "MaxPool2D" => LayerType::MaxPool2D,
+    "Mean" => LayerType::Mean,
+    "Minimum" => LayerType::Min,
+    "Mish" => LayerType::Mish,
+    "Mul" => LayerType::Mul,
+    "MultiHeadAttention" => LayerType::MultiHeadAttention,
+    "NonMaxSuppression" => LayerType::NonMaxSuppression,
+    "Noop" => LayerType::Noop,
+    "Pack" => LayerType::Pack,
+    "Pad" => LayerType::Pad,
+    "PiecewiseLinear" => LayerType::PiecewiseLinear,
+    "Pow" => LayerType::Pow,
+    "Permute" => LayerType::Permute,
+    "Reciprocal" => LayerType::Reciprocal,
+    "ReduceMax" => LayerType::ReduceMax,
+    "ReduceSum" => LayerType::ReduceSum,
+    "Reshape" => LayerType::Reshape,
+    "Requantize" => LayerType::Requantize,
+    "ResizeNearestNeighbor" => LayerType::ResizeNN,
+    "ROIAlign" => LayerType::ROIAlign,
+    "ResizeBilinear" => LayerType::ResizeBilinear,
+    "Reverse" => LayerType::Rotate,
+    "Rotate" => LayerType::Rotate,
+    "Rsqrt" => LayerType::Rsqrt,
+    "ScaledDotProductAttention" => LayerType::ScaledDotProductAttention,
+    "Select" => LayerType::Select,
+    "Sin" => LayerType::Sin,
+    "Slice" => LayerType::Slice,
+    "Softmax" => LayerType::Softmax,
+    "Softplus" => LayerType::Softplus,
+    "SpaceToBatch" => LayerType::SpaceToBatch,
+    "SpaceToDepth" => LayerType::SpaceToDepth,
+    "Split" => LayerType::Split,
+    "Sqrt" => LayerType::Sqrt,
+    "Square" => LayerType::Square,
+    "SquaredDifference" => LayerType::SquaredDifference,
+    "Sub" => LayerType::Sub,
+    "Tanh" => LayerType::Tanh,
+    "Tile" => LayerType::Tile,
+    "TopK" => LayerType::TopK,
+    "Transpose" => LayerType::Transpose,
+    "Unpack" => LayerType::Unpack,
+    "Update" => LayerType::Update,
+    "Upsample" => LayerType::Upsample,
+    _ => return None,
+  };
+  Some(layer_type)
+}
+
 impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
   pub fn assign_tensors_map(
     &self,
     mut layouter: impl Layouter<F>,
     columns: &Vec<Column<Advice>>,
-    tensors: &BTreeMap<i64, Array<F, IxDyn>>,
+    tensors: &BTreeMap<i64, Array<Value<F>, IxDyn>>,
   ) -> Result<BTreeMap<i64, AssignedTensor<F>>, Error> {
     let tensors = layouter.assign_region(
       || "asssignment",
@@ -122,7 +288,7 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
                 || "assignment",
                 columns[col_idx],
                 row_idx,
-                || Value::known(*val),
+                || *val,
               )
               .unwrap();
             flat.push(Rc::new(cell));
@@ -167,7 +333,7 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
     &self,
     mut layouter: impl Layouter<F>,
     columns: &Vec<Column<Advice>>,
-    tensors: &BTreeMap<i64, Array<F, IxDyn>>,
+    tensors: &BTreeMap<i64, Array<Value<F>, IxDyn>>,
   ) -> Result<Vec<AssignedTensor<F>>, Error> {
     let tensor_map = self
       .assign_tensors_map(
@@ -290,57 +456,52 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
   }
 
   pub fn generate_from_file(config_file: &str, inp_file: &str) -> ModelCircuit<F> {
-    let config = load_model_msgpack(config_file, inp_file);
+    Self::generate_from_file_mode(config_file, inp_file, LoaderMode::Permissive)
+  }
+
+  // In strict mode, unknown ops (including training-only ops like Dropout) are rejected instead of
+  // being mapped to Noop
+  pub fn generate_from_file_mode(
+    config_file: &str,
+    inp_file: &str,
+    mode: LoaderMode,
+  ) -> ModelCircuit<F> {
+    let config = load_model_msgpack_mode(config_file, inp_file, mode);
     Self::generate_from_msgpack(config, true)
   }
 
   pub fn generate_from_msgpack(config: ModelMsgpack, panic_empty_tensor: bool) -> ModelCircuit<F> {
+    Self::generate_from_msgpack_with(config, panic_empty_tensor, CustomFunctions::default())
+  }
+
+  // For models that use registered functions by name, i.e., the CustomNonlinear and
+  // PiecewiseLinear layers
+  pub fn generate_from_msgpack_with(
+    config: ModelMsgpack,
+    panic_empty_tensor: bool,
+    custom_functions: CustomFunctions,
+  ) -> ModelCircuit<F> {
+    let errors = check_model(&config);
+    if errors.len() > 0 {
+      for error in errors.iter() {
+        println!("{}", error);
+      }
+      panic!("model check failed with {} errors", errors.len());
+    }
+
     let to_field = |x: i64| {
       let bias = 1 << 31;
       let x_pos = x + bias;
       F::from(x_pos as u64) - F::from(bias as u64)
     };
 
-    let match_layer = |x: &str| match x {
-      "AveragePool2D" => LayerType::AvgPool2D,
-      "Add" => LayerType::Add,
-      "BatchMatMul" => LayerType::BatchMatMul,
-      "Broadcast" => LayerType::Broadcast,
-      "Concatenation" => LayerType::Concatenation,
-      "Conv2D" => LayerType::Conv2D,
-      "Div" => LayerType::DivFixed, // TODO: rename to DivFixed
-      "DivVar" => LayerType::DivVar,
-      "FullyConnected" => LayerType::FullyConnected,
-      "Logistic" => LayerType::Logistic,
-      "MaskNegInf" => LayerType::MaskNegInf,
-      "MaxPool2D" => LayerType::MaxPool2D,
-      "Mean" => LayerType::Mean,
-      "Mul" => LayerType::Mul,
-      "Noop" => LayerType::Noop,
-      "Pack" => LayerType::Pack,
-      "Pad" => LayerType::Pad,
-      "Pow" => LayerType::Pow,
-      "Permute" => LayerType::Permute,
-      "Reshape" => LayerType::Reshape,
-      "ResizeNearestNeighbor" => LayerType::ResizeNN,
-      "Rotate" => LayerType::Rotate,
-      "Rsqrt" => LayerType::Rsqrt,
-      "Slice" => LayerType::Slice,
-      "Softmax" => LayerType::Softmax,
-      "Split" => LayerType::Split,
-      "Sqrt" => LayerType::Sqrt,
-      "Square" => LayerType::Square,
-      "SquaredDifference" => LayerType::SquaredDifference,
-      "Sub" => LayerType::Sub,
-      "Tanh" => LayerType::Tanh,
-      "Transpose" => LayerType::Transpose,
-      "Update" => LayerType::Update,
-      _ => panic!("unknown op: {}", x),
-    };
-
     let mut tensors = BTreeMap::new();
     for flat in config.tensors {
-      let value_flat = flat.data.iter().map(|x| to_field(*x)).collect::<Vec<_>>();
+      let value_flat = flat
+        .data
+        .iter()
+        .map(|x| Value::known(to_field(*x)))
+        .collect::<Vec<_>>();
       let shape = flat.shape.iter().map(|x| *x as usize).collect::<Vec<_>>();
       let num_el: usize = shape.iter().product();
       if panic_empty_tensor && num_el != value_flat.len() {
@@ -363,49 +524,116 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
         .layers
         .iter()
         .map(|layer| {
-          let layer_type = match_layer(&layer.layer_type);
+          let layer_type = match_layer(&layer.layer_type)
+            .unwrap_or_else(|| panic!("unknown op: {}", layer.layer_type));
           let layer_gadgets = match layer_type {
             LayerType::Add => Box::new(AddChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Abs => Box::new(AbsChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::ArgMax => Box::new(ArgMaxChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::AssertClose => Box::new(AssertCloseChip {}) as Box<dyn GadgetConsumer>,
             LayerType::AvgPool2D => Box::new(AvgPool2DChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::BatchToSpace => Box::new(BatchToSpaceChip {}) as Box<dyn GadgetConsumer>,
             LayerType::BatchMatMul => Box::new(BatchMatMulChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Broadcast => Box::new(BroadcastChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Concatenation => Box::new(ConcatenationChip {}) as Box<dyn GadgetConsumer>,
-            LayerType::DivFixed => Box::new(ConcatenationChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Clip => Box::new(ClipChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::DivFixed => Box::new(DivFixedChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Einsum => Box::new(EinsumChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Equal => Box::new(EqualChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Exp => Box::new(ExpChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Flatten => Box::new(FlattenChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Div => Box::new(DivChip {}) as Box<dyn GadgetConsumer>,
             LayerType::DivVar => Box::new(DivVarChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Conv1D => Box::new(Conv1DChip {
+              config: LayerConfig::default(),
+              _marker: PhantomData::<F>,
+            }) as Box<dyn GadgetConsumer>,
             LayerType::Conv2D => Box::new(Conv2DChip {
               config: LayerConfig::default(),
               _marker: PhantomData::<F>,
             }) as Box<dyn GadgetConsumer>,
+            LayerType::Conv3D => Box::new(Conv3DChip {
+              config: LayerConfig::default(),
+              _marker: PhantomData::<F>,
+            }) as Box<dyn GadgetConsumer>,
+            LayerType::Cos => Box::new(CosChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::CumSum => Box::new(CumSumChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::CustomNonlinear => Box::new(CustomNonlinearChip {
+              custom_functions: custom_functions.clone(),
+            }) as Box<dyn GadgetConsumer>,
+            LayerType::DepthToSpace => Box::new(DepthToSpaceChip {}) as Box<dyn GadgetConsumer>,
             LayerType::FullyConnected => Box::new(FullyConnectedChip {
               config: FullyConnectedConfig { normalize: true },
               _marker: PhantomData::<F>,
             }) as Box<dyn GadgetConsumer>,
+            LayerType::Gelu => Box::new(GeluChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Greater => Box::new(GreaterChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::GRU => Box::new(GRUChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Hardmax => Box::new(HardmaxChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::L2Norm => Box::new(L2NormChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Less => Box::new(LessChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Log => Box::new(LogChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::LogSoftmax => Box::new(LogSoftmaxChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::LogicalAnd => Box::new(LogicalAndChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::LogicalNot => Box::new(LogicalNotChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::LogicalOr => Box::new(LogicalOrChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Logistic => Box::new(LogisticChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::LRN => Box::new(LRNChip {}) as Box<dyn GadgetConsumer>,
             LayerType::MaskNegInf => Box::new(MaskNegInfChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Max => Box::new(MaximumChip {}) as Box<dyn GadgetConsumer>,
             LayerType::MaxPool2D => Box::new(MaxPool2DChip {
               marker: PhantomData::<F>,
             }) as Box<dyn GadgetConsumer>,
             LayerType::Mean => Box::new(MeanChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Min => Box::new(MinChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Mish => Box::new(MishChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Mul => Box::new(MulChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::MultiHeadAttention => {
+              Box::new(MultiHeadAttentionChip {}) as Box<dyn GadgetConsumer>
+            }
+            LayerType::NonMaxSuppression => {
+              Box::new(NonMaxSuppressionChip {}) as Box<dyn GadgetConsumer>
+            }
             LayerType::Noop => Box::new(NoopChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Pack => Box::new(PackChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Pad => Box::new(PadChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::PiecewiseLinear => Box::new(PiecewiseLinearChip {
+              custom_functions: custom_functions.clone(),
+            }) as Box<dyn GadgetConsumer>,
             LayerType::Pow => Box::new(PowChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Permute => Box::new(PermuteChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Reciprocal => Box::new(ReciprocalChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::ReduceMax => Box::new(ReduceMaxChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::ReduceSum => Box::new(ReduceSumChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Reshape => Box::new(ReshapeChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Requantize => Box::new(RequantizeChip {}) as Box<dyn GadgetConsumer>,
             LayerType::ResizeNN => Box::new(ResizeNNChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::ROIAlign => Box::new(ROIAlignChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::ResizeBilinear => Box::new(ResizeBilinearChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Rotate => Box::new(RotateChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Rsqrt => Box::new(RsqrtChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::ScaledDotProductAttention => {
+              Box::new(ScaledDotProductAttentionChip {}) as Box<dyn GadgetConsumer>
+            }
+            LayerType::Select => Box::new(SelectChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Sin => Box::new(SinChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Slice => Box::new(SliceChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Softmax => Box::new(SoftmaxChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Softplus => Box::new(SoftplusChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::SpaceToBatch => Box::new(SpaceToBatchChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::SpaceToDepth => Box::new(SpaceToDepthChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Split => Box::new(SplitChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Sqrt => Box::new(SqrtChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Square => Box::new(SquareChip {}) as Box<dyn GadgetConsumer>,
             LayerType::SquaredDifference => Box::new(SquaredDiffChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Sub => Box::new(SubChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Tanh => Box::new(TanhChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Tile => Box::new(TileChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::TopK => Box::new(TopKChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Transpose => Box::new(TransposeChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Unpack => Box::new(UnpackChip {}) as Box<dyn GadgetConsumer>,
             LayerType::Update => Box::new(UpdateChip {}) as Box<dyn GadgetConsumer>,
+            LayerType::Upsample => Box::new(UpsampleChip {}) as Box<dyn GadgetConsumer>,
           }
           .used_gadgets(layer.params.clone());
           for gadget in layer_gadgets {
@@ -446,26 +674,75 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
 
     // The input lookup is always used
     used_gadgets.insert(GadgetType::InputLookup);
+    let reserved_rows = config.reserved_rows.unwrap_or(DEFAULT_RESERVED_ROWS);
+    // The tables cover [min_val, max_val] and must fit in the rows that are not reserved
+    let num_rows = (1 << config.k) - reserved_rows;
+    // Rescaling falls back to VarDivRound if the shift gadget can't handle the scale factor
+    if !ShiftChip::<F>::supports(config.global_sf as u64, num_rows as usize) {
+      used_gadgets.remove(&GadgetType::Shift);
+    }
+    // Dot products accumulate in place instead of going through the adder
+    if config.use_mac.unwrap_or(false) && used_gadgets.contains(&GadgetType::DotProduct) {
+      used_gadgets.insert(GadgetType::Mac);
+    }
     let used_gadgets = Arc::new(used_gadgets);
-    let gadget = &GADGET_CONFIG;
-    let cloned_gadget = gadget.lock().unwrap().clone();
-    *gadget.lock().unwrap() = GadgetConfig {
+    // The Pow lookup table is built for a single fractional exponent
+    let pow_exponents = config
+      .layers
+      .iter()
+      .filter_map(|layer| match layer.layer_type.as_str() {
+        "Pow" if PowChip::integer_exponent(&layer.params).is_none() => {
+          Some(layer.params[0] as f64 / layer.params[1] as f64)
+        }
+        "LRN" => Some(LRNChip::pow_exponent(&layer.params)),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    assert!(
+      pow_exponents.windows(2).all(|x| x[0] == x[1]),
+      "only one fractional Pow exponent is supported per model: {:?}",
+      pow_exponents
+    );
+    let commit_before = commit_before_idxes(&config);
+    let gadget_config = GadgetConfig {
       scale_factor: config.global_sf as u64,
       shift_min_val: -(config.global_sf * config.global_sf * (1 << 17)),
       div_outp_min_val: -(1 << (config.k - 1)),
       min_val: -(1 << (config.k - 1)),
-      max_val: (1 << (config.k - 1)) - 10,
+      max_val: -(1 << (config.k - 1)) + num_rows - 1,
       k: config.k as usize,
-      num_rows: (1 << config.k) - 10 + 1,
+      num_rows: num_rows as usize,
+      reserved_rows: reserved_rows as usize,
       num_cols: config.num_cols as usize,
       used_gadgets: used_gadgets.clone(),
-      commit_before: config.commit_before.clone().unwrap_or(vec![]),
+      commit_before: commit_before.clone(),
       commit_after: config.commit_after.clone().unwrap_or(vec![]),
+      reveal_examples: config.reveal_examples.clone().unwrap_or(vec![]),
       use_selectors: config.use_selectors.unwrap_or(true),
       num_bits_per_elem: config.bits_per_elem.unwrap_or(config.k),
-      ..cloned_gadget
+      pow_exponent: pow_exponents.first().cloned().unwrap_or(3.),
+      nonlinear_input_bits: config.nonlinear_input_bits.unwrap_or(0),
+      custom_functions,
+      ..GadgetConfig::default()
     };
 
+    // Split each digest into 128-bit limbs so they fit in the field
+    let metadata = config
+      .metadata_digests
+      .unwrap_or(vec![])
+      .iter()
+      .flat_map(|digest| {
+        let digest = digest.trim_start_matches("0x").as_bytes().to_vec();
+        digest
+          .chunks(32)
+          .map(|chunk| {
+            let chunk = std::str::from_utf8(chunk).unwrap();
+            F::from_u128(u128::from_str_radix(chunk, 16).expect("invalid metadata digest"))
+          })
+          .collect::<Vec<_>>()
+      })
+      .collect::<Vec<_>>();
+
     ModelCircuit {
       tensors,
       dag_config,
@@ -474,8 +751,11 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
       bits_per_elem: config.bits_per_elem.unwrap_or(config.k) as usize,
       inp_idxes: config.inp_idxes.clone(),
       commit_after: config.commit_after.unwrap_or(vec![]),
-      commit_before: config.commit_before.unwrap_or(vec![]),
+      commit_before,
       num_random: config.num_random.unwrap_or(0),
+      metadata,
+      reveal_examples: config.reveal_examples.unwrap_or(vec![]),
+      gadget_config,
     }
   }
 
@@ -484,7 +764,7 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
     mut layouter: impl Layouter<F>,
     constants: &HashMap<i64, CellRc<F>>,
     config: &ModelConfig<F>,
-    tensors: &BTreeMap<i64, Array<F, IxDyn>>,
+    tensors: &BTreeMap<i64, Array<Value<F>, IxDyn>>,
   ) -> (BTreeMap<i64, AssignedTensor<F>>, CellRc<F>) {
     let num_bits = self.bits_per_elem;
     let packer_config = PackerChip::<F>::construct(num_bits, config.gadget_config.as_ref());
@@ -554,19 +834,172 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelCircuit<F> {
 
     commitments[0].clone()
   }
+
+  // Assign tables
+  pub fn load_lookups(
+    &self,
+    config: &ModelConfig<F>,
+    mut layouter: impl Layouter<F>,
+  ) -> Result<(), Error> {
+    let gadget_rc: Rc<GadgetConfig> = config.gadget_config.clone().into();
+    for gadget in self.used_gadgets.iter() {
+      match gadget {
+        GadgetType::AddPairs => {
+          let chip = AddPairsChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "add pairs lookup"))?;
+        }
+        GadgetType::Adder => {
+          let chip = AdderChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "adder lookup"))?;
+        }
+        GadgetType::BiasDivRoundRelu6 => {
+          let chip = BiasDivRoundRelu6Chip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "bias div round relu6 lookup"))?;
+        }
+        GadgetType::BiasDivRoundRelu => {
+          let chip = BiasDivRoundRelu6Chip::<F>::construct_relu(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "bias div round relu lookup"))?;
+        }
+        GadgetType::BiasDivFloorRelu6 => {
+          let chip = BiasDivFloorRelu6Chip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "bias div floor relu6 lookup"))?;
+        }
+        GadgetType::BiasDivFloorRelu => {
+          let chip = BiasDivFloorRelu6Chip::<F>::construct_relu(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "bias div floor relu lookup"))?;
+        }
+        GadgetType::DotProduct => {
+          let chip = DotProductChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "dot product lookup"))?;
+        }
+        GadgetType::VarDivRound => {
+          let chip = VarDivRoundChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "var div lookup"))?;
+        }
+        GadgetType::PiecewiseLinear(name) => {
+          let chip = PiecewiseLinearGadgetChip::<F>::construct(gadget_rc.clone(), *name);
+          chip.load_lookups(layouter.namespace(|| format!("piecewise linear {} lookup", name)))?;
+        }
+        GadgetType::Pow => {
+          let chip = PowGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "pow lookup"))?;
+        }
+        GadgetType::Relu => {
+          let chip = ReluChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "relu lookup"))?;
+        }
+        GadgetType::Rsqrt => {
+          let chip = RsqrtGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "rsqrt lookup"))?;
+        }
+        GadgetType::Sin => {
+          let chip = SinGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "sin lookup"))?;
+        }
+        GadgetType::Cos => {
+          let chip = CosGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "cos lookup"))?;
+        }
+        GadgetType::Custom(name) => {
+          let chip = CustomNonlinearGadgetChip::<F>::construct(gadget_rc.clone(), *name);
+          chip.load_lookups(layouter.namespace(|| format!("custom {} lookup", name)))?;
+        }
+        GadgetType::Sign => {
+          let chip = SignChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "sign lookup"))?;
+        }
+        GadgetType::Sqrt => {
+          let chip = SqrtGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "sqrt lookup"))?;
+        }
+        GadgetType::Tanh => {
+          let chip = TanhGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "tanh lookup"))?;
+        }
+        GadgetType::Exp => {
+          let chip = ExpGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "exp lookup"))?;
+        }
+        GadgetType::Ln => {
+          let chip = LnGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "ln lookup"))?;
+        }
+        GadgetType::Logistic => {
+          let chip = LogisticGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "logistic lookup"))?;
+        }
+        GadgetType::Gelu => {
+          let chip = GeluGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "gelu lookup"))?;
+        }
+        GadgetType::Erf => {
+          let chip = ErfGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "erf lookup"))?;
+        }
+        GadgetType::Softplus => {
+          let chip = SoftplusGadgetChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "softplus lookup"))?;
+        }
+        GadgetType::InputLookup => {
+          let chip = InputLookupChip::<F>::construct(gadget_rc.clone());
+          chip.load_lookups(layouter.namespace(|| "input lookup"))?;
+        }
+        GadgetType::BitDecomposition => {}
+        GadgetType::VarDivRoundBig => {}
+        GadgetType::VarDivRoundBig3 => {}
+        GadgetType::VarMod => {}
+        GadgetType::GreaterThan => {}
+        GadgetType::Mac => {}
+        GadgetType::Max => {}
+        GadgetType::MulPairs => {}
+        GadgetType::Mux => {}
+        GadgetType::Packer => {} // Only uses the input lookup
+        GadgetType::RangeCheck => {}
+        GadgetType::Shift => {}
+        GadgetType::SignBit => {}
+        GadgetType::SqrtBig => {}
+        GadgetType::Square => {}
+        GadgetType::SquaredDiff => {}
+        GadgetType::SubPairs => {}
+        GadgetType::Update => {}
+        _ => panic!("unsupported gadget {:?}", gadget),
+      }
+    }
+
+    Ok(())
+  }
 }
 
 impl<F: PrimeField + Ord + FromUniformBytes<64>> Circuit<F> for ModelCircuit<F> {
   type Config = ModelConfig<F>;
   type FloorPlanner = SimpleFloorPlanner;
-  type Params = ();
+  type Params = GadgetConfig;
 
+  // Keygen only needs the shapes, so the tensors are unknown
   fn without_witnesses(&self) -> Self {
-    todo!()
+    let tensors = self
+      .tensors
+      .iter()
+      .map(|(idx, tensor)| (*idx, Array::from_elem(tensor.raw_dim(), Value::unknown())))
+      .collect();
+    Self {
+      tensors,
+      ..self.clone()
+    }
+  }
+
+  fn params(&self) -> Self::Params {
+    self.gadget_config.clone()
+  }
+
+  fn configure(_meta: &mut ConstraintSystem<F>) -> Self::Config {
+    panic!("the model circuit is configured with its gadget config, see configure_with_params")
   }
 
-  fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-    let mut gadget_config = crate::model::GADGET_CONFIG.lock().unwrap().clone();
+  fn configure_with_params(
+    meta: &mut ConstraintSystem<F>,
+    mut gadget_config: Self::Params,
+  ) -> Self::Config {
     let columns = (0..gadget_config.num_cols)
       .map(|_| meta.advice_column())
       .collect::<Vec<_>>();
@@ -590,15 +1023,42 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> Circuit<F> for ModelCircuit<F>
         GadgetType::AddPairs => AddPairsChip::<F>::configure(meta, gadget_config),
         GadgetType::Adder => AdderChip::<F>::configure(meta, gadget_config),
         GadgetType::BiasDivRoundRelu6 => BiasDivRoundRelu6Chip::<F>::configure(meta, gadget_config),
-        GadgetType::BiasDivFloorRelu6 => panic!(),
+        GadgetType::BiasDivRoundRelu => {
+          BiasDivRoundRelu6Chip::<F>::configure_relu(meta, gadget_config)
+        }
+        GadgetType::BiasDivFloorRelu6 => BiasDivFloorRelu6Chip::<F>::configure(meta, gadget_config),
+        GadgetType::BiasDivFloorRelu => {
+          BiasDivFloorRelu6Chip::<F>::configure_relu(meta, gadget_config)
+        }
+        GadgetType::BitDecomposition => BitDecompositionChip::<F>::configure(meta, gadget_config),
+        GadgetType::Cos => CosGadgetChip::<F>::configure(meta, gadget_config),
+        GadgetType::Custom(name) => {
+          CustomNonlinearGadgetChip::<F>::configure(meta, gadget_config, name)
+        }
         GadgetType::DotProduct => DotProductChip::<F>::configure(meta, gadget_config),
+        GadgetType::Erf => ErfGadgetChip::<F>::configure(meta, gadget_config),
         GadgetType::Exp => ExpGadgetChip::<F>::configure(meta, gadget_config),
+        GadgetType::Gelu => GeluGadgetChip::<F>::configure(meta, gadget_config),
+        GadgetType::GreaterThan => GreaterThanChip::<F>::configure(meta, gadget_config),
+        GadgetType::Ln => LnGadgetChip::<F>::configure(meta, gadget_config),
         GadgetType::Logistic => LogisticGadgetChip::<F>::configure(meta, gadget_config),
+        GadgetType::Mac => MacChip::<F>::configure(meta, gadget_config),
         GadgetType::Max => MaxChip::<F>::configure(meta, gadget_config),
         GadgetType::MulPairs => MulPairsChip::<F>::configure(meta, gadget_config),
+        GadgetType::Mux => MuxChip::<F>::configure(meta, gadget_config),
+        GadgetType::PiecewiseLinear(name) => {
+          PiecewiseLinearGadgetChip::<F>::configure(meta, gadget_config, name)
+        }
         GadgetType::Pow => PowGadgetChip::<F>::configure(meta, gadget_config),
+        GadgetType::RangeCheck => RangeCheckChip::<F>::configure(meta, gadget_config),
         GadgetType::Relu => ReluChip::<F>::configure(meta, gadget_config),
         GadgetType::Rsqrt => RsqrtGadgetChip::<F>::configure(meta, gadget_config),
+        GadgetType::Shift => ShiftChip::<F>::configure(meta, gadget_config)
+          .expect("the shift gadget is only used when it supports the scale factor"),
+        GadgetType::Sign => SignChip::<F>::configure(meta, gadget_config),
+        GadgetType::SignBit => SignBitChip::<F>::configure(meta, gadget_config),
+        GadgetType::Sin => SinGadgetChip::<F>::configure(meta, gadget_config),
+        GadgetType::Softplus => SoftplusGadgetChip::<F>::configure(meta, gadget_config),
         GadgetType::Sqrt => SqrtGadgetChip::<F>::configure(meta, gadget_config),
         GadgetType::SqrtBig => SqrtBigChip::<F>::configure(meta, gadget_config),
         GadgetType::Square => SquareGadgetChip::<F>::configure(meta, gadget_config),
@@ -608,16 +1068,28 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> Circuit<F> for ModelCircuit<F>
         GadgetType::VarDivRound => VarDivRoundChip::<F>::configure(meta, gadget_config),
         GadgetType::VarDivRoundBig => VarDivRoundBigChip::<F>::configure(meta, gadget_config),
         GadgetType::VarDivRoundBig3 => VarDivRoundBig3Chip::<F>::configure(meta, gadget_config),
+        GadgetType::VarMod => VarModChip::<F>::configure(meta, gadget_config),
         GadgetType::InputLookup => gadget_config, // This is always loaded
         GadgetType::Update => UpdateGadgetChip::<F>::configure(meta, gadget_config),
-        GadgetType::Packer => panic!(),
+        GadgetType::Packer => {
+          let packer_config =
+            PackerChip::<F>::construct(gadget_config.num_bits_per_elem as usize, &gadget_config);
+          PackerChip::<F>::configure(meta, packer_config, gadget_config)
+        }
       };
     }
 
-    let hasher = if gadget_config.commit_before.len() + gadget_config.commit_after.len() > 0 {
-      let packer_config =
-        PackerChip::<F>::construct(gadget_config.num_bits_per_elem as usize, &gadget_config);
-      gadget_config = PackerChip::<F>::configure(meta, packer_config, gadget_config);
+    // Examples that are not revealed are committed to
+    let commit_examples = gadget_config.reveal_examples.iter().any(|x| *x == 0);
+    let hasher = if gadget_config.commit_before.len() + gadget_config.commit_after.len() > 0
+      || commit_examples
+    {
+      // The packer may already be configured as a used gadget
+      if !gadget_config.selectors.contains_key(&GadgetType::Packer) {
+        let packer_config =
+          PackerChip::<F>::construct(gadget_config.num_bits_per_elem as usize, &gadget_config);
+        gadget_config = PackerChip::<F>::configure(meta, packer_config, gadget_config);
+      }
 
       // TODO
       let input = gadget_config.columns[0..L].try_into().unwrap();
@@ -633,6 +1105,13 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> Circuit<F> for ModelCircuit<F>
       None
     };
 
+    // The reservation must cover the blinding rows of the final constraint system
+    assert!(
+      meta.blinding_factors() + 1 <= gadget_config.reserved_rows,
+      "reserved_rows must be at least {}",
+      meta.blinding_factors() + 1
+    );
+
     ModelConfig {
       gadget_config: gadget_config.into(),
       public_col,
@@ -642,74 +1121,7 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> Circuit<F> for ModelCircuit<F>
   }
 
   fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
-    // Assign tables
-    let gadget_rc: Rc<GadgetConfig> = config.gadget_config.clone().into();
-    for gadget in self.used_gadgets.iter() {
-      match gadget {
-        GadgetType::AddPairs => {
-          let chip = AddPairsChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "add pairs lookup"))?;
-        }
-        GadgetType::Adder => {
-          let chip = AdderChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "adder lookup"))?;
-        }
-        GadgetType::BiasDivRoundRelu6 => {
-          let chip = BiasDivRoundRelu6Chip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "bias div round relu6 lookup"))?;
-        }
-        GadgetType::DotProduct => {
-          let chip = DotProductChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "dot product lookup"))?;
-        }
-        GadgetType::VarDivRound => {
-          let chip = VarDivRoundChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "var div lookup"))?;
-        }
-        GadgetType::Pow => {
-          let chip = PowGadgetChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "pow lookup"))?;
-        }
-        GadgetType::Relu => {
-          let chip = ReluChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "relu lookup"))?;
-        }
-        GadgetType::Rsqrt => {
-          let chip = RsqrtGadgetChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "rsqrt lookup"))?;
-        }
-        GadgetType::Sqrt => {
-          let chip = SqrtGadgetChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "sqrt lookup"))?;
-        }
-        GadgetType::Tanh => {
-          let chip = TanhGadgetChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "tanh lookup"))?;
-        }
-        GadgetType::Exp => {
-          let chip = ExpGadgetChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "exp lookup"))?;
-        }
-        GadgetType::Logistic => {
-          let chip = LogisticGadgetChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "logistic lookup"))?;
-        }
-        GadgetType::InputLookup => {
-          let chip = InputLookupChip::<F>::construct(gadget_rc.clone());
-          chip.load_lookups(layouter.namespace(|| "input lookup"))?;
-        }
-        GadgetType::VarDivRoundBig => {}
-        GadgetType::VarDivRoundBig3 => {}
-        GadgetType::Max => {}
-        GadgetType::MulPairs => {}
-        GadgetType::SqrtBig => {}
-        GadgetType::Square => {}
-        GadgetType::SquaredDiff => {}
-        GadgetType::SubPairs => {}
-        GadgetType::Update => {}
-        _ => panic!("unsupported gadget {:?}", gadget),
-      }
-    }
+    self.load_lookups(&config, layouter.namespace(|| "lookups"))?;
 
     // Assign weights and constants
     let constants_base = self
@@ -819,18 +1231,161 @@ impl<F: PrimeField + Ord + FromUniformBytes<64>> Circuit<F> for ModelCircuit<F>
       new_public_vals.push(val);
       total_idx += 1;
     }
-    for tensor in result {
-      for cell in tensor.iter() {
+    // The metadata is only bound through the public instance, it is not used by the circuit
+    if self.metadata.len() > 0 {
+      let metadata = pub_layouter
+        .assign_region(
+          || "metadata",
+          |mut region| {
+            let mut cells = vec![];
+            for (i, val) in self.metadata.iter().enumerate() {
+              let row_idx = i / config.gadget_config.columns.len();
+              let col_idx = i % config.gadget_config.columns.len();
+              let cell = region.assign_advice(
+                || format!("metadata_{}", i),
+                config.gadget_config.columns[col_idx],
+                row_idx,
+                || Value::known(*val),
+              )?;
+              cells.push(cell);
+            }
+            Ok(cells)
+          },
+        )
+        .unwrap();
+      for cell in metadata.iter() {
         pub_layouter
-          .constrain_instance(cell.as_ref().cell(), config.public_col, total_idx)
+          .constrain_instance(cell.cell(), config.public_col, total_idx)
           .unwrap();
         let val = convert_to_bigint(cell.value().map(|x| x.to_owned()));
         new_public_vals.push(val);
         total_idx += 1;
       }
     }
+    for tensor in result {
+      if self.reveal_examples.len() == 0 {
+        for cell in tensor.iter() {
+          pub_layouter
+            .constrain_instance(cell.as_ref().cell(), config.public_col, total_idx)
+            .unwrap();
+          let val = convert_to_bigint(cell.value().map(|x| x.to_owned()));
+          new_public_vals.push(val);
+          total_idx += 1;
+        }
+        continue;
+      }
+
+      // Mixed visibility: each example is either revealed or replaced by its commitment
+      assert_eq!(tensor.shape()[0], self.reveal_examples.len());
+      for (batch_idx, reveal) in self.reveal_examples.iter().enumerate() {
+        let example = tensor.index_axis(Axis(0), batch_idx).to_owned();
+        let cells = if *reveal == 1 {
+          example.iter().cloned().collect::<Vec<_>>()
+        } else {
+          let to_commit = BTreeMap::from([(0, example)]);
+          let commitment = self.copy_and_commit(
+            pub_layouter.namespace(|| "commit example"),
+            &constants,
+            &config,
+            &to_commit,
+          );
+          vec![commitment]
+        };
+        for cell in cells.iter() {
+          pub_layouter
+            .constrain_instance(cell.as_ref().cell(), config.public_col, total_idx)
+            .unwrap();
+          let val = convert_to_bigint(cell.value().map(|x| x.to_owned()));
+          new_public_vals.push(val);
+          total_idx += 1;
+        }
+      }
+    }
     *PUBLIC_VALS.lock().unwrap() = new_public_vals;
 
     Ok(())
   }
 }
+
+// Embeds a model as a sub-circuit of another halo2 circuit. The host generates the ModelCircuit
+// first, passes it to configure from its own configure, and calls
+// synthesize_into with its own assigned inputs. The outputs are returned to the host instead of
+// being exposed, so the host decides what to constrain. Input cells must be in columns with
+// equality enabled. Commitments and metadata are only supported by the standalone circuit.
+pub struct ModelChip<F: PrimeField + Ord + FromUniformBytes<64>> {
+  pub circuit: ModelCircuit<F>,
+  pub config: ModelConfig<F>,
+}
+
+impl<F: PrimeField + Ord + FromUniformBytes<64>> ModelChip<F> {
+  pub fn construct(circuit: ModelCircuit<F>, config: ModelConfig<F>) -> Self {
+    Self { circuit, config }
+  }
+
+  pub fn configure(meta: &mut ConstraintSystem<F>, circuit: &ModelCircuit<F>) -> ModelConfig<F> {
+    assert!(
+      circuit.commit_before.len() + circuit.commit_after.len() == 0,
+      "commitments are not supported in an embedded model"
+    );
+    assert!(circuit.metadata.len() == 0 && circuit.reveal_examples.len() == 0);
+    <ModelCircuit<F> as Circuit<F>>::configure_with_params(meta, circuit.params())
+  }
+
+  // Inputs are in the order of inp_idxes. Returns the model outputs in the order of out_idxes
+  pub fn synthesize_into(
+    &self,
+    mut layouter: impl Layouter<F>,
+    inputs: &Vec<AssignedTensor<F>>,
+  ) -> Result<Vec<AssignedTensor<F>>, Error> {
+    let circuit = &self.circuit;
+    let config = &self.config;
+    assert_eq!(inputs.len(), circuit.inp_idxes.len());
+
+    circuit.load_lookups(config, layouter.namespace(|| "model lookups"))?;
+    let constants_base = circuit.assign_constants(
+      layouter.namespace(|| "model constants"),
+      config.gadget_config.clone(),
+    )?;
+    let constants = circuit.assign_constants2(
+      layouter.namespace(|| "model constants 2"),
+      config.gadget_config.clone(),
+      &constants_base,
+    )?;
+
+    // Assign the weights, then use the host's cells for the inputs
+    let weights = circuit
+      .tensors
+      .iter()
+      .filter(|(idx, _)| !circuit.inp_idxes.contains(*idx))
+      .map(|(idx, tensor)| (*idx, tensor.clone()))
+      .collect::<BTreeMap<_, _>>();
+    let mut tensor_map = circuit.assign_tensors_map(
+      layouter.namespace(|| "model weights"),
+      &config.gadget_config.columns,
+      &weights,
+    )?;
+    for (idx, input) in circuit.inp_idxes.iter().zip(inputs.iter()) {
+      if let Some(tensor) = circuit.tensors.get(idx) {
+        assert_eq!(
+          tensor.shape(),
+          input.shape(),
+          "input {} has the wrong shape",
+          idx
+        );
+      }
+      tensor_map.insert(*idx, input.clone());
+    }
+    let tensors = circuit.tensor_map_to_vec(&tensor_map)?;
+
+    let dag_chip = DAGLayerChip::<F>::construct(circuit.dag_config.clone());
+    let (_, result) = dag_chip.forward(
+      layouter.namespace(|| "model dag"),
+      &tensors,
+      &constants,
+      config.gadget_config.clone(),
+      &LayerConfig::default(),
+    )?;
+
+    Ok(result)
+  }
+}
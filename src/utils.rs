@@ -1,4 +1,15 @@
+pub mod audit;
+pub mod bundle;
+pub mod checks;
+pub mod explain;
 pub mod helpers;
+pub mod instance_layout;
+pub mod key_cache;
+pub mod json_io;
 pub mod loader;
+pub mod optimizer;
+pub mod proving;
 pub mod proving_ipa;
 pub mod proving_kzg;
+pub mod telemetry;
+pub mod verifier_vectors;
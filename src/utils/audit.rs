@@ -0,0 +1,262 @@
+use std::collections::{HashMap, HashSet};
+
+use halo2_proofs::{
+  circuit::Value,
+  halo2curves::ff::PrimeField,
+  plonk::{
+    Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error,
+    Expression, Fixed, FloorPlanner, Instance, Selector,
+  },
+};
+
+// (advice column index, absolute row)
+type Cell = (usize, usize);
+// Selectors and (advice column index, rotation) queried by an expression
+type Queries = (Vec<Selector>, Vec<(usize, i32)>);
+
+#[derive(Clone, Debug)]
+pub struct RegionAudit {
+  // Namespace path of the region, e.g. "dag/dag conv 2d/..."
+  pub namespace: String,
+  pub region: String,
+  pub num_assigned: usize,
+  pub unconstrained: Vec<Cell>,
+}
+
+struct AuditRegion {
+  namespace: String,
+  region: String,
+  cells: Vec<Cell>,
+}
+
+// Records the layout of a circuit without computing any witness values. Mirrors what keygen
+// does, so it is safe to run on any circuit keygen works on.
+#[derive(Default)]
+struct AuditAssignment {
+  namespaces: Vec<String>,
+  current_region: Option<AuditRegion>,
+  regions: Vec<AuditRegion>,
+  enabled_selectors: HashMap<Selector, Vec<usize>>,
+  copied: HashSet<Cell>,
+}
+
+impl<F: PrimeField> Assignment<F> for AuditAssignment {
+  fn enter_region<NR, N>(&mut self, name_fn: N)
+  where
+    NR: Into<String>,
+    N: FnOnce() -> NR,
+  {
+    self.current_region = Some(AuditRegion {
+      namespace: self.namespaces.join("/"),
+      region: name_fn().into(),
+      cells: vec![],
+    });
+  }
+
+  fn annotate_column<A, AR>(&mut self, _annotation: A, _column: Column<Any>)
+  where
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+  }
+
+  fn exit_region(&mut self) {
+    let region = self.current_region.take().unwrap();
+    self.regions.push(region);
+  }
+
+  fn enable_selector<A, AR>(&mut self, _: A, selector: &Selector, row: usize) -> Result<(), Error>
+  where
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    self
+      .enabled_selectors
+      .entry(*selector)
+      .or_insert(vec![])
+      .push(row);
+    Ok(())
+  }
+
+  fn query_instance(&self, _column: Column<Instance>, _row: usize) -> Result<Value<F>, Error> {
+    Ok(Value::unknown())
+  }
+
+  fn assign_advice<V, VR, A, AR>(
+    &mut self,
+    _: A,
+    column: Column<Advice>,
+    row: usize,
+    _to: V,
+  ) -> Result<(), Error>
+  where
+    V: FnOnce() -> Value<VR>,
+    VR: Into<Assigned<F>>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    if let Some(region) = self.current_region.as_mut() {
+      region.cells.push((column.index(), row));
+    }
+    Ok(())
+  }
+
+  fn assign_fixed<V, VR, A, AR>(
+    &mut self,
+    _: A,
+    _column: Column<Fixed>,
+    _row: usize,
+    _to: V,
+  ) -> Result<(), Error>
+  where
+    V: FnOnce() -> Value<VR>,
+    VR: Into<Assigned<F>>,
+    A: FnOnce() -> AR,
+    AR: Into<String>,
+  {
+    Ok(())
+  }
+
+  fn copy(
+    &mut self,
+    left_column: Column<Any>,
+    left_row: usize,
+    right_column: Column<Any>,
+    right_row: usize,
+  ) -> Result<(), Error> {
+    if let Any::Advice(_) = left_column.column_type() {
+      self.copied.insert((left_column.index(), left_row));
+    }
+    if let Any::Advice(_) = right_column.column_type() {
+      self.copied.insert((right_column.index(), right_row));
+    }
+    Ok(())
+  }
+
+  fn fill_from_row(
+    &mut self,
+    _column: Column<Fixed>,
+    _row: usize,
+    _to: Value<Assigned<F>>,
+  ) -> Result<(), Error> {
+    Ok(())
+  }
+
+  fn get_challenge(&self, _challenge: Challenge) -> Value<F> {
+    Value::unknown()
+  }
+
+  fn push_namespace<NR, N>(&mut self, name_fn: N)
+  where
+    NR: Into<String>,
+    N: FnOnce() -> NR,
+  {
+    self.namespaces.push(name_fn().into());
+  }
+
+  fn pop_namespace(&mut self, _gadget_name: Option<String>) {
+    self.namespaces.pop();
+  }
+}
+
+fn merge_queries(mut a: Queries, b: Queries) -> Queries {
+  a.0.extend(b.0);
+  a.1.extend(b.1);
+  a
+}
+
+fn expression_queries<F: PrimeField>(expr: &Expression<F>) -> Queries {
+  expr.evaluate(
+    &|_| (vec![], vec![]),
+    &|selector| (vec![selector], vec![]),
+    &|_| (vec![], vec![]),
+    &|query| (vec![], vec![(query.column_index(), query.rotation().0)]),
+    &|_| (vec![], vec![]),
+    &|_| (vec![], vec![]),
+    &|a| a,
+    &merge_queries,
+    &merge_queries,
+    &|a, _| a,
+  )
+}
+
+// Finds advice cells that are assigned in a region but are not queried by any enabled gate or
+// lookup, and are not part of any copy constraint. Such cells are free for a malicious prover to
+// choose, so every hit should be checked by hand.
+pub fn audit_circuit<F: PrimeField, C: Circuit<F>>(circuit: &C) -> Vec<RegionAudit> {
+  let mut cs = ConstraintSystem::<F>::default();
+  let config = C::configure_with_params(&mut cs, circuit.params());
+
+  let mut assignment = AuditAssignment::default();
+  C::FloorPlanner::synthesize(&mut assignment, circuit, config, cs.constants().clone()).unwrap();
+
+  let mut queries = cs
+    .gates()
+    .iter()
+    .map(|gate| {
+      gate
+        .polynomials()
+        .iter()
+        .map(|poly| expression_queries(poly))
+        .fold((vec![], vec![]), merge_queries)
+    })
+    .collect::<Vec<_>>();
+  for lookup in cs.lookups().iter() {
+    let lookup_queries = lookup
+      .input_expressions()
+      .iter()
+      .map(|expr| expression_queries(expr))
+      .fold((vec![], vec![]), merge_queries);
+    queries.push(lookup_queries);
+  }
+
+  // Gates without a selector apply to every row of the columns they query
+  let mut always_covered = HashSet::new();
+  let mut covered = HashSet::new();
+  for (selectors, advice_queries) in queries.iter() {
+    if selectors.is_empty() {
+      for (col, _) in advice_queries.iter() {
+        always_covered.insert(*col);
+      }
+      continue;
+    }
+    for selector in selectors.iter() {
+      let rows = match assignment.enabled_selectors.get(selector) {
+        Some(rows) => rows,
+        None => continue,
+      };
+      for row in rows.iter() {
+        for (col, rot) in advice_queries.iter() {
+          let row = *row as i64 + *rot as i64;
+          if row >= 0 {
+            covered.insert((*col, row as usize));
+          }
+        }
+      }
+    }
+  }
+
+  assignment
+    .regions
+    .into_iter()
+    .map(|region| {
+      let cells = region.cells.into_iter().collect::<HashSet<_>>();
+      let mut unconstrained = cells
+        .iter()
+        .filter(|cell| {
+          !always_covered.contains(&cell.0)
+            && !covered.contains(*cell)
+            && !assignment.copied.contains(*cell)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+      unconstrained.sort();
+      RegionAudit {
+        namespace: region.namespace,
+        region: region.region,
+        num_assigned: cells.len(),
+        unconstrained,
+      }
+    })
+    .collect()
+}
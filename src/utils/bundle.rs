@@ -0,0 +1,134 @@
+use std::{
+  fs::File,
+  io::Write,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde_derive::{Deserialize, Serialize};
+
+use super::telemetry::ProverTelemetry;
+
+// Signed statement by the prover about a proof. Relying parties that consume proofs off-chain can
+// check who produced a proof and that it is still fresh without any extra infrastructure.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BundleMetadata {
+  // Seconds since the unix epoch
+  pub created_at: u64,
+  pub expires_at: u64,
+  // Proving statistics, only present when the prover ran with --telemetry
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub telemetry: Option<ProverTelemetry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofBundle {
+  pub metadata: BundleMetadata,
+  // Hex encoded ed25519 public key and signature
+  pub public_key: String,
+  pub signature: String,
+}
+
+impl ProofBundle {
+  pub fn write(&self, path: &str) {
+    let mut file = File::create(path).unwrap();
+    file
+      .write_all(serde_json::to_string_pretty(self).unwrap().as_bytes())
+      .unwrap();
+  }
+
+  pub fn read(path: &str) -> Self {
+    let bundle = std::fs::read_to_string(path).unwrap();
+    serde_json::from_str(&bundle).unwrap()
+  }
+}
+
+fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap()
+    .as_secs()
+}
+
+// The signature covers the proof, the public values, and the metadata
+fn signed_message(proof: &[u8], public_vals: &[u8], metadata: &BundleMetadata) -> Vec<u8> {
+  let mut msg = vec![];
+  for part in [proof, public_vals] {
+    msg.extend((part.len() as u64).to_le_bytes());
+    msg.extend(part);
+  }
+  msg.extend(serde_json::to_vec(metadata).unwrap());
+  msg
+}
+
+// Loads the 32 byte ed25519 secret key at key_path, generating a new one if the file doesn't exist
+pub fn load_or_create_signing_key(key_path: &str) -> SigningKey {
+  match std::fs::read(key_path) {
+    Ok(bytes) => {
+      let bytes: [u8; 32] = bytes.try_into().expect("signing key must be 32 bytes");
+      SigningKey::from_bytes(&bytes)
+    }
+    Err(_) => {
+      let key = SigningKey::generate(&mut rand::rngs::OsRng);
+      let mut file = File::create(key_path).unwrap();
+      file.write_all(&key.to_bytes()).unwrap();
+      println!("Wrote new signing key to {}", key_path);
+      key
+    }
+  }
+}
+
+pub fn sign_bundle(
+  key: &SigningKey,
+  proof: &[u8],
+  public_vals: &[u8],
+  ttl: u64,
+  telemetry: Option<ProverTelemetry>,
+) -> ProofBundle {
+  let created_at = now();
+  let metadata = BundleMetadata {
+    created_at,
+    expires_at: created_at + ttl,
+    telemetry,
+  };
+  let signature = key.sign(&signed_message(proof, public_vals, &metadata));
+  ProofBundle {
+    metadata,
+    public_key: hex::encode(key.verifying_key().to_bytes()),
+    signature: hex::encode(signature.to_bytes()),
+  }
+}
+
+// Checks the signature and expiry. If trusted_key is given, the bundle must also be signed by it.
+pub fn verify_bundle(
+  bundle: &ProofBundle,
+  proof: &[u8],
+  public_vals: &[u8],
+  trusted_key: Option<&str>,
+) -> Result<(), String> {
+  if let Some(trusted_key) = trusted_key {
+    if !bundle.public_key.eq_ignore_ascii_case(trusted_key) {
+      return Err(format!("bundle is signed by untrusted key {}", bundle.public_key));
+    }
+  }
+
+  let public_key: [u8; 32] = hex::decode(&bundle.public_key)
+    .map_err(|e| e.to_string())?
+    .try_into()
+    .map_err(|_| "public key must be 32 bytes".to_string())?;
+  let public_key = VerifyingKey::from_bytes(&public_key).map_err(|e| e.to_string())?;
+  let signature: [u8; 64] = hex::decode(&bundle.signature)
+    .map_err(|e| e.to_string())?
+    .try_into()
+    .map_err(|_| "signature must be 64 bytes".to_string())?;
+  let signature = Signature::from_bytes(&signature);
+
+  public_key
+    .verify(&signed_message(proof, public_vals, &bundle.metadata), &signature)
+    .map_err(|_| "invalid bundle signature".to_string())?;
+
+  if now() >= bundle.metadata.expires_at {
+    return Err(format!("bundle expired at {}", bundle.metadata.expires_at));
+  }
+  Ok(())
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use super::loader::ModelMsgpack;
+
+// Checks every edge of the DAG before any circuit is built. Returns all of the problems found so
+// that they can be fixed at once, instead of failing on the first bad layer during synthesis.
+//
+// NOTE: all tensors share the global scale factor, so there is no per-edge scale to check.
+pub fn check_model(config: &ModelMsgpack) -> Vec<String> {
+  let mut errors = vec![];
+
+  // Shapes of the tensors that are known so far
+  let mut shapes: HashMap<i64, Vec<i64>> = HashMap::new();
+  for tensor in config.tensors.iter() {
+    shapes.insert(tensor.idx, tensor.shape.clone());
+  }
+
+  let mut producers: HashMap<i64, usize> = HashMap::new();
+  for (layer_idx, layer) in config.layers.iter().enumerate() {
+    for idx in layer.out_idxes.iter() {
+      if let Some(prev) = producers.insert(*idx, layer_idx) {
+        errors.push(format!(
+          "layer {} ({}): output {} was already produced by layer {}",
+          layer_idx, layer.layer_type, idx, prev
+        ));
+      }
+    }
+  }
+
+  for (layer_idx, layer) in config.layers.iter().enumerate() {
+    let name = format!("layer {} ({})", layer_idx, layer.layer_type);
+
+    if layer.inp_idxes.len() != layer.inp_shapes.len() {
+      errors.push(format!(
+        "{}: {} inputs but {} input shapes",
+        name,
+        layer.inp_idxes.len(),
+        layer.inp_shapes.len()
+      ));
+    }
+    if layer.out_idxes.len() != layer.out_shapes.len() {
+      errors.push(format!(
+        "{}: {} outputs but {} output shapes",
+        name,
+        layer.out_idxes.len(),
+        layer.out_shapes.len()
+      ));
+    }
+
+    for (inp_idx, inp_shape) in layer.inp_idxes.iter().zip(layer.inp_shapes.iter()) {
+      match producers.get(inp_idx) {
+        Some(producer) if *producer >= layer_idx => {
+          errors.push(format!(
+            "{}: input {} is produced by layer {}, which runs after it",
+            name, inp_idx, producer
+          ));
+          continue;
+        }
+        _ => {}
+      }
+
+      if let Some(shape) = shapes.get(inp_idx) {
+        let num_el = shape.iter().product::<i64>();
+        let inp_num_el = inp_shape.iter().product::<i64>();
+        // Scalars are stored as [1] but may be declared as []
+        if shape != inp_shape && !(num_el == 1 && inp_num_el == 1) {
+          errors.push(format!(
+            "{}: input {} has shape {:?} but the layer expects {:?}",
+            name, inp_idx, shape, inp_shape
+          ));
+        }
+      }
+    }
+
+    for (out_idx, out_shape) in layer.out_idxes.iter().zip(layer.out_shapes.iter()) {
+      shapes.insert(*out_idx, out_shape.clone());
+    }
+  }
+
+  for out_idx in config.out_idxes.iter() {
+    if !producers.contains_key(out_idx) && !shapes.contains_key(out_idx) {
+      errors.push(format!("model output {} is never produced", out_idx));
+    }
+  }
+
+  errors
+}
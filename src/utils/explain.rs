@@ -0,0 +1,93 @@
+use std::{fs::File, io::Write};
+
+use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+use serde_derive::Serialize;
+
+use crate::model::ModelCircuit;
+
+use super::{
+  helpers::get_public_values,
+  instance_layout::{decode_outputs, InstanceLayout},
+  json_io::JsonTensor,
+  loader::ModelMsgpack,
+};
+
+// Occlusion attribution: every group of `patch` consecutive input elements is replaced by the
+// baseline and the model is rerun. The score of an element is how much the chosen output dropped,
+// so positive scores mark elements that pushed the output up. The model is run by the circuit
+// itself, so the scores use exactly the fixed-point semantics of the proof.
+#[derive(Clone, Debug, Serialize)]
+pub struct Attribution {
+  // Index into the revealed outputs, flattened in instance order
+  pub output: usize,
+  pub output_value: f64,
+  pub patch: usize,
+  pub baseline: f64,
+  pub inputs: Vec<JsonTensor>,
+}
+
+impl Attribution {
+  pub fn write(&self, path: &str) {
+    let mut file = File::create(path).unwrap();
+    file
+      .write_all(serde_json::to_string_pretty(self).unwrap().as_bytes())
+      .unwrap();
+  }
+}
+
+// Synthesizes the witness for the model and returns the revealed outputs as real numbers
+pub fn run_model(config: &ModelMsgpack) -> Vec<f64> {
+  let layout = InstanceLayout::from_model(config);
+  let circuit = ModelCircuit::<Fr>::generate_from_msgpack(config.clone(), true);
+  MockProver::run(config.k as u32, &circuit, vec![vec![]]).unwrap();
+  decode_outputs(&layout, &get_public_values::<Fr>())
+    .into_iter()
+    .flat_map(|(_, outp)| outp.iter().cloned().collect::<Vec<_>>())
+    .collect()
+}
+
+pub fn occlusion(
+  config: &ModelMsgpack,
+  output: usize,
+  patch: usize,
+  baseline: f64,
+) -> Attribution {
+  assert!(patch > 0, "patch size must be positive");
+  let outputs = run_model(config);
+  assert!(output < outputs.len(), "the model only has {} outputs", outputs.len());
+  let output_value = outputs[output];
+  let baseline_scaled = (baseline * config.global_sf as f64).round() as i64;
+
+  let mut inputs = vec![];
+  for inp_idx in config.inp_idxes.iter() {
+    let tensor_pos = config.tensors.iter().position(|x| x.idx == *inp_idx).unwrap();
+    let tensor = &config.tensors[tensor_pos];
+    let mut scores = vec![0.; tensor.data.len()];
+
+    for start in (0..tensor.data.len()).step_by(patch) {
+      let end = (start + patch).min(tensor.data.len());
+      let mut occluded = config.clone();
+      for val in occluded.tensors[tensor_pos].data[start..end].iter_mut() {
+        *val = baseline_scaled;
+      }
+      let score = output_value - run_model(&occluded)[output];
+      for s in scores[start..end].iter_mut() {
+        *s = score;
+      }
+    }
+
+    inputs.push(JsonTensor {
+      name: inp_idx.to_string(),
+      shape: tensor.shape.clone(),
+      data: scores,
+    });
+  }
+
+  Attribution {
+    output,
+    output_value,
+    patch,
+    baseline,
+    inputs,
+  }
+}
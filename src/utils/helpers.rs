@@ -63,37 +63,30 @@ pub fn get_public_values<F: PrimeField>() -> Vec<F> {
 }
 
 // Broadcast
-fn shape_dominates(s1: &[usize], s2: &[usize]) -> bool {
-  if s1.len() != s2.len() {
-    return false;
-  }
-
-  for (x1, x2) in s1.iter().zip(s2.iter()) {
-    if x1 < x2 {
-      return false;
-    }
-  }
-
-  true
-}
-
-// Precondition: s1.len() < s2.len()
-fn intermediate_shape(s1: &[usize], s2: &[usize]) -> Vec<usize> {
-  let mut res = vec![1; s2.len() - s1.len()];
-  for s in s1.iter() {
-    res.push(*s);
-  }
-  res
-}
-
-fn final_shape(s1: &[usize], s2: &[usize]) -> Vec<usize> {
-  let mut res = vec![];
-  for (x1, x2) in s1.iter().zip(s2.iter()) {
-    res.push(std::cmp::max(*x1, *x2));
-  }
-  res
+// The NumPy broadcast of two shapes: aligned from the last axis, each pair of sizes must be equal
+// or one of them 1
+pub fn broadcast_shape(s1: &[usize], s2: &[usize]) -> Vec<usize> {
+  let ndim = std::cmp::max(s1.len(), s2.len());
+  let pad = |s: &[usize]| {
+    let mut res = vec![1; ndim - s.len()];
+    res.extend(s.iter());
+    res
+  };
+  let (s1, s2) = (pad(s1), pad(s2));
+
+  s1.iter()
+    .zip(s2.iter())
+    .map(|(x1, x2)| {
+      if x1 != x2 && *x1 != 1 && *x2 != 1 {
+        panic!("Cannot broadcast {:?} and {:?}", s1, s2);
+      }
+      std::cmp::max(*x1, *x2)
+    })
+    .collect()
 }
 
+// Expanding an array only repeats its elements, so the cells of a broadcast tensor are copies of
+// the original ones
 pub fn broadcast<G: Clone>(
   x1: &Array<G, IxDyn>,
   x2: &Array<G, IxDyn>,
@@ -102,39 +95,36 @@ pub fn broadcast<G: Clone>(
     return (x1.clone(), x2.clone());
   }
 
-  if x1.ndim() == x2.ndim() {
-    let s1 = x1.shape();
-    let s2 = x2.shape();
-    if shape_dominates(s1, s2) {
-      return (x1.clone(), x2.broadcast(s1).unwrap().into_owned());
-    } else if shape_dominates(x2.shape(), x1.shape()) {
-      return (x1.broadcast(s2).unwrap().into_owned(), x2.clone());
-    }
-  }
-
-  let (tmp1, tmp2) = if x1.ndim() < x2.ndim() {
-    (x1, x2)
-  } else {
-    (x2, x1)
-  };
-
-  // tmp1.ndim() < tmp2.ndim()
-  let s1 = tmp1.shape();
-  let s2 = tmp2.shape();
-  let s = intermediate_shape(s1, s2);
-  let final_shape = final_shape(s2, s.as_slice());
-
-  let tmp1 = tmp1.broadcast(s.clone()).unwrap().into_owned();
-  let tmp1 = tmp1.broadcast(final_shape.as_slice()).unwrap().into_owned();
-  let tmp2 = tmp2.broadcast(final_shape.as_slice()).unwrap().into_owned();
-  // println!("x1: {:?} x2: {:?}", x1.shape(), x2.shape());
-  // println!("s1: {:?} s2: {:?} s: {:?}", s1, s2, s);
-  // println!("tmp1 shape: {:?}", tmp1.shape());
-  // println!("tmp2 shape: {:?}", tmp2.shape());
+  let shape = broadcast_shape(x1.shape(), x2.shape());
+  (
+    x1.broadcast(shape.as_slice()).unwrap().into_owned(),
+    x2.broadcast(shape.as_slice()).unwrap().into_owned(),
+  )
+}
 
-  if x1.ndim() < x2.ndim() {
-    return (tmp1, tmp2);
+// Math
+// The error function, from the Chebyshev fit of erfc in Numerical Recipes (fractional error below
+// 1.2e-7), since f64 has no erf
+pub fn erf(x: f64) -> f64 {
+  let z = x.abs();
+  let t = 1. / (1. + 0.5 * z);
+  let coeffs = [
+    -1.26551223,
+    1.00002368,
+    0.37409196,
+    0.09678418,
+    -0.18628806,
+    0.27886807,
+    -1.13520398,
+    1.48851587,
+    -0.82215223,
+    0.17087277,
+  ];
+  let poly = coeffs.iter().rev().fold(0., |acc, c| c + t * acc);
+  let erfc = t * (-z * z + poly).exp();
+  if x >= 0. {
+    1. - erfc
   } else {
-    return (tmp2, tmp1);
+    erfc - 1.
   }
 }
@@ -0,0 +1,139 @@
+use std::{collections::HashMap, fs::File, io::Write};
+
+use halo2_proofs::{circuit::Value, halo2curves::ff::PrimeField};
+use ndarray::{Array, IxDyn};
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+  helpers::convert_pos_int,
+  loader::{commit_before_idxes, ModelMsgpack},
+};
+
+// One contiguous run of public instance values
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstanceEntry {
+  // One of: commitment, metadata, output, output_commitment
+  pub kind: String,
+  pub offset: usize,
+  pub len: usize,
+  pub tensor_idx: Option<i64>,
+  // Set for per-example entries in batch mode
+  pub example: Option<usize>,
+  pub shape: Vec<i64>,
+  pub scale_factor: i64,
+}
+
+// Describes where every value in the public instance comes from, in the order that the circuit
+// constrains them. Written next to the proof so that verifiers decode outputs from data instead of
+// assuming the shapes and scale factor of the model.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstanceLayout {
+  pub entries: Vec<InstanceEntry>,
+}
+
+impl InstanceLayout {
+  pub fn from_model(config: &ModelMsgpack) -> Self {
+    let mut shapes: HashMap<i64, Vec<i64>> = HashMap::new();
+    for tensor in config.tensors.iter() {
+      shapes.insert(tensor.idx, tensor.shape.clone());
+    }
+    for layer in config.layers.iter() {
+      for (idx, shape) in layer.out_idxes.iter().zip(layer.out_shapes.iter()) {
+        shapes.insert(*idx, shape.clone());
+      }
+    }
+
+    let mut entries = vec![];
+    let mut offset = 0;
+    let mut push = |kind: &str, len: usize, tensor_idx, example, shape: Vec<i64>, sf: i64| {
+      entries.push(InstanceEntry {
+        kind: kind.to_string(),
+        offset,
+        len,
+        tensor_idx,
+        example,
+        shape,
+        scale_factor: sf,
+      });
+      offset += len;
+    };
+
+    // Must match the order in ModelCircuit::synthesize
+    let commit_before = commit_before_idxes(config);
+    let commit_after = config.commit_after.clone().unwrap_or(vec![]);
+    for _ in commit_before.iter().chain(commit_after.iter()) {
+      push("commitment", 1, None, None, vec![1], 1);
+    }
+    // Each hex digest is split into 128-bit limbs
+    for digest in config.metadata_digests.clone().unwrap_or(vec![]).iter() {
+      let num_limbs = digest.trim_start_matches("0x").len().div_ceil(32);
+      push("metadata", num_limbs, None, None, vec![num_limbs as i64], 1);
+    }
+
+    let reveal_examples = config.reveal_examples.clone().unwrap_or(vec![]);
+    for idx in config.out_idxes.iter() {
+      let shape = shapes.get(idx).unwrap().clone();
+      if reveal_examples.len() == 0 {
+        let len = shape.iter().product::<i64>() as usize;
+        push("output", len, Some(*idx), None, shape, config.global_sf);
+        continue;
+      }
+
+      let example_shape = shape[1..].to_vec();
+      for (batch_idx, reveal) in reveal_examples.iter().enumerate() {
+        if *reveal == 1 {
+          let len = example_shape.iter().product::<i64>() as usize;
+          let shape = example_shape.clone();
+          push("output", len, Some(*idx), Some(batch_idx), shape, config.global_sf);
+        } else {
+          push("output_commitment", 1, Some(*idx), Some(batch_idx), vec![1], 1);
+        }
+      }
+    }
+
+    InstanceLayout { entries }
+  }
+
+  pub fn num_public_vals(&self) -> usize {
+    self.entries.iter().map(|entry| entry.len).sum()
+  }
+
+  pub fn write(&self, path: &str) {
+    let mut file = File::create(path).unwrap();
+    file
+      .write_all(serde_json::to_string_pretty(self).unwrap().as_bytes())
+      .unwrap();
+  }
+
+  pub fn read(path: &str) -> Self {
+    let layout = std::fs::read_to_string(path).unwrap();
+    serde_json::from_str(&layout).unwrap()
+  }
+}
+
+// Decodes the revealed outputs in the public values using their recorded shapes and scale factors
+pub fn decode_outputs<F: PrimeField>(
+  layout: &InstanceLayout,
+  public_vals: &Vec<F>,
+) -> Vec<(InstanceEntry, Array<f64, IxDyn>)> {
+  assert_eq!(
+    layout.num_public_vals(),
+    public_vals.len(),
+    "instance layout does not match the public values"
+  );
+
+  layout
+    .entries
+    .iter()
+    .filter(|entry| entry.kind == "output")
+    .map(|entry| {
+      let vals = public_vals[entry.offset..entry.offset + entry.len]
+        .iter()
+        .map(|x| convert_pos_int(Value::known(*x)) as f64 / entry.scale_factor as f64)
+        .collect::<Vec<_>>();
+      let shape = entry.shape.iter().map(|x| *x as usize).collect::<Vec<_>>();
+      let outp = Array::from_shape_vec(IxDyn(&shape), vals).unwrap();
+      (entry.clone(), outp)
+    })
+    .collect()
+}
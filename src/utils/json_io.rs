@@ -0,0 +1,97 @@
+use std::{fs::File, io::Write};
+
+use halo2_proofs::halo2curves::ff::PrimeField;
+use serde_derive::{Deserialize, Serialize};
+
+use super::{
+  instance_layout::{decode_outputs, InstanceLayout},
+  loader::{ModelMsgpack, TensorMsgpack},
+};
+
+// JSON interchange format for the CLI, so that callers don't need msgpack or the Rust types:
+//   {"inputs": [{"name": "0", "shape": [1, 28, 28, 1], "data": [0.0, 0.5, ...]}]}
+//   {"outputs": [{"name": "output_14", "shape": [1, 10], "data": [0.1, ...]}]}
+// Data is flattened in row-major order and given as real numbers, which are scaled by the model's
+// scale factor. Inputs are matched to the model inputs by position; the name is informational.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JsonTensor {
+  pub name: String,
+  pub shape: Vec<i64>,
+  pub data: Vec<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JsonInputs {
+  pub inputs: Vec<JsonTensor>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JsonOutputs {
+  pub outputs: Vec<JsonTensor>,
+}
+
+pub fn load_json_inputs(inp_path: &str, config: &ModelMsgpack) -> Vec<TensorMsgpack> {
+  let inp: JsonInputs = {
+    let file = File::open(inp_path).unwrap();
+    serde_json::from_reader(file).unwrap()
+  };
+  assert_eq!(
+    inp.inputs.len(),
+    config.inp_idxes.len(),
+    "expected {} inputs",
+    config.inp_idxes.len()
+  );
+
+  inp
+    .inputs
+    .iter()
+    .zip(config.inp_idxes.iter())
+    .map(|(tensor, idx)| {
+      assert_eq!(
+        tensor.shape.iter().product::<i64>() as usize,
+        tensor.data.len(),
+        "input {} has the wrong number of elements",
+        tensor.name
+      );
+      TensorMsgpack {
+        idx: *idx,
+        shape: tensor.shape.clone(),
+        data: tensor
+          .data
+          .iter()
+          .map(|x| (x * config.global_sf as f64).round() as i64)
+          .collect(),
+      }
+    })
+    .collect()
+}
+
+pub fn write_json_outputs<F: PrimeField>(
+  path: &str,
+  layout: &InstanceLayout,
+  public_vals: &Vec<F>,
+) {
+  let outputs = decode_outputs(layout, public_vals)
+    .into_iter()
+    .map(|(entry, outp)| {
+      let name = match entry.example {
+        Some(example) => format!("output_{}_{}", entry.tensor_idx.unwrap(), example),
+        None => format!("output_{}", entry.tensor_idx.unwrap()),
+      };
+      JsonTensor {
+        name,
+        shape: entry.shape,
+        data: outp.iter().cloned().collect(),
+      }
+    })
+    .collect();
+
+  let mut file = File::create(path).unwrap();
+  file
+    .write_all(
+      serde_json::to_string_pretty(&JsonOutputs { outputs })
+        .unwrap()
+        .as_bytes(),
+    )
+    .unwrap();
+}
@@ -0,0 +1,52 @@
+use std::{fs::File, path::Path};
+
+use super::loader::ModelMsgpack;
+
+// Weights and inputs are assigned to advice columns, so the keys only depend on the structure of
+// the model: the layers, the tensor shapes, and the circuit settings. A fine-tuned model can reuse
+// the keys of the original one.
+pub fn structure(config: &ModelMsgpack) -> ModelMsgpack {
+  let mut structure = config.clone();
+  for tensor in structure.tensors.iter_mut() {
+    tensor.data = vec![];
+  }
+  structure.tensors.sort_by_key(|tensor| tensor.idx);
+  structure
+}
+
+// Whether the two models have the same proving and verification keys
+pub fn keys_compatible(config_a: &ModelMsgpack, config_b: &ModelMsgpack) -> bool {
+  let structure_a = serde_json::to_value(structure(config_a)).unwrap();
+  let structure_b = serde_json::to_value(structure(config_b)).unwrap();
+  structure_a == structure_b
+}
+
+// Each entry of the cache is a directory with the structure of the model and its keys
+pub fn find_cached_keys(cache_dir: &str, config: &ModelMsgpack) -> Option<String> {
+  let entries = std::fs::read_dir(cache_dir).ok()?;
+  for entry in entries {
+    let entry_dir = entry.unwrap().path();
+    let structure_path = entry_dir.join("structure.json");
+    let cached: ModelMsgpack = match File::open(&structure_path) {
+      Ok(file) => serde_json::from_reader(file).unwrap(),
+      Err(_) => continue,
+    };
+    if keys_compatible(&cached, config) && entry_dir.join("pkey").exists() {
+      return Some(entry_dir.to_str().unwrap().to_string());
+    }
+  }
+  None
+}
+
+// Copies the keys that the prover just wrote into a new cache entry
+pub fn cache_keys(cache_dir: &str, config: &ModelMsgpack, vkey_path: &str, pkey_path: &str) {
+  std::fs::create_dir_all(cache_dir).unwrap();
+  let num_entries = std::fs::read_dir(cache_dir).unwrap().count();
+  let entry_dir = Path::new(cache_dir).join(format!("model_{}", num_entries));
+  std::fs::create_dir_all(&entry_dir).unwrap();
+
+  let file = File::create(entry_dir.join("structure.json")).unwrap();
+  serde_json::to_writer(file, &structure(config)).unwrap();
+  std::fs::copy(vkey_path, entry_dir.join("vkey")).unwrap();
+  std::fs::copy(pkey_path, entry_dir.join("pkey")).unwrap();
+}
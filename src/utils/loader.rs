@@ -2,6 +2,10 @@ use std::{fs::File, io::BufReader};
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::{gadgets::gadget::DEFAULT_RESERVED_ROWS, model::match_layer};
+
+use super::{json_io::load_json_inputs, optimizer::downcast_1x1_convs};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TensorMsgpack {
   pub idx: i64,
@@ -34,20 +38,209 @@ pub struct ModelMsgpack {
   pub commit_after: Option<Vec<Vec<i64>>>,
   pub bits_per_elem: Option<i64>, // Specifically for packing for the commitments
   pub num_random: Option<i64>,
+  // Hex-encoded digests (e.g., model card, license) exposed in the public instance
+  pub metadata_digests: Option<Vec<String>>,
+  // Per-example output visibility in batch mode: 1 reveals the output, 0 only commits to it
+  pub reveal_examples: Option<Vec<i64>>,
+  // Rows at the bottom of the circuit kept free of tables, defaults to the halo2 blinding rows
+  pub reserved_rows: Option<i64>,
+  // Accumulate the dot products of the FC and convolution layers with the MAC gadget
+  pub use_mac: Option<bool>,
+  // Bit-width of the nonlinearity inputs when it is above k. The lookup tables are replaced by
+  // coarse tables of at most 2^12 entries with linear interpolation between them
+  pub nonlinear_input_bits: Option<i64>,
+  // Commit to the inputs with Poseidon so only their digest is public, see commit_before_idxes
+  pub commit_inputs: Option<bool>,
 }
 
-pub fn load_config_msgpack(config_path: &str) -> ModelMsgpack {
-  let model: ModelMsgpack = {
-    let file = File::open(config_path).unwrap();
-    let mut reader = BufReader::new(file);
-    rmp_serde::from_read(&mut reader).unwrap()
+// The groups of tensors committed to before the DAG. With commit_inputs, the inputs are committed
+// first, so their digest is the first public value. The prover can later reveal the inputs and
+// anyone can recompute the digest to check they are the ones the proof was made for.
+pub fn commit_before_idxes(model: &ModelMsgpack) -> Vec<Vec<i64>> {
+  let mut commit_before = vec![];
+  if model.commit_inputs.unwrap_or(false) {
+    commit_before.push(model.inp_idxes.clone());
+  }
+  commit_before.extend(model.commit_before.clone().unwrap_or(vec![]));
+  commit_before
+}
+
+// Widths and counts of the dot products a layer performs
+fn dot_product_shapes(layer: &LayerMsgpack) -> Vec<(i64, i64)> {
+  let num_out = |shape: &Vec<i64>| shape.iter().product::<i64>();
+  match layer.layer_type.as_str() {
+    "Conv2D" => {
+      let weights = &layer.inp_shapes[1];
+      let out = &layer.out_shapes[0];
+      // Depthwise convolutions do one dot product per output element over the kernel
+      if layer.params[0] == 1 {
+        vec![(weights[1] * weights[2], num_out(out))]
+      } else {
+        vec![(weights[1..].iter().product(), num_out(out))]
+      }
+    }
+    "FullyConnected" | "BatchMatMul" => {
+      let inp = &layer.inp_shapes[0];
+      vec![(inp[inp.len() - 1], num_out(&layer.out_shapes[0]))]
+    }
+    _ => vec![],
+  }
+}
+
+// Picks the number of columns that wastes the fewest cells on the dot products while still
+// fitting in 2^k rows. Commitments need at least 8 columns for Poseidon, and piecewise linear
+// nonlinearities need 7.
+pub fn choose_num_cols(model: &ModelMsgpack) -> i64 {
+  let shapes = model
+    .layers
+    .iter()
+    .flat_map(|layer| dot_product_shapes(layer))
+    .collect::<Vec<_>>();
+
+  let commits = commit_before_idxes(model).len()
+    + model.commit_after.as_ref().map_or(0, |x| x.len());
+  let piecewise_linear = model.nonlinear_input_bits.unwrap_or(0) > 0
+    || model
+      .layers
+      .iter()
+      .any(|layer| layer.layer_type == "PiecewiseLinear");
+  let min_cols = if commits > 0 {
+    8
+  } else if piecewise_linear {
+    7
+  } else {
+    6
   };
+  let max_cols = 32;
+  if shapes.len() == 0 {
+    return min_cols;
+  }
+
+  let max_rows = (1 << model.k) - model.reserved_rows.unwrap_or(DEFAULT_RESERVED_ROWS);
+  let mut best: Option<(i64, i64)> = None;
+  for num_cols in min_cols..max_cols + 1 {
+    let inps_per_row = (num_cols - 1) / 2;
+    let rows = shapes
+      .iter()
+      .map(|(width, count)| (width + inps_per_row - 1) / inps_per_row * count)
+      .sum::<i64>();
+    if rows > max_rows {
+      continue;
+    }
+    let cells = rows * num_cols;
+    if best.map_or(true, |(_, best_cells)| cells < best_cells) {
+      best = Some((num_cols, cells));
+    }
+  }
+
+  best.map_or(max_cols, |(num_cols, _)| num_cols)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoaderMode {
+  // Error on any unknown field or op
+  Strict,
+  // Warn on unknown fields and map unknown ops that don't affect inference to Noop
+  Permissive,
+}
+
+// Ops that exporters sometimes leave in the graph but that are the identity at inference time
+pub const INFERENCE_NOOP_OPS: [&str; 8] = [
+  "AlphaDropout",
+  "Dropout",
+  "GaussianDropout",
+  "GaussianNoise",
+  "Identity",
+  "PreventGradient",
+  "Snapshot",
+  "StopGradient",
+];
+
+// Top-level fields that the converter emits but the circuit doesn't use
+pub const IGNORED_FIELDS: [&str; 1] = ["commit"];
+
+fn unknown_keys(raw: &serde_json::Value, known: &serde_json::Value, prefix: &str) -> Vec<String> {
+  match (raw.as_object(), known.as_object()) {
+    (Some(raw), Some(known)) => raw
+      .keys()
+      .filter(|key| !known.contains_key(*key))
+      .filter(|key| !prefix.is_empty() || !IGNORED_FIELDS.contains(&key.as_str()))
+      .map(|key| format!("{}{}", prefix, key))
+      .collect(),
+    _ => vec![],
+  }
+}
+
+// Fields in the file that the loader doesn't know about (and serde silently drops)
+fn unknown_fields(raw: &serde_json::Value, model: &ModelMsgpack) -> Vec<String> {
+  let known = serde_json::to_value(model).unwrap();
+  let mut unknown = unknown_keys(raw, &known, "");
+  for key in ["layers", "tensors"] {
+    if let (Some(raw), Some(known)) = (raw[key].as_array(), known[key].as_array()) {
+      for (i, (raw, known)) in raw.iter().zip(known.iter()).enumerate() {
+        unknown.extend(unknown_keys(raw, known, &format!("{}[{}].", key, i)));
+      }
+    }
+  }
+  unknown
+}
+
+pub fn load_config_msgpack(config_path: &str) -> ModelMsgpack {
+  load_config_msgpack_mode(config_path, LoaderMode::Permissive)
+}
+
+pub fn load_config_msgpack_mode(config_path: &str, mode: LoaderMode) -> ModelMsgpack {
+  let buf = std::fs::read(config_path).unwrap();
+  let mut model: ModelMsgpack = rmp_serde::from_slice(&buf).unwrap();
+
+  let raw: serde_json::Value = rmp_serde::from_slice(&buf).unwrap();
+  let fields = unknown_fields(&raw, &model);
+  if fields.len() > 0 {
+    if mode == LoaderMode::Strict {
+      panic!("unknown fields in {}: {:?}", config_path, fields);
+    }
+    println!("WARNING: ignoring unknown fields in {}: {:?}", config_path, fields);
+  }
+
+  for (idx, layer) in model.layers.iter_mut().enumerate() {
+    if match_layer(&layer.layer_type).is_some() {
+      continue;
+    }
+    let is_noop = INFERENCE_NOOP_OPS.contains(&layer.layer_type.as_str());
+    if mode == LoaderMode::Strict || !is_noop {
+      panic!("unknown op at layer {}: {}", idx, layer.layer_type);
+    }
+    println!("WARNING: mapping {} at layer {} to Noop", layer.layer_type, idx);
+    layer.layer_type = "Noop".to_string();
+    layer.params = vec![0];
+    layer.inp_idxes.truncate(1);
+    layer.inp_shapes.truncate(1);
+  }
+
+  downcast_1x1_convs(&mut model);
+
+  // A non-positive number of columns means the loader picks one
+  if model.num_cols <= 0 {
+    model.num_cols = choose_num_cols(&model);
+    println!("Using {} columns", model.num_cols);
+  }
   model
 }
 
 pub fn load_model_msgpack(config_path: &str, inp_path: &str) -> ModelMsgpack {
-  let mut model = load_config_msgpack(config_path);
-  let inp: Vec<TensorMsgpack> = {
+  load_model_msgpack_mode(config_path, inp_path, LoaderMode::Permissive)
+}
+
+pub fn load_model_msgpack_mode(
+  config_path: &str,
+  inp_path: &str,
+  mode: LoaderMode,
+) -> ModelMsgpack {
+  let mut model = load_config_msgpack_mode(config_path, mode);
+  // Inputs may also be given in the JSON format of json_io
+  let inp: Vec<TensorMsgpack> = if inp_path.ends_with(".json") {
+    load_json_inputs(inp_path, &model)
+  } else {
     let file = File::open(inp_path).unwrap();
     let mut reader = BufReader::new(file);
     rmp_serde::from_read(&mut reader).unwrap()
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use super::loader::{LayerMsgpack, ModelMsgpack};
+
+// A 1x1 Conv2D with unit strides is a fully connected layer over the flattened pixels. Fully
+// connected layers are checked with random linear combinations instead of one dot product per
+// output, so they are much cheaper.
+fn is_1x1_conv(
+  layer: &LayerMsgpack,
+  weight_uses: &HashMap<i64, usize>,
+  constants: &Vec<i64>,
+  num_random: i64,
+) -> bool {
+  if layer.layer_type != "Conv2D" || layer.inp_shapes.len() < 2 || layer.params.len() < 5 {
+    return false;
+  }
+  let params = &layer.params;
+  let inp_shape = &layer.inp_shapes[0];
+  let weights_shape = &layer.inp_shapes[1];
+  let out_shape = &layer.out_shapes[0];
+  let groups = if params.len() > 7 { params[7] } else { 1 };
+
+  // Regular convolution, no relu6 (the fully connected layer doesn't support it), unit strides
+  let conv_ok = params[0] == 0 && (params[2] == 0 || params[2] == 1) && params[3] == 1;
+  let shape_ok = params[4] == 1
+    && groups == 1
+    && weights_shape.len() == 4
+    && weights_shape[1] == 1
+    && weights_shape[2] == 1
+    && inp_shape.len() == 4
+    && inp_shape[1..3] == out_shape[1..3];
+  if !conv_ok || !shape_ok {
+    return false;
+  }
+
+  // The weights are reshaped in place, so they must be constants that only this layer uses
+  let weights_idx = layer.inp_idxes[1];
+  if !constants.contains(&weights_idx) || weight_uses.get(&weights_idx) != Some(&1) {
+    return false;
+  }
+
+  // The random vectors must cover both dimensions of the output
+  let rows = inp_shape[0] * inp_shape[1] * inp_shape[2];
+  rows <= num_random && out_shape[3] <= num_random
+}
+
+// Rewrites 1x1 convolutions into Reshape -> FullyConnected -> Reshape
+pub fn downcast_1x1_convs(model: &mut ModelMsgpack) {
+  let num_random = model.num_random.unwrap_or(0);
+  let constants = model.tensors.iter().map(|x| x.idx).collect::<Vec<_>>();
+  let mut weight_uses: HashMap<i64, usize> = HashMap::new();
+  for layer in model.layers.iter() {
+    for idx in layer.inp_idxes.iter() {
+      *weight_uses.entry(*idx).or_insert(0) += 1;
+    }
+  }
+
+  let mut next_idx = model
+    .layers
+    .iter()
+    .flat_map(|layer| layer.inp_idxes.iter().chain(layer.out_idxes.iter()))
+    .chain(constants.iter())
+    .max()
+    .map_or(0, |x| x + 1);
+
+  let mut layers = vec![];
+  for layer in model.layers.iter() {
+    if !is_1x1_conv(layer, &weight_uses, &constants, num_random) {
+      layers.push(layer.clone());
+      continue;
+    }
+
+    let inp_shape = layer.inp_shapes[0].clone();
+    let out_shape = layer.out_shapes[0].clone();
+    let rows = inp_shape[0] * inp_shape[1] * inp_shape[2];
+    let flat_inp_shape = vec![rows, inp_shape[3]];
+    let flat_out_shape = vec![rows, out_shape[3]];
+    let weights_shape = vec![out_shape[3], inp_shape[3]];
+    let (flat_inp_idx, flat_out_idx) = (next_idx, next_idx + 1);
+    next_idx += 2;
+
+    // (O, 1, 1, C) -> (O, C), the data is unchanged
+    let weights_idx = layer.inp_idxes[1];
+    for tensor in model.tensors.iter_mut() {
+      if tensor.idx == weights_idx {
+        tensor.shape = weights_shape.clone();
+      }
+    }
+
+    let mut fc_inp_idxes = vec![flat_inp_idx, weights_idx];
+    let mut fc_inp_shapes = vec![flat_inp_shape.clone(), weights_shape];
+    if layer.inp_idxes.len() > 2 {
+      fc_inp_idxes.push(layer.inp_idxes[2]);
+      fc_inp_shapes.push(layer.inp_shapes[2].clone());
+    }
+
+    layers.push(LayerMsgpack {
+      layer_type: "Reshape".to_string(),
+      params: vec![],
+      inp_idxes: vec![layer.inp_idxes[0]],
+      inp_shapes: vec![inp_shape],
+      out_idxes: vec![flat_inp_idx],
+      out_shapes: vec![flat_inp_shape],
+      mask: vec![],
+    });
+    // Activation, transposed weights, and whether to floor after the division
+    let floor = (layer.params.get(12) == Some(&1)) as i64;
+    layers.push(LayerMsgpack {
+      layer_type: "FullyConnected".to_string(),
+      params: vec![layer.params[2], 1, floor],
+      inp_idxes: fc_inp_idxes,
+      inp_shapes: fc_inp_shapes,
+      out_idxes: vec![flat_out_idx],
+      out_shapes: vec![flat_out_shape.clone()],
+      mask: vec![],
+    });
+    layers.push(LayerMsgpack {
+      layer_type: "Reshape".to_string(),
+      params: vec![],
+      inp_idxes: vec![flat_out_idx],
+      inp_shapes: vec![flat_out_shape],
+      out_idxes: layer.out_idxes.clone(),
+      out_shapes: layer.out_shapes.clone(),
+      mask: vec![],
+    });
+  }
+
+  model.layers = layers;
+}
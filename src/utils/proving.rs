@@ -0,0 +1,78 @@
+use halo2_proofs::{
+  arithmetic::CurveAffine,
+  halo2curves::ff::{FromUniformBytes, PrimeField, WithSmallOrderMulGroup},
+  plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, VerifyingKey},
+  poly::{
+    commitment::{CommitmentScheme, Params, Prover, Verifier},
+    VerificationStrategy,
+  },
+  transcript::{
+    Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+  },
+};
+
+use crate::model::ModelCircuit;
+
+// Field / curve generic versions of the proving steps. time_circuit_kzg and time_circuit_ipa are
+// the concrete versions used by the binaries; these are for crates that embed zkml with their own
+// commitment scheme, e.g. keygen::<G1Affine, _> and prove::<KZGCommitmentScheme<Bn256>,
+// ProverSHPLONK<_>>.
+
+pub fn keygen<'params, C, P>(params: &P, circuit: &ModelCircuit<C::Scalar>) -> ProvingKey<C>
+where
+  C: CurveAffine,
+  C::Scalar: PrimeField + Ord + FromUniformBytes<64>,
+  P: Params<'params, C>,
+{
+  let vk = keygen_vk(params, circuit).unwrap();
+  keygen_pk(params, vk, circuit).unwrap()
+}
+
+pub fn prove<'params, Scheme, P>(
+  params: &'params Scheme::ParamsProver,
+  pk: &ProvingKey<Scheme::Curve>,
+  circuit: ModelCircuit<Scheme::Scalar>,
+  public_vals: &Vec<Scheme::Scalar>,
+) -> Vec<u8>
+where
+  Scheme: CommitmentScheme,
+  Scheme::Scalar: PrimeField + Ord + FromUniformBytes<64> + WithSmallOrderMulGroup<3>,
+  P: Prover<'params, Scheme>,
+{
+  let rng = rand::thread_rng();
+  let mut transcript = Blake2bWrite::<_, Scheme::Curve, Challenge255<_>>::init(vec![]);
+  create_proof::<Scheme, P, Challenge255<Scheme::Curve>, _, _, ModelCircuit<Scheme::Scalar>>(
+    params,
+    pk,
+    &[circuit],
+    &[&[&public_vals[..]]],
+    rng,
+    &mut transcript,
+  )
+  .unwrap();
+  transcript.finalize()
+}
+
+pub fn verify<'params, Scheme, V, Strategy>(
+  params: &'params Scheme::ParamsVerifier,
+  vk: &VerifyingKey<Scheme::Curve>,
+  proof: &[u8],
+  public_vals: &Vec<Scheme::Scalar>,
+) -> bool
+where
+  Scheme: CommitmentScheme,
+  Scheme::Scalar: PrimeField + Ord + FromUniformBytes<64> + WithSmallOrderMulGroup<3>,
+  V: Verifier<'params, Scheme>,
+  Strategy: VerificationStrategy<'params, Scheme, V>,
+{
+  let strategy = Strategy::new(params);
+  let mut transcript = Blake2bRead::<_, Scheme::Curve, Challenge255<_>>::init(proof);
+  verify_proof::<Scheme, V, Challenge255<Scheme::Curve>, _, Strategy>(
+    params,
+    vk,
+    strategy,
+    &[&[&public_vals[..]]],
+    &mut transcript,
+  )
+  .is_ok()
+}
@@ -23,7 +23,10 @@ use halo2_proofs::{
   },
 };
 
-use crate::{model::ModelCircuit, utils::helpers::get_public_values};
+use crate::{
+  model::ModelCircuit,
+  utils::{helpers::get_public_values, telemetry::ProverTelemetry},
+};
 
 pub fn get_ipa_params(params_dir: &str, degree: u32) -> ParamsIPA<EqAffine> {
   let path = format!("{}/{}.params", params_dir, degree);
@@ -45,7 +48,7 @@ pub fn get_ipa_params(params_dir: &str, degree: u32) -> ParamsIPA<EqAffine> {
   params
 }
 
-pub fn time_circuit_ipa(circuit: ModelCircuit<Fp>) {
+pub fn time_circuit_ipa(circuit: ModelCircuit<Fp>, telemetry: bool) {
   let rng = rand::thread_rng();
   let start = Instant::now();
 
@@ -108,6 +111,17 @@ pub fn time_circuit_ipa(circuit: ModelCircuit<Fp>) {
   };
   println!("Proof size: {} bytes", proof_size);
 
+  if telemetry {
+    ProverTelemetry::new(
+      "ipa",
+      degree as usize,
+      pk_duration - circuit_duration,
+      fill_duration - pk_duration,
+      proof_duration - fill_duration,
+    )
+    .write("telemetry.json");
+  }
+
   let strategy = SingleStrategy::new(&params);
   let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
   assert!(
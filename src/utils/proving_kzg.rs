@@ -8,7 +8,7 @@ use std::{
 use halo2_proofs::{
   dev::MockProver,
   halo2curves::bn256::{Bn256, Fr, G1Affine},
-  plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, VerifyingKey},
+  plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, VerifyingKey},
   poly::{
     commitment::Params,
     kzg::{
@@ -23,7 +23,10 @@ use halo2_proofs::{
   SerdeFormat,
 };
 
-use crate::{model::ModelCircuit, utils::helpers::get_public_values};
+use crate::{
+  model::ModelCircuit,
+  utils::{helpers::get_public_values, telemetry::ProverTelemetry},
+};
 
 pub fn get_kzg_params(params_dir: &str, degree: u32) -> ParamsKZG<Bn256> {
   let rng = rand::thread_rng();
@@ -71,7 +74,13 @@ pub fn verify_kzg(
   );
 }
 
-pub fn time_circuit_kzg(circuit: ModelCircuit<Fr>) {
+// If cached_pkey is set, the proving key is read from it instead of being generated. It must come
+// from a structurally identical model (see key_cache::keys_compatible).
+pub fn time_circuit_kzg(
+  circuit: ModelCircuit<Fr>,
+  telemetry: bool,
+  cached_pkey: Option<&str>,
+) -> Option<ProverTelemetry> {
   let rng = rand::thread_rng();
   let start = Instant::now();
 
@@ -84,27 +93,42 @@ pub fn time_circuit_kzg(circuit: ModelCircuit<Fr>) {
     circuit_duration
   );
 
-  let vk_circuit = circuit.clone();
-  let vk = keygen_vk(&params, &vk_circuit).unwrap();
-  drop(vk_circuit);
-  let vk_duration = start.elapsed();
-  println!(
-    "Time elapsed in generating vkey: {:?}",
-    vk_duration - circuit_duration
-  );
+  let pk = match cached_pkey {
+    Some(pkey_path) => {
+      let pk = ProvingKey::<G1Affine>::read::<_, ModelCircuit<Fr>>(
+        &mut BufReader::new(File::open(pkey_path).unwrap()),
+        SerdeFormat::RawBytes,
+        circuit.gadget_config.clone(),
+      )
+      .unwrap();
+      println!("Loaded cached pkey from {}", pkey_path);
+      pk
+    }
+    None => {
+      let vk_circuit = circuit.clone();
+      let vk = keygen_vk(&params, &vk_circuit).unwrap();
+      drop(vk_circuit);
+      let vk_duration = start.elapsed();
+      println!(
+        "Time elapsed in generating vkey: {:?}",
+        vk_duration - circuit_duration
+      );
+
+      let pk_circuit = circuit.clone();
+      let pk = keygen_pk(&params, vk, &pk_circuit).unwrap();
+      println!(
+        "Time elapsed in generating pkey: {:?}",
+        start.elapsed() - vk_duration
+      );
+      drop(pk_circuit);
+      pk
+    }
+  };
+  let pk_duration = start.elapsed();
 
-  let vkey_size = serialize(&vk.to_bytes(SerdeFormat::RawBytes), "vkey");
+  let vkey_size = serialize(&pk.get_vk().to_bytes(SerdeFormat::RawBytes), "vkey");
   println!("vkey size: {} bytes", vkey_size);
 
-  let pk_circuit = circuit.clone();
-  let pk = keygen_pk(&params, vk, &pk_circuit).unwrap();
-  let pk_duration = start.elapsed();
-  println!(
-    "Time elapsed in generating pkey: {:?}",
-    pk_duration - vk_duration
-  );
-  drop(pk_circuit);
-
   let pkey_size = serialize(&pk.to_bytes(SerdeFormat::RawBytes), "pkey");
   println!("pkey size: {} bytes", pkey_size);
 
@@ -152,6 +176,19 @@ pub fn time_circuit_kzg(circuit: ModelCircuit<Fr>) {
 
   println!("Proof size: {} bytes", proof_size);
 
+  let telemetry = telemetry.then(|| {
+    ProverTelemetry::new(
+      "kzg",
+      degree as usize,
+      pk_duration - circuit_duration,
+      fill_duration - pk_duration,
+      proof_duration - fill_duration,
+    )
+  });
+  if let Some(telemetry) = &telemetry {
+    telemetry.write("telemetry.json");
+  }
+
   let strategy = SingleStrategy::new(&params);
   let transcript_read = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
 
@@ -165,6 +202,8 @@ pub fn time_circuit_kzg(circuit: ModelCircuit<Fr>) {
   );
   let verify_duration = start.elapsed();
   println!("Verifying time: {:?}", verify_duration - proof_duration);
+
+  telemetry
 }
 
 // Standalone verification
@@ -173,7 +212,7 @@ pub fn verify_circuit_kzg(
   vkey_fname: &str,
   proof_fname: &str,
   public_vals_fname: &str,
-) {
+) -> Vec<Fr> {
   let degree = circuit.k as u32;
   let params = get_kzg_params("./params_kzg", degree);
   println!("Loaded the parameters");
@@ -181,7 +220,7 @@ pub fn verify_circuit_kzg(
   let vk = VerifyingKey::read::<BufReader<File>, ModelCircuit<Fr>>(
     &mut BufReader::new(File::open(vkey_fname).unwrap()),
     SerdeFormat::RawBytes,
-    (),
+    circuit.gadget_config.clone(),
   )
   .unwrap();
   println!("Loaded vkey");
@@ -202,5 +241,7 @@ pub fn verify_circuit_kzg(
   verify_kzg(&params, &vk, strategy, &public_vals, transcript);
   let verify_duration = start.elapsed();
   println!("Verifying time: {:?}", verify_duration - verify_start);
-  println!("Proof verified!")
+  println!("Proof verified!");
+
+  public_vals
 }
@@ -0,0 +1,50 @@
+use std::{fs::File, io::Write, time::Duration};
+
+use serde_derive::{Deserialize, Serialize};
+
+// Non-sensitive proving statistics, written next to the proof and into the proof bundle when
+// requested. Contains no witness, model, or input data.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProverTelemetry {
+  pub crate_version: String,
+  pub backend: String,
+  pub k: usize,
+  pub keygen_ms: u128,
+  pub synthesis_ms: u128,
+  pub prove_ms: u128,
+  pub peak_memory_kb: Option<u64>,
+}
+
+impl ProverTelemetry {
+  pub fn new(
+    backend: &str,
+    k: usize,
+    keygen: Duration,
+    synthesis: Duration,
+    prove: Duration,
+  ) -> Self {
+    ProverTelemetry {
+      crate_version: env!("CARGO_PKG_VERSION").to_string(),
+      backend: backend.to_string(),
+      k,
+      keygen_ms: keygen.as_millis(),
+      synthesis_ms: synthesis.as_millis(),
+      prove_ms: prove.as_millis(),
+      peak_memory_kb: peak_memory_kb(),
+    }
+  }
+
+  pub fn write(&self, path: &str) {
+    let mut file = File::create(path).unwrap();
+    file
+      .write_all(serde_json::to_string_pretty(self).unwrap().as_bytes())
+      .unwrap();
+  }
+}
+
+// Only available on Linux
+pub fn peak_memory_kb() -> Option<u64> {
+  let status = std::fs::read_to_string("/proc/self/status").ok()?;
+  let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+  line.split_whitespace().nth(1)?.parse().ok()
+}
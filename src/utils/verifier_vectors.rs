@@ -0,0 +1,157 @@
+use std::{fs::File, io::Write};
+
+use halo2_proofs::{
+  circuit::Value,
+  dev::MockProver,
+  halo2curves::{
+    bn256::{Bn256, Fq, Fq2, Fr, G1Affine, G2Affine},
+    ff::PrimeField,
+  },
+  poly::kzg::{commitment::KZGCommitmentScheme, multiopen::ProverSHPLONK},
+  SerdeFormat,
+};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{interface::verifier_params, model::ModelCircuitBn256};
+
+use super::{
+  helpers::{convert_to_bigint, get_public_values},
+  loader::{load_config_msgpack, load_model_msgpack},
+  proving::{keygen, prove},
+  proving_kzg::get_kzg_params,
+};
+
+// Test vectors for reimplementations of the verifier in other languages. The format is stable:
+// bytes are hex encoded, field elements and curve coordinates are decimal strings, and G2
+// coordinates are [c0, c1]. The proof uses KZG on bn256 with SHPLONK and the Blake2b transcript
+// (Challenge255), as in interface::verify.
+pub const VECTOR_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct G2Point {
+  pub x: [String; 2],
+  pub y: [String; 2],
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifierVector {
+  pub version: u32,
+  pub description: String,
+  pub k: u32,
+  pub g2: G2Point,
+  pub s_g2: G2Point,
+  // Hex encoded msgpack of the model config, as taken by interface::verify
+  pub config: String,
+  pub vk: String,
+  pub proof: String,
+  pub public_vals: Vec<String>,
+  pub expected: bool,
+}
+
+fn field_to_string<F: PrimeField>(x: &F) -> String {
+  convert_to_bigint(Value::known(*x)).to_str_radix(10)
+}
+
+fn fq2_to_strings(x: &Fq2) -> [String; 2] {
+  [field_to_string(&x.c0), field_to_string(&x.c1)]
+}
+
+fn to_g2_point(p: &G2Affine) -> G2Point {
+  G2Point {
+    x: fq2_to_strings(&p.x),
+    y: fq2_to_strings(&p.y),
+  }
+}
+
+fn from_g2_point(p: &G2Point) -> G2Affine {
+  let fq = |x: &String| Fq::from_str_vartime(x).unwrap();
+  G2Affine {
+    x: Fq2::new(fq(&p.x[0]), fq(&p.x[1])),
+    y: Fq2::new(fq(&p.y[0]), fq(&p.y[1])),
+  }
+}
+
+impl VerifierVector {
+  pub fn verify(&self) -> bool {
+    let params = verifier_params(self.k, from_g2_point(&self.g2), from_g2_point(&self.s_g2));
+    crate::interface::is_valid(
+      self.vk.clone(),
+      self.proof.clone(),
+      &self.public_vals,
+      self.config.clone(),
+      &params,
+    )
+  }
+}
+
+// Proves the model on the input and emits one valid vector followed by vectors with a corrupted
+// proof and a corrupted public value, which must be rejected
+pub fn generate_vectors(config_fname: &str, inp_fname: &str) -> Vec<VerifierVector> {
+  let config = load_config_msgpack(config_fname);
+  let config_hex = hex::encode(rmp_serde::to_vec_named(&config).unwrap());
+
+  let circuit = ModelCircuitBn256::generate_from_msgpack(
+    load_model_msgpack(config_fname, inp_fname),
+    true,
+  );
+  let k = circuit.k as u32;
+  let params = get_kzg_params("./params_kzg", k);
+  let pk = keygen::<G1Affine, _>(&params, &circuit);
+
+  MockProver::run(k, &circuit, vec![vec![]]).unwrap();
+  let public_vals = get_public_values::<Fr>();
+  assert!(public_vals.len() > 0, "the model has no public values");
+  let proof = prove::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>>(
+    &params,
+    &pk,
+    circuit,
+    &public_vals,
+  );
+
+  let valid = VerifierVector {
+    version: VECTOR_VERSION,
+    description: "valid proof".to_string(),
+    k,
+    g2: to_g2_point(&params.g2),
+    s_g2: to_g2_point(&params.s_g2),
+    config: config_hex,
+    vk: hex::encode(pk.get_vk().to_bytes(SerdeFormat::RawBytes)),
+    proof: hex::encode(&proof),
+    public_vals: public_vals.iter().map(field_to_string).collect(),
+    expected: true,
+  };
+
+  let mut bad_proof = proof.clone();
+  let mid = bad_proof.len() / 2;
+  bad_proof[mid] ^= 1;
+  let bad_proof = VerifierVector {
+    description: "proof with a flipped bit".to_string(),
+    proof: hex::encode(&bad_proof),
+    expected: false,
+    ..valid.clone()
+  };
+
+  let mut bad_public_vals = valid.public_vals.clone();
+  let last = public_vals.len() - 1;
+  bad_public_vals[last] = field_to_string(&(public_vals[last] + Fr::ONE));
+  let bad_public_vals = VerifierVector {
+    description: "incremented last public value".to_string(),
+    public_vals: bad_public_vals,
+    expected: false,
+    ..valid.clone()
+  };
+
+  vec![valid, bad_proof, bad_public_vals]
+}
+
+pub fn write_vectors(path: &str, vectors: &Vec<VerifierVector>) {
+  let mut file = File::create(path).unwrap();
+  file
+    .write_all(serde_json::to_string_pretty(vectors).unwrap().as_bytes())
+    .unwrap();
+}
+
+pub fn read_vectors(path: &str) -> Vec<VerifierVector> {
+  let vectors = std::fs::read_to_string(path).unwrap();
+  serde_json::from_str(&vectors).unwrap()
+}
@@ -0,0 +1,17 @@
+#!/usr/bin/env bash
+# Checks to run before a release, from the repository root. This includes the tests that are
+# ignored by default because they generate params and keys for the full models. Pass --generate
+# to (re)write the checked in artifacts first, then commit them.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+cargo build --release
+
+if [[ "${1:-}" == "--generate" ]]; then
+  ./target/release/gen_verifier_vectors examples/mnist/model.msgpack examples/mnist/inp.msgpack \
+    testing/verifier_vectors/mnist.json
+  ZKML_UPDATE_VK_DIGESTS=1 cargo test --release --test vk_digests -- --ignored
+fi
+
+cargo test --release --workspace -- --include-ignored
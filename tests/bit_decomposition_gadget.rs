@@ -0,0 +1,171 @@
+use std::rc::Rc;
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+  dev::MockProver,
+  halo2curves::bn256::Fr,
+  plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use proptest::prelude::*;
+use zkml::gadgets::{
+  bit_decomposition::BitDecompositionChip,
+  gadget::{Gadget, GadgetConfig, GadgetType, DEFAULT_RESERVED_ROWS},
+  input_lookup::InputLookupChip,
+};
+
+// The bit decomposition gadget on its own. There are 4 bits per row, so 8 bits take two rows and
+// the zero accumulator. The bits are exposed on the instance column.
+
+const K: u32 = 8;
+const NUM_COLS: usize = 5;
+const NUM_BITS: usize = 8;
+
+// With a tamper, a single row [acc, b_0, ..., b_3] is assigned by hand instead of by the gadget,
+// followed by a zero accumulator
+#[derive(Clone, Default)]
+struct BitDecompositionCircuit {
+  inp: Vec<u64>,
+  tamper: Option<[u64; NUM_COLS]>,
+}
+
+impl Circuit<Fr> for BitDecompositionCircuit {
+  type Config = (GadgetConfig, Column<Instance>);
+  type FloorPlanner = SimpleFloorPlanner;
+  type Params = ();
+
+  fn without_witnesses(&self) -> Self {
+    Self::default()
+  }
+
+  fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+    let columns = (0..NUM_COLS)
+      .map(|_| meta.advice_column())
+      .collect::<Vec<_>>();
+    for col in columns.iter() {
+      meta.enable_equality(*col);
+    }
+    let public_col = meta.instance_column();
+    meta.enable_equality(public_col);
+
+    let gadget_config = GadgetConfig {
+      columns,
+      num_rows: (1 << K) - DEFAULT_RESERVED_ROWS as usize,
+      use_selectors: true,
+      ..Default::default()
+    };
+    let gadget_config = InputLookupChip::<Fr>::configure(meta, gadget_config);
+    (
+      BitDecompositionChip::<Fr>::configure(meta, gadget_config),
+      public_col,
+    )
+  }
+
+  fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+    let (gadget_config, public_col) = config;
+    let columns = gadget_config.columns.clone();
+    let gadget_config = Rc::new(gadget_config);
+    InputLookupChip::<Fr>::construct(gadget_config.clone())
+      .load_lookups(layouter.namespace(|| "input lookup"))?;
+
+    let out = match self.tamper {
+      Some(row) => layouter.assign_region(
+        || "tampered row",
+        |mut region| {
+          let selector = gadget_config.selectors[&GadgetType::BitDecomposition][0];
+          selector.enable(&mut region, 0)?;
+          let mut cells = vec![];
+          for (i, val) in row.iter().enumerate() {
+            let val = Value::known(Fr::from(*val));
+            cells.push(region.assign_advice(|| "", columns[i], 0, || val)?);
+          }
+          region.assign_advice(|| "", columns[0], 1, || Value::known(Fr::from(0)))?;
+          Ok(cells[1..].to_vec())
+        },
+      )?,
+      None => {
+        let (zero, inp) = layouter.assign_region(
+          || "bit decomposition inputs",
+          |mut region| {
+            let zero = region.assign_advice(|| "", columns[0], 0, || Value::known(Fr::from(0)))?;
+            let inp = self
+              .inp
+              .iter()
+              .enumerate()
+              .map(|(i, x)| {
+                region.assign_advice(|| "", columns[0], i + 1, || Value::known(Fr::from(*x)))
+              })
+              .collect::<Result<Vec<AssignedCell<Fr, Fr>>, _>>()?;
+            Ok((zero, inp))
+          },
+        )?;
+        BitDecompositionChip::<Fr>::construct(gadget_config.clone(), NUM_BITS).forward(
+          layouter.namespace(|| "bit decomposition"),
+          &vec![inp.iter().collect()],
+          &vec![&zero],
+        )?
+      }
+    };
+    for (i, cell) in out.iter().enumerate() {
+      layouter.constrain_instance(cell.cell(), public_col, i)?;
+    }
+
+    Ok(())
+  }
+}
+
+fn verify(circuit: BitDecompositionCircuit, expected: Vec<u64>) -> bool {
+  let public_vals = expected.iter().map(|x| Fr::from(*x)).collect::<Vec<_>>();
+  let prover = MockProver::run(K, &circuit, vec![public_vals]).unwrap();
+  prover.verify().is_ok()
+}
+
+fn bits(x: u64, num_bits: usize) -> Vec<u64> {
+  (0..num_bits).map(|i| (x >> i) & 1).collect()
+}
+
+fn tampered(row: [u64; NUM_COLS]) -> bool {
+  let circuit = BitDecompositionCircuit {
+    inp: vec![],
+    tamper: Some(row),
+  };
+  verify(circuit, row[1..].to_vec())
+}
+
+// The hand-assigned row is accepted when it is a valid decomposition
+#[test]
+fn bit_decomposition_accepts_honest_row() {
+  assert!(tampered([6, 0, 1, 1, 0]));
+}
+
+// The bits recompose to 1 + 2 + 4 = 7 instead of the accumulator
+#[test]
+fn bit_decomposition_rejects_wrong_recomposition() {
+  assert!(!tampered([6, 1, 1, 1, 0]));
+}
+
+// 6 = 2 + 4 * 1 satisfies the running sum with a non-boolean bit
+#[test]
+fn bit_decomposition_rejects_non_boolean_bit() {
+  assert!(!tampered([6, 2, 0, 1, 0]));
+}
+
+// The accumulator after the last row is zero, so 2^8 doesn't fit in 8 bits
+#[test]
+fn bit_decomposition_rejects_overflow() {
+  let circuit = BitDecompositionCircuit {
+    inp: vec![1 << NUM_BITS],
+    tamper: None,
+  };
+  assert!(!verify(circuit, vec![0; NUM_BITS]));
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(16))]
+
+  #[test]
+  fn bit_decomposition_matches(inp in prop::collection::vec(0u64..(1 << NUM_BITS), 1..4)) {
+    let expected = inp.iter().flat_map(|x| bits(*x, NUM_BITS)).collect();
+    let circuit = BitDecompositionCircuit { inp, tamper: None };
+    prop_assert!(verify(circuit, expected));
+  }
+}
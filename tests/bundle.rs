@@ -0,0 +1,43 @@
+use ed25519_dalek::SigningKey;
+use zkml::utils::{
+  bundle::{sign_bundle, verify_bundle},
+  telemetry::ProverTelemetry,
+};
+
+// The telemetry is part of the signed metadata, so it can't be changed after signing
+
+const PROOF: &[u8] = &[1, 2, 3];
+const PUBLIC_VALS: &[u8] = &[4, 5];
+
+fn telemetry() -> ProverTelemetry {
+  ProverTelemetry {
+    backend: "kzg".to_string(),
+    k: 17,
+    prove_ms: 1000,
+    ..Default::default()
+  }
+}
+
+#[test]
+fn telemetry_is_signed() {
+  let key = SigningKey::from_bytes(&[7; 32]);
+  let bundle = sign_bundle(&key, PROOF, PUBLIC_VALS, 3600, Some(telemetry()));
+  assert_eq!(verify_bundle(&bundle, PROOF, PUBLIC_VALS, None), Ok(()));
+
+  let mut tampered = bundle.clone();
+  tampered.metadata.telemetry.as_mut().unwrap().prove_ms = 1;
+  assert!(verify_bundle(&tampered, PROOF, PUBLIC_VALS, None).is_err());
+
+  let mut dropped = bundle;
+  dropped.metadata.telemetry = None;
+  assert!(verify_bundle(&dropped, PROOF, PUBLIC_VALS, None).is_err());
+}
+
+#[test]
+fn telemetry_is_skipped_when_off() {
+  let key = SigningKey::from_bytes(&[7; 32]);
+  let bundle = sign_bundle(&key, PROOF, PUBLIC_VALS, 3600, None);
+  let metadata = serde_json::to_value(&bundle.metadata).unwrap();
+  assert!(metadata.get("telemetry").is_none());
+  assert_eq!(verify_bundle(&bundle, PROOF, PUBLIC_VALS, None), Ok(()));
+}
@@ -0,0 +1,336 @@
+use std::sync::Mutex;
+
+use halo2_proofs::{
+  circuit::Value,
+  dev::MockProver,
+  halo2curves::bn256::{Bn256, Fr},
+  plonk::{keygen_vk, Circuit, ConstraintSystem},
+  poly::kzg::commitment::ParamsKZG,
+  SerdeFormat,
+};
+use ndarray::{Array, Axis, IxDyn};
+use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+use zkml::{
+  gadgets::{
+    gadget::{GadgetType, DEFAULT_RESERVED_ROWS},
+    nonlinear::{erf::ErfGadgetChip, non_linearity::NonLinearGadget},
+    nonlinear::{rsqrt::RsqrtGadgetChip, sqrt::SqrtGadgetChip},
+  },
+  model::ModelCircuit,
+  utils::{
+    helpers::{convert_pos_int, get_public_values},
+    loader::{load_config_msgpack, LayerMsgpack, ModelMsgpack, TensorMsgpack},
+    optimizer::downcast_1x1_convs,
+  },
+};
+
+// Algebraic identities of the gadgets, checked over the whole table range. Off-by-one errors in
+// the tables only show up at the edges, so the strategies always include the range endpoints.
+
+const K: i64 = 12;
+const SF: i64 = 64;
+const MIN_VAL: i64 = -(1 << (K - 1));
+const MAX_VAL: i64 = MIN_VAL + (1 << K) - DEFAULT_RESERVED_ROWS - 1;
+const NUM_ROWS: i64 = (1 << K) - DEFAULT_RESERVED_ROWS;
+
+// The public values are global, so circuits can't be run concurrently
+static CIRCUIT_LOCK: Mutex<()> = Mutex::new(());
+
+// Proves a model with the mock prover and returns its (scaled) outputs
+fn run_model(config: ModelMsgpack) -> Vec<i64> {
+  let _guard = CIRCUIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+  let circuit = ModelCircuit::<Fr>::generate_from_msgpack(config, true);
+  MockProver::run(K as u32, &circuit, vec![vec![]]).unwrap();
+  let public_vals = get_public_values::<Fr>();
+  let prover = MockProver::run(K as u32, &circuit, vec![public_vals.clone()]).unwrap();
+  assert_eq!(prover.verify(), Ok(()));
+
+  public_vals
+    .iter()
+    .map(|x| convert_pos_int(Value::known(*x)) as i64)
+    .collect()
+}
+
+fn tensor(idx: i64, shape: Vec<i64>, data: Vec<i64>) -> TensorMsgpack {
+  TensorMsgpack { idx, shape, data }
+}
+
+// A model over the given tensors, with the default settings of the tests
+fn model_config(tensors: Vec<TensorMsgpack>, layers: Vec<LayerMsgpack>) -> ModelMsgpack {
+  ModelMsgpack {
+    global_sf: SF,
+    k: K,
+    num_cols: 6,
+    inp_idxes: vec![0],
+    out_idxes: layers.last().unwrap().out_idxes.clone(),
+    tensors,
+    layers,
+    use_selectors: Some(true),
+    commit_before: Some(vec![]),
+    commit_after: Some(vec![]),
+    bits_per_elem: None,
+    num_random: None,
+    metadata_digests: None,
+    reveal_examples: None,
+    reserved_rows: None,
+    use_mac: None,
+    nonlinear_input_bits: None,
+    commit_inputs: None,
+  }
+}
+
+// Proves a single layer model and returns its output
+fn run_layer(layer_type: &str, params: Vec<i64>, inp: Vec<i64>) -> Vec<i64> {
+  let shape = vec![inp.len() as i64];
+  let config = model_config(
+    vec![tensor(0, shape.clone(), inp)],
+    vec![LayerMsgpack {
+      layer_type: layer_type.to_string(),
+      params,
+      inp_idxes: vec![0],
+      inp_shapes: vec![shape.clone()],
+      out_idxes: vec![1],
+      out_shapes: vec![shape],
+      mask: vec![],
+    }],
+  );
+  run_model(config)
+}
+
+// Rounding x / sf to the nearest value perturbs y = x^2 by at most this much
+fn squared_tolerance(x: f64) -> f64 {
+  let err = 0.5 / SF as f64;
+  err * (2. * x.abs() + err)
+}
+
+// The Maclaurin series of erf, which converges quickly enough for |x| <= 4. Beyond that, erf(x) is
+// within 2e-8 of its sign
+fn reference_erf(x: f64) -> f64 {
+  if x.abs() > 4. {
+    return x.signum();
+  }
+  let mut term = x;
+  let mut sum = x;
+  for n in 1..100 {
+    term *= -x * x / n as f64;
+    sum += term / (2 * n + 1) as f64;
+  }
+  sum * 2. / std::f64::consts::PI.sqrt()
+}
+
+fn table_vals(lo: i64, hi: i64) -> impl Strategy<Value = Vec<i64>> {
+  prop::collection::vec(prop_oneof![Just(lo), Just(hi), lo..=hi], 1..32)
+}
+
+#[test]
+fn rsqrt_squared_is_reciprocal() {
+  let map = RsqrtGadgetChip::<Fr>::generate_map(SF as u64, MIN_VAL, NUM_ROWS);
+  for x in 1..=MAX_VAL {
+    let rsqrt = map[&(x - MIN_VAL)] as f64 / SF as f64;
+    let exact = (SF as f64 / x as f64).sqrt();
+    let recip = SF as f64 / x as f64;
+    assert!(
+      (rsqrt * rsqrt - recip).abs() <= squared_tolerance(exact) + 1e-9,
+      "rsqrt({}) = {}",
+      x,
+      rsqrt
+    );
+  }
+}
+
+#[test]
+fn sqrt_squared_is_identity() {
+  let map = SqrtGadgetChip::<Fr>::generate_map(SF as u64, MIN_VAL, NUM_ROWS);
+  for x in 0..=MAX_VAL {
+    let sqrt = map[&(x - MIN_VAL)] as f64 / SF as f64;
+    let x_real = x as f64 / SF as f64;
+    assert!(
+      (sqrt * sqrt - x_real).abs() <= squared_tolerance(x_real.sqrt()) + 1e-9,
+      "sqrt({}) = {}",
+      x,
+      sqrt
+    );
+  }
+}
+
+#[test]
+fn erf_matches_reference() {
+  let map = ErfGadgetChip::<Fr>::generate_map(SF as u64, MIN_VAL, NUM_ROWS);
+  for x in MIN_VAL..=MAX_VAL {
+    let expected = (reference_erf(x as f64 / SF as f64) * SF as f64).round() as i64;
+    let erf = map[&(x - MIN_VAL)];
+    assert!((erf - expected).abs() <= 1, "erf({}) = {}", x, erf);
+  }
+}
+
+// A 1x1 conv that floors is rewritten into a fully connected layer, which must still floor. The
+// weight is 0.5, so rounding and flooring differ on odd inputs.
+#[test]
+fn floor_1x1_conv_survives_downcast() {
+  let inp = vec![3, -3, 5, -5];
+  let mut config = model_config(
+    vec![
+      tensor(0, vec![1, 2, 2, 1], inp.clone()),
+      tensor(1, vec![1, 1, 1, 1], vec![SF / 2]),
+      tensor(2, vec![1], vec![0]),
+    ],
+    vec![LayerMsgpack {
+      layer_type: "Conv2D".to_string(),
+      params: vec![0, 1, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1],
+      inp_idxes: vec![0, 1, 2],
+      inp_shapes: vec![vec![1, 2, 2, 1], vec![1, 1, 1, 1], vec![1]],
+      out_idxes: vec![3],
+      out_shapes: vec![vec![1, 2, 2, 1]],
+      mask: vec![],
+    }],
+  );
+  config.num_random = Some(4);
+
+  downcast_1x1_convs(&mut config);
+  let layer_types = config.layers.iter().map(|x| x.layer_type.as_str()).collect::<Vec<_>>();
+  assert_eq!(layer_types, vec!["Reshape", "FullyConnected", "Reshape"]);
+
+  let out = run_model(config);
+  let expected = inp.iter().map(|x| x.div_euclid(2)).collect::<Vec<_>>();
+  assert_eq!(out, expected);
+}
+
+// Keygen runs on the circuit without its witnesses, which must give the same key. The divisor of
+// DivVar is a tensor, so it is unknown once the witnesses are dropped.
+#[test]
+fn vk_is_independent_of_witnesses() {
+  let _guard = CIRCUIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+  let config = model_config(
+    vec![tensor(0, vec![4], vec![-3, -1, 2, 5]), tensor(1, vec![1], vec![3 * SF])],
+    vec![LayerMsgpack {
+      layer_type: "DivVar".to_string(),
+      params: vec![],
+      inp_idxes: vec![0, 1],
+      inp_shapes: vec![vec![4], vec![1]],
+      out_idxes: vec![2],
+      out_shapes: vec![vec![4]],
+      mask: vec![],
+    }],
+  );
+  let circuit = ModelCircuit::<Fr>::generate_from_msgpack(config, true);
+  let params = ParamsKZG::<Bn256>::setup(K as u32, StdRng::seed_from_u64(0));
+  let vk = keygen_vk(&params, &circuit).unwrap();
+  let blank_vk = keygen_vk(&params, &circuit.without_witnesses()).unwrap();
+  assert_eq!(
+    vk.to_bytes(SerdeFormat::RawBytes),
+    blank_vk.to_bytes(SerdeFormat::RawBytes)
+  );
+}
+
+// Shifting by the scale factor needs 2 * sf rows in the input lookup table. Larger scale factors
+// rescale with VarDivRound instead, and configuring the circuit must not panic.
+#[test]
+fn large_scale_factors_skip_the_shift_gadget() {
+  let _guard = CIRCUIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+  let used_gadgets = |sf: i64| {
+    let mut config = model_config(
+      vec![tensor(0, vec![1, 2], vec![sf, 2 * sf]), tensor(1, vec![2, 2], vec![sf, 0, 0, sf])],
+      vec![LayerMsgpack {
+        layer_type: "FullyConnected".to_string(),
+        params: vec![0],
+        inp_idxes: vec![0, 1],
+        inp_shapes: vec![vec![1, 2], vec![2, 2]],
+        out_idxes: vec![2],
+        out_shapes: vec![vec![1, 2]],
+        mask: vec![],
+      }],
+    );
+    config.global_sf = sf;
+    let circuit = ModelCircuit::<Fr>::generate_from_msgpack(config, true);
+    let mut cs = ConstraintSystem::<Fr>::default();
+    ModelCircuit::<Fr>::configure_with_params(&mut cs, circuit.params());
+    circuit.gadget_config.used_gadgets.clone()
+  };
+
+  assert!(used_gadgets(SF).contains(&GadgetType::Shift));
+  let large = used_gadgets(1 << (K - 1));
+  assert!(!large.contains(&GadgetType::Shift));
+  assert!(large.contains(&GadgetType::VarDivRound));
+}
+
+// The Mean layers of the twitter model, with their params and shapes. One of them averages
+// [1, 1024, 1] over axis 1 into [1], which drops the unit axes that aren't reduced as well.
+#[test]
+fn twitter_means_match_reference() {
+  let twitter =
+    load_config_msgpack(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/twitter/config.msgpack"));
+  let means = twitter
+    .layers
+    .iter()
+    .filter(|x| x.layer_type == "Mean")
+    .collect::<Vec<_>>();
+  assert!(!means.is_empty());
+
+  for mean in means {
+    let inp_shape = mean.inp_shapes[0].clone();
+    let ndim = inp_shape.len() as i64;
+    let mut axes = mean
+      .params
+      .iter()
+      .map(|x| (if *x < 0 { x + ndim } else { *x }) as usize)
+      .collect::<Vec<_>>();
+    axes.sort();
+    axes.dedup();
+
+    let num_inps = inp_shape.iter().product::<i64>();
+    let inp = (0..num_inps).map(|x| x % 7 - 3).collect::<Vec<_>>();
+    let shape = inp_shape.iter().map(|x| *x as usize).collect::<Vec<_>>();
+    let mut sums = Array::from_shape_vec(IxDyn(&shape), inp.clone()).unwrap();
+    for axis in axes.iter().rev() {
+      sums = sums.sum_axis(Axis(*axis));
+    }
+    let div = axes.iter().map(|i| inp_shape[*i]).product::<i64>();
+    let expected = sums
+      .iter()
+      .map(|x| (2 * x + div).div_euclid(2 * div))
+      .collect::<Vec<_>>();
+
+    let layer = LayerMsgpack {
+      inp_idxes: vec![0],
+      inp_shapes: vec![inp_shape.clone()],
+      out_idxes: vec![1],
+      ..mean.clone()
+    };
+    let out = run_model(model_config(vec![tensor(0, inp_shape, inp)], vec![layer]));
+    assert_eq!(out, expected);
+  }
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(16))]
+
+  #[test]
+  fn var_div_round_inverts_mul(
+    x in table_vals(MIN_VAL, MAX_VAL),
+    d in prop_oneof![Just(1i64), Just(SF), 1..=SF],
+  ) {
+    let inp = x.iter().map(|x| x * d).collect::<Vec<_>>();
+    let out = run_layer("Div", vec![d], inp);
+    prop_assert_eq!(out, x);
+  }
+
+  #[test]
+  fn rsqrt_circuit_matches_table(x in table_vals(1, MAX_VAL)) {
+    let map = RsqrtGadgetChip::<Fr>::generate_map(SF as u64, MIN_VAL, NUM_ROWS);
+    let expected = x.iter().map(|x| map[&(x - MIN_VAL)]).collect::<Vec<_>>();
+    let out = run_layer("Rsqrt", vec![], x);
+    prop_assert_eq!(out, expected);
+  }
+
+  // Row 0 of the nonlinear tables is the all-zero padding row, so min_val itself maps to 0
+  #[test]
+  fn abs_matches_absolute_value(x in table_vals(MIN_VAL + 1, MAX_VAL)) {
+    let out = run_layer("Abs", vec![], x.clone());
+    let expected = x.iter().map(|x| x.abs()).collect::<Vec<_>>();
+    prop_assert_eq!(out, expected);
+  }
+}
@@ -0,0 +1,24 @@
+use zkml::utils::loader::{load_config_msgpack_mode, LoaderMode};
+
+// Strict mode must accept the bundled models, which carry converter metadata the circuit ignores,
+// and still reject fields it doesn't know about.
+
+const MNIST: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/mnist/model.msgpack");
+
+#[test]
+fn strict_mode_loads_mnist() {
+  let model = load_config_msgpack_mode(MNIST, LoaderMode::Strict);
+  assert!(model.layers.len() > 0);
+}
+
+#[test]
+#[should_panic(expected = "unknown fields")]
+fn strict_mode_rejects_unknown_fields() {
+  let buf = std::fs::read(MNIST).unwrap();
+  let mut raw: serde_json::Value = rmp_serde::from_slice(&buf).unwrap();
+  raw["not_a_field"] = serde_json::Value::from(1);
+
+  let path = std::env::temp_dir().join("zkml_strict_unknown_field.msgpack");
+  std::fs::write(&path, rmp_serde::to_vec_named(&raw).unwrap()).unwrap();
+  load_config_msgpack_mode(path.to_str().unwrap(), LoaderMode::Strict);
+}
@@ -0,0 +1,137 @@
+use std::rc::Rc;
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+  dev::MockProver,
+  halo2curves::bn256::Fr,
+  plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use proptest::prelude::*;
+use zkml::gadgets::{
+  gadget::{Gadget, GadgetConfig},
+  mux::MuxChip,
+};
+
+// The mux gadget on its own, without a model around it. The outputs are exposed on the instance
+// column so the expected values are checked by the prover.
+
+const K: u32 = 6;
+const NUM_COLS: usize = 10;
+
+fn to_field(x: i64) -> Fr {
+  if x < 0 {
+    -Fr::from(x.unsigned_abs())
+  } else {
+    Fr::from(x as u64)
+  }
+}
+
+#[derive(Clone, Default)]
+struct MuxCircuit {
+  cond: Vec<i64>,
+  a: Vec<i64>,
+  b: Vec<i64>,
+}
+
+impl Circuit<Fr> for MuxCircuit {
+  type Config = (GadgetConfig, Column<Instance>);
+  type FloorPlanner = SimpleFloorPlanner;
+  type Params = ();
+
+  fn without_witnesses(&self) -> Self {
+    Self::default()
+  }
+
+  fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+    let columns = (0..NUM_COLS)
+      .map(|_| meta.advice_column())
+      .collect::<Vec<_>>();
+    for col in columns.iter() {
+      meta.enable_equality(*col);
+    }
+    let public_col = meta.instance_column();
+    meta.enable_equality(public_col);
+
+    let gadget_config = GadgetConfig {
+      columns,
+      use_selectors: true,
+      ..Default::default()
+    };
+    (MuxChip::<Fr>::configure(meta, gadget_config), public_col)
+  }
+
+  fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+    let (gadget_config, public_col) = config;
+    let columns = gadget_config.columns.clone();
+
+    let (zero, inps) = layouter.assign_region(
+      || "mux inputs",
+      |mut region| {
+        let zero = region.assign_advice(|| "", columns[0], 0, || Value::known(Fr::from(0)))?;
+        let mut inps: Vec<Vec<AssignedCell<Fr, Fr>>> = vec![];
+        for (i, vals) in [&self.cond, &self.a, &self.b].iter().enumerate() {
+          let cells = vals
+            .iter()
+            .enumerate()
+            .map(|(j, x)| {
+              region.assign_advice(|| "", columns[i], j + 1, || Value::known(to_field(*x)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+          inps.push(cells);
+        }
+        Ok((zero, inps))
+      },
+    )?;
+
+    let mux_chip = MuxChip::<Fr>::construct(Rc::new(gadget_config));
+    let vec_inputs = inps.iter().map(|x| x.iter().collect()).collect::<Vec<_>>();
+    let out = mux_chip.forward(layouter.namespace(|| "mux"), &vec_inputs, &vec![&zero])?;
+    for (i, cell) in out.iter().enumerate() {
+      layouter.constrain_instance(cell.cell(), public_col, i)?;
+    }
+
+    Ok(())
+  }
+}
+
+fn verify(circuit: MuxCircuit, expected: Vec<i64>) -> bool {
+  let public_vals = expected.iter().map(|x| to_field(*x)).collect::<Vec<_>>();
+  let prover = MockProver::run(K, &circuit, vec![public_vals]).unwrap();
+  prover.verify().is_ok()
+}
+
+#[test]
+fn mux_rejects_non_boolean_condition() {
+  let circuit = MuxCircuit {
+    cond: vec![2],
+    a: vec![5],
+    b: vec![3],
+  };
+  // b + c * (a - b) with c = 2
+  assert!(!verify(circuit, vec![7]));
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(16))]
+
+  #[test]
+  fn mux_selects(
+    vals in prop::collection::vec((any::<bool>(), -1000i64..1000, -1000i64..1000), 1..20),
+  ) {
+    let circuit = MuxCircuit {
+      cond: vals.iter().map(|x| x.0 as i64).collect(),
+      a: vals.iter().map(|x| x.1).collect(),
+      b: vals.iter().map(|x| x.2).collect(),
+    };
+    let expected = vals.iter().map(|(c, a, b)| if *c { *a } else { *b }).collect();
+    prop_assert!(verify(circuit, expected));
+  }
+
+  #[test]
+  fn mux_rejects_wrong_output(c in any::<bool>(), a in -1000i64..1000, b in -1000i64..1000) {
+    prop_assume!(a != b);
+    let circuit = MuxCircuit { cond: vec![c as i64], a: vec![a], b: vec![b] };
+    let wrong = if c { b } else { a };
+    prop_assert!(!verify(circuit, vec![wrong]));
+  }
+}
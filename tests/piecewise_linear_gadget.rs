@@ -0,0 +1,206 @@
+use std::rc::Rc;
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+  dev::MockProver,
+  halo2curves::bn256::Fr,
+  plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use proptest::prelude::*;
+use zkml::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType, DEFAULT_RESERVED_ROWS},
+  input_lookup::InputLookupChip,
+  nonlinear::relu::ReluChip,
+};
+
+// A nonlinearity on inputs wider than its table, through the coarse table and the interpolation
+// constraints. ReLU is exact under interpolation since 0 is a segment boundary, so the outputs
+// can be checked against max(x, 0). The outputs are exposed on the instance column.
+
+const K: u32 = 8;
+const INPUT_BITS: i64 = 12;
+const X_MAX: i64 = 1 << (INPUT_BITS - 1);
+
+fn to_field(x: i64) -> Fr {
+  if x < 0 {
+    -Fr::from(x.unsigned_abs())
+  } else {
+    Fr::from(x as u64)
+  }
+}
+
+// With a tamper, the row of the first input is assigned by hand as the honest
+// [x, s, r, y_i, delta_i, y, rem] plus the tamper, instead of by the gadget
+#[derive(Clone, Default)]
+struct CompressedReluCircuit<const NUM_COLS: usize> {
+  inp: Vec<i64>,
+  tamper: Option<[i64; 7]>,
+}
+
+impl<const NUM_COLS: usize> CompressedReluCircuit<NUM_COLS> {
+  fn honest_row(gadget_config: &GadgetConfig, x: i64) -> [i64; 7] {
+    let maps = &gadget_config.maps[&GadgetType::Relu];
+    let (x_min, w) = (maps[2][&0], maps[2][&1]);
+    let seg = (x - x_min).div_euclid(w);
+    let r = (x - x_min).rem_euclid(w);
+    let (y0, delta) = (maps[0][&seg], maps[1][&seg]);
+    let num = 2 * (w * y0 + delta * r) + w;
+    [x, seg + 1, r, y0, delta, num.div_euclid(2 * w), num.rem_euclid(2 * w)]
+  }
+}
+
+impl<const NUM_COLS: usize> Circuit<Fr> for CompressedReluCircuit<NUM_COLS> {
+  type Config = (GadgetConfig, Column<Instance>);
+  type FloorPlanner = SimpleFloorPlanner;
+  type Params = ();
+
+  fn without_witnesses(&self) -> Self {
+    Self::default()
+  }
+
+  fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+    let columns = (0..NUM_COLS)
+      .map(|_| meta.advice_column())
+      .collect::<Vec<_>>();
+    for col in columns.iter() {
+      meta.enable_equality(*col);
+    }
+    let fixed_col = meta.fixed_column();
+    meta.enable_equality(fixed_col);
+    let public_col = meta.instance_column();
+    meta.enable_equality(public_col);
+
+    let gadget_config = GadgetConfig {
+      columns,
+      fixed_columns: vec![fixed_col],
+      scale_factor: 16,
+      shift_min_val: -(1 << 20),
+      num_rows: (1 << K) - DEFAULT_RESERVED_ROWS as usize,
+      use_selectors: true,
+      nonlinear_input_bits: INPUT_BITS,
+      ..Default::default()
+    };
+    let gadget_config = InputLookupChip::<Fr>::configure(meta, gadget_config);
+    (ReluChip::<Fr>::configure(meta, gadget_config), public_col)
+  }
+
+  fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+    let (gadget_config, public_col) = config;
+    let columns = gadget_config.columns.clone();
+    let gadget_config = Rc::new(gadget_config);
+
+    InputLookupChip::<Fr>::construct(gadget_config.clone())
+      .load_lookups(layouter.namespace(|| "input lookup"))?;
+    let relu_chip = ReluChip::<Fr>::construct(gadget_config.clone());
+    relu_chip.load_lookups(layouter.namespace(|| "relu lookup"))?;
+
+    let out = match self.tamper {
+      Some(tamper) => layouter.assign_region(
+        || "tampered row",
+        |mut region| {
+          let selector = gadget_config.selectors[&GadgetType::Relu][0];
+          selector.enable(&mut region, 0)?;
+          let row = Self::honest_row(&gadget_config, self.inp[0]);
+          let mut cells = vec![];
+          for (i, (val, diff)) in row.iter().zip(tamper.iter()).enumerate() {
+            let val = Value::known(to_field(val + diff));
+            cells.push(region.assign_advice(|| "", columns[i], 0, || val)?);
+          }
+          Ok(vec![cells[5].clone()])
+        },
+      )?,
+      None => {
+        let (zero, inp) = layouter.assign_region(
+          || "relu inputs",
+          |mut region| {
+            let zero = region.assign_advice(|| "", columns[0], 0, || Value::known(Fr::from(0)))?;
+            let inp = self
+              .inp
+              .iter()
+              .enumerate()
+              .map(|(i, x)| {
+                region.assign_advice(|| "", columns[0], i + 1, || Value::known(to_field(*x)))
+              })
+              .collect::<Result<Vec<AssignedCell<Fr, Fr>>, _>>()?;
+            Ok((zero, inp))
+          },
+        )?;
+        relu_chip.forward(
+          layouter.namespace(|| "relu"),
+          &vec![inp.iter().collect()],
+          &vec![&zero],
+        )?
+      }
+    };
+    for (i, cell) in out.iter().enumerate() {
+      layouter.constrain_instance(cell.cell(), public_col, i)?;
+    }
+
+    Ok(())
+  }
+}
+
+fn verify<const NUM_COLS: usize>(
+  inp: Vec<i64>,
+  tamper: Option<[i64; 7]>,
+  expected: Vec<i64>,
+) -> bool {
+  let circuit = CompressedReluCircuit::<NUM_COLS> { inp, tamper };
+  let public_vals = expected.iter().map(|x| to_field(*x)).collect::<Vec<_>>();
+  let prover = MockProver::run(K, &circuit, vec![public_vals]).unwrap();
+  prover.verify().is_ok()
+}
+
+#[test]
+fn compressed_relu_domain_edges() {
+  let inp = vec![-X_MAX, -1, 0, 1, X_MAX - 1];
+  let expected = inp.iter().map(|x| (*x).max(0)).collect();
+  assert!(verify::<14>(inp, None, expected));
+}
+
+#[test]
+#[should_panic(expected = "piecewise linear functions need at least 7 columns")]
+fn compressed_relu_needs_seven_columns() {
+  verify::<6>(vec![1], None, vec![1]);
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(16))]
+
+  // The input lengths are not multiples of the ops per row, so the padding is covered
+  #[test]
+  fn compressed_relu_matches(inp in prop::collection::vec(-X_MAX..X_MAX, 1..9)) {
+    let expected = inp.iter().map(|x| (*x).max(0)).collect();
+    prop_assert!(verify::<14>(inp, None, expected));
+  }
+
+  #[test]
+  fn compressed_relu_rejects_wrong_output(
+    x in -X_MAX..X_MAX,
+    err in prop_oneof![-8i64..0, 1i64..9],
+  ) {
+    prop_assert!(!verify::<14>(vec![x], None, vec![x.max(0) + err]));
+  }
+
+  // The hand-assigned row is accepted when it is not tampered with
+  #[test]
+  fn compressed_relu_accepts_honest_row(x in -X_MAX..X_MAX) {
+    prop_assert!(verify::<7>(vec![x], Some([0; 7]), vec![x.max(0)]));
+  }
+
+  // Shifting y by one keeps both gates satisfied, so only the range check on rem can catch it.
+  // Shifting the segment keeps x = x_min + (s - 1) * w + r, but puts r out of range.
+  #[test]
+  fn compressed_relu_rejects_tampered_row(
+    x in -X_MAX..X_MAX,
+    tamper in prop_oneof![
+      Just([0, 0, 0, 0, 0, 1, -64]),
+      Just([0, 0, 0, 0, 0, -1, 64]),
+      Just([0, 1, -32, 0, 0, 0, 0]),
+      Just([0, -1, 32, 0, 0, 0, 0]),
+    ],
+  ) {
+    let expected = x.max(0) + tamper[5];
+    prop_assert!(!verify::<7>(vec![x], Some(tamper), vec![expected]));
+  }
+}
@@ -0,0 +1,133 @@
+use std::rc::Rc;
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+  dev::MockProver,
+  halo2curves::bn256::Fr,
+  plonk::{Circuit, ConstraintSystem, Error},
+};
+use proptest::prelude::*;
+use zkml::gadgets::{
+  gadget::{Gadget, GadgetConfig, DEFAULT_RESERVED_ROWS},
+  input_lookup::InputLookupChip,
+  range_check::RangeCheckChip,
+};
+
+// The range check gadget on its own. With 2^8 rows, the limbs have 7 bits, so 10 bits is a full
+// limb and a partial top limb of 3 bits, which is only in range after the shift.
+
+const K: u32 = 8;
+const NUM_COLS: usize = 8;
+const NUM_BITS: usize = 10;
+const MAX: i64 = 1 << NUM_BITS;
+
+fn to_field(x: i64) -> Fr {
+  if x < 0 {
+    -Fr::from(x.unsigned_abs())
+  } else {
+    Fr::from(x as u64)
+  }
+}
+
+#[derive(Clone, Default)]
+struct RangeCheckCircuit {
+  inp: Vec<i64>,
+}
+
+impl Circuit<Fr> for RangeCheckCircuit {
+  type Config = GadgetConfig;
+  type FloorPlanner = SimpleFloorPlanner;
+  type Params = ();
+
+  fn without_witnesses(&self) -> Self {
+    Self::default()
+  }
+
+  fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+    let columns = (0..NUM_COLS)
+      .map(|_| meta.advice_column())
+      .collect::<Vec<_>>();
+    for col in columns.iter() {
+      meta.enable_equality(*col);
+    }
+    let fixed_col = meta.fixed_column();
+    meta.enable_equality(fixed_col);
+
+    let gadget_config = GadgetConfig {
+      columns,
+      fixed_columns: vec![fixed_col],
+      num_rows: (1 << K) - DEFAULT_RESERVED_ROWS as usize,
+      use_selectors: true,
+      ..Default::default()
+    };
+    let gadget_config = InputLookupChip::<Fr>::configure(meta, gadget_config);
+    RangeCheckChip::<Fr>::configure(meta, gadget_config)
+  }
+
+  fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+    let columns = config.columns.clone();
+    let config = Rc::new(config);
+    InputLookupChip::<Fr>::construct(config.clone())
+      .load_lookups(layouter.namespace(|| "input lookup"))?;
+
+    let (zero, inp) = layouter.assign_region(
+      || "range check inputs",
+      |mut region| {
+        let zero = region.assign_advice(|| "", columns[0], 0, || Value::known(Fr::from(0)))?;
+        let inp = self
+          .inp
+          .iter()
+          .enumerate()
+          .map(|(i, x)| {
+            region.assign_advice(|| "", columns[0], i + 1, || Value::known(to_field(*x)))
+          })
+          .collect::<Result<Vec<AssignedCell<Fr, Fr>>, _>>()?;
+        Ok((zero, inp))
+      },
+    )?;
+
+    RangeCheckChip::<Fr>::construct(config, NUM_BITS).forward(
+      layouter.namespace(|| "range check"),
+      &vec![inp.iter().collect()],
+      &vec![&zero],
+    )?;
+    Ok(())
+  }
+}
+
+fn verify(inp: Vec<i64>) -> bool {
+  let prover = MockProver::run(K, &RangeCheckCircuit { inp }, vec![]).unwrap();
+  prover.verify().is_ok()
+}
+
+#[test]
+fn range_check_accepts_edges() {
+  assert!(verify(vec![0, 1, MAX - 1]));
+}
+
+// 2^10 only sets the bit above the top limb's 3 bits, which the shift moves out of the table
+#[test]
+fn range_check_rejects_one_more_bit() {
+  assert!(!verify(vec![MAX]));
+  assert!(!verify(vec![2 * MAX - 1]));
+}
+
+#[test]
+fn range_check_rejects_negative() {
+  assert!(!verify(vec![-1]));
+  assert!(!verify(vec![-MAX]));
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(16))]
+
+  #[test]
+  fn range_check_accepts_in_range(inp in prop::collection::vec(0..MAX, 1..8)) {
+    prop_assert!(verify(inp));
+  }
+
+  #[test]
+  fn range_check_rejects_out_of_range(x in MAX..(1 << 28)) {
+    prop_assert!(!verify(vec![x]));
+  }
+}
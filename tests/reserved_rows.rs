@@ -0,0 +1,100 @@
+use std::rc::Rc;
+
+use halo2_proofs::{
+  circuit::{Layouter, SimpleFloorPlanner, Value},
+  dev::MockProver,
+  halo2curves::bn256::Fr,
+  plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+  poly::Rotation,
+};
+use zkml::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType, DEFAULT_RESERVED_ROWS},
+  input_lookup::InputLookupChip,
+};
+
+// The lookup tables fill every row that isn't reserved, so the last table row and the last
+// assignable row are both 2^k - DEFAULT_RESERVED_ROWS - 1. The circuit range checks a value on
+// that row against the input lookup table.
+
+const K: u32 = 8;
+const NUM_ROWS: usize = (1 << K) - DEFAULT_RESERVED_ROWS as usize;
+
+// EXTRA_ROWS grows the table and moves the checked row past the usable rows
+#[derive(Clone, Default)]
+struct CapacityCircuit<const EXTRA_ROWS: usize> {
+  val: u64,
+}
+
+impl<const EXTRA_ROWS: usize> Circuit<Fr> for CapacityCircuit<EXTRA_ROWS> {
+  type Config = (GadgetConfig, Column<Advice>, Selector);
+  type FloorPlanner = SimpleFloorPlanner;
+  type Params = ();
+
+  fn without_witnesses(&self) -> Self {
+    Self::default()
+  }
+
+  fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+    let col = meta.advice_column();
+    let selector = meta.complex_selector();
+    let gadget_config = GadgetConfig {
+      num_rows: NUM_ROWS + EXTRA_ROWS,
+      ..Default::default()
+    };
+    let gadget_config = InputLookupChip::<Fr>::configure(meta, gadget_config);
+
+    let lookup = gadget_config.tables[&GadgetType::InputLookup][0];
+    meta.lookup("capacity range check", |meta| {
+      let s = meta.query_selector(selector);
+      let x = meta.query_advice(col, Rotation::cur());
+      vec![(s * x, lookup)]
+    });
+
+    (gadget_config, col, selector)
+  }
+
+  fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+    let (gadget_config, col, selector) = config;
+    InputLookupChip::<Fr>::construct(Rc::new(gadget_config))
+      .load_lookups(layouter.namespace(|| "input lookup"))?;
+
+    layouter.assign_region(
+      || "last row",
+      |mut region| {
+        let row = NUM_ROWS + EXTRA_ROWS - 1;
+        selector.enable(&mut region, row)?;
+        region.assign_advice(|| "", col, row, || Value::known(Fr::from(self.val)))?;
+        Ok(())
+      },
+    )
+  }
+}
+
+#[test]
+fn last_usable_row_is_checked() {
+  let circuit = CapacityCircuit::<0> {
+    val: NUM_ROWS as u64 - 1,
+  };
+  let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+  assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn value_past_the_table_is_rejected() {
+  let circuit = CapacityCircuit::<0> {
+    val: NUM_ROWS as u64,
+  };
+  let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+  assert!(prover.verify().is_err());
+}
+
+// The extra row lands on the first blinding row, which can't be assigned
+#[test]
+#[should_panic]
+fn row_past_the_reservation_is_rejected() {
+  let circuit = CapacityCircuit::<1> {
+    val: NUM_ROWS as u64,
+  };
+  let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+  prover.verify().unwrap();
+}
@@ -0,0 +1,200 @@
+use std::rc::Rc;
+
+use halo2_proofs::{
+  circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+  dev::MockProver,
+  halo2curves::bn256::Fr,
+  plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use proptest::prelude::*;
+use zkml::gadgets::{
+  gadget::{Gadget, GadgetConfig, GadgetType, DEFAULT_RESERVED_ROWS},
+  input_lookup::InputLookupChip,
+  range_check::RangeCheckChip,
+  sign_bit::SignBitChip,
+};
+
+// The sign bit gadget on its own, for x \in [-2^12, 2^12). The sign bits are exposed on the
+// instance column.
+
+const K: u32 = 8;
+const NUM_COLS: usize = 8;
+const NUM_BITS: usize = 12;
+const MAX: i64 = 1 << NUM_BITS;
+
+fn to_field(x: i64) -> Fr {
+  if x < 0 {
+    -Fr::from(x.unsigned_abs())
+  } else {
+    Fr::from(x as u64)
+  }
+}
+
+// With a tamper, the first op of a row is assigned by hand as [x, b, shift, y], and y is range
+// checked as in the gadget
+#[derive(Clone, Default)]
+struct SignBitCircuit {
+  inp: Vec<i64>,
+  tamper: Option<[i64; 3]>,
+}
+
+impl Circuit<Fr> for SignBitCircuit {
+  type Config = (GadgetConfig, Column<Instance>);
+  type FloorPlanner = SimpleFloorPlanner;
+  type Params = ();
+
+  fn without_witnesses(&self) -> Self {
+    Self::default()
+  }
+
+  fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+    let columns = (0..NUM_COLS)
+      .map(|_| meta.advice_column())
+      .collect::<Vec<_>>();
+    for col in columns.iter() {
+      meta.enable_equality(*col);
+    }
+    let fixed_col = meta.fixed_column();
+    meta.enable_equality(fixed_col);
+    let public_col = meta.instance_column();
+    meta.enable_equality(public_col);
+
+    let gadget_config = GadgetConfig {
+      columns,
+      fixed_columns: vec![fixed_col],
+      num_rows: (1 << K) - DEFAULT_RESERVED_ROWS as usize,
+      use_selectors: true,
+      ..Default::default()
+    };
+    let gadget_config = InputLookupChip::<Fr>::configure(meta, gadget_config);
+    let gadget_config = RangeCheckChip::<Fr>::configure(meta, gadget_config);
+    (SignBitChip::<Fr>::configure(meta, gadget_config), public_col)
+  }
+
+  fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+    let (gadget_config, public_col) = config;
+    let columns = gadget_config.columns.clone();
+    let fixed_col = gadget_config.fixed_columns[0];
+    let gadget_config = Rc::new(gadget_config);
+    InputLookupChip::<Fr>::construct(gadget_config.clone())
+      .load_lookups(layouter.namespace(|| "input lookup"))?;
+
+    let out = match self.tamper {
+      Some([x, b, y]) => {
+        let (zero, b, y) = layouter.assign_region(
+          || "tampered row",
+          |mut region| {
+            let selector = gadget_config.selectors[&GadgetType::SignBit][0];
+            selector.enable(&mut region, 0)?;
+            let shift = Value::known(Fr::from(MAX as u64));
+            let shift = region.assign_fixed(|| "", fixed_col, 0, || shift)?;
+            let mut cells = vec![];
+            for (i, val) in [x, b, 0, y, 0, 0, 0, 0].iter().enumerate() {
+              let cell = if i == 2 {
+                shift.copy_advice(|| "", &mut region, columns[i], 0)?
+              } else {
+                region.assign_advice(|| "", columns[i], 0, || Value::known(to_field(*val)))?
+              };
+              cells.push(cell);
+            }
+            Ok((cells[4].clone(), cells[1].clone(), cells[3].clone()))
+          },
+        )?;
+        RangeCheckChip::<Fr>::construct(gadget_config.clone(), NUM_BITS).forward(
+          layouter.namespace(|| "range check"),
+          &vec![vec![&y]],
+          &vec![&zero],
+        )?;
+        vec![b]
+      }
+      None => {
+        let (zero, inp) = layouter.assign_region(
+          || "sign bit inputs",
+          |mut region| {
+            let zero = region.assign_advice(|| "", columns[0], 0, || Value::known(Fr::from(0)))?;
+            let inp = self
+              .inp
+              .iter()
+              .enumerate()
+              .map(|(i, x)| {
+                region.assign_advice(|| "", columns[0], i + 1, || Value::known(to_field(*x)))
+              })
+              .collect::<Result<Vec<AssignedCell<Fr, Fr>>, _>>()?;
+            Ok((zero, inp))
+          },
+        )?;
+        SignBitChip::<Fr>::construct(gadget_config.clone(), NUM_BITS).forward(
+          layouter.namespace(|| "sign bit"),
+          &vec![inp.iter().collect()],
+          &vec![&zero],
+        )?
+      }
+    };
+    for (i, cell) in out.iter().enumerate() {
+      layouter.constrain_instance(cell.cell(), public_col, i)?;
+    }
+
+    Ok(())
+  }
+}
+
+fn verify(circuit: SignBitCircuit, expected: Vec<i64>) -> bool {
+  let public_vals = expected.iter().map(|x| to_field(*x)).collect::<Vec<_>>();
+  let prover = MockProver::run(K, &circuit, vec![public_vals]).unwrap();
+  prover.verify().is_ok()
+}
+
+fn sign_bits(inp: Vec<i64>) -> bool {
+  let expected = inp.iter().map(|x| (*x < 0) as i64).collect();
+  verify(SignBitCircuit { inp, tamper: None }, expected)
+}
+
+fn tampered(row: [i64; 3]) -> bool {
+  let circuit = SignBitCircuit {
+    inp: vec![],
+    tamper: Some(row),
+  };
+  verify(circuit, vec![row[1]])
+}
+
+#[test]
+fn sign_bit_edges() {
+  assert!(sign_bits(vec![0, -MAX, MAX - 1, -1, 1]));
+}
+
+#[test]
+fn sign_bit_rejects_out_of_range() {
+  assert!(!sign_bits(vec![MAX]));
+  assert!(!sign_bits(vec![-MAX - 1]));
+}
+
+// The hand-assigned rows are accepted when the sign is right
+#[test]
+fn sign_bit_accepts_honest_row() {
+  assert!(tampered([5, 0, 5]));
+  assert!(tampered([-5, 1, MAX - 5]));
+  assert!(tampered([-MAX, 1, 0]));
+}
+
+// A flipped sign keeps y = x + b * 2^12, but puts y out of range
+#[test]
+fn sign_bit_rejects_tampered_sign() {
+  assert!(!tampered([5, 1, MAX + 5]));
+  assert!(!tampered([-5, 0, -5]));
+  assert!(!tampered([0, 1, MAX]));
+}
+
+#[test]
+fn sign_bit_rejects_non_boolean_sign() {
+  assert!(!tampered([-5, 2, 2 * MAX - 5]));
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(16))]
+
+  // The input lengths are not multiples of the ops per row, so the padding is covered
+  #[test]
+  fn sign_bit_matches(inp in prop::collection::vec(-MAX..MAX, 1..8)) {
+    prop_assert!(sign_bits(inp));
+  }
+}
@@ -0,0 +1,30 @@
+use zkml::utils::verifier_vectors::read_vectors;
+
+// Every vector in testing/verifier_vectors must give its expected result with interface::verify.
+// Generate new vectors with gen_verifier_vectors. A missing or empty directory is an error, since
+// the test would otherwise pass without checking anything.
+#[test]
+fn vectors_match_interface_verify() {
+  let vectors_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/testing/verifier_vectors");
+  let entries = std::fs::read_dir(vectors_dir)
+    .unwrap_or_else(|e| panic!("can't read verifier vectors in {}: {}", vectors_dir, e));
+
+  let mut num_vectors = 0;
+  for entry in entries {
+    let path = entry.unwrap().path();
+    if path.extension().map_or(true, |x| x != "json") {
+      continue;
+    }
+    for vector in read_vectors(path.to_str().unwrap()) {
+      num_vectors += 1;
+      assert_eq!(
+        vector.verify(),
+        vector.expected,
+        "{}: {}",
+        path.display(),
+        vector.description
+      );
+    }
+  }
+  assert!(num_vectors > 0, "no verifier vectors in {}", vectors_dir);
+}
@@ -0,0 +1,58 @@
+use halo2_proofs::{
+  halo2curves::bn256::{Bn256, Fr},
+  plonk::keygen_vk,
+  poly::kzg::commitment::ParamsKZG,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use zkml::model::ModelCircuit;
+
+// Any change to the circuit layout changes the verification keys of the bundled models and breaks
+// deployed verifiers. The params come from a fixed seed rather than ./params_kzg so the digests
+// are the same on every machine. Set ZKML_UPDATE_VK_DIGESTS=1 to rewrite them when the change is
+// intended.
+const DIGESTS_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testing/vk_digests.txt");
+
+fn vk_digest(config_fname: &str, inp_fname: &str) -> String {
+  let root = env!("CARGO_MANIFEST_DIR");
+  let circuit = ModelCircuit::<Fr>::generate_from_file(
+    &format!("{}/{}", root, config_fname),
+    &format!("{}/{}", root, inp_fname),
+  );
+  let params = ParamsKZG::<Bn256>::setup(circuit.k as u32, StdRng::seed_from_u64(0));
+  let vk = keygen_vk(&params, &circuit).unwrap();
+  format!("{:?}", vk.transcript_repr())
+}
+
+#[test]
+#[ignore = "generates params and keys for the full models"]
+fn vk_digests_match_golden() {
+  let update = std::env::var("ZKML_UPDATE_VK_DIGESTS").map_or(false, |x| x == "1");
+  let golden = std::fs::read_to_string(DIGESTS_FILE).unwrap();
+
+  let mut lines = vec![];
+  let mut mismatches = vec![];
+  for line in golden.lines() {
+    if line.starts_with('#') || line.trim().is_empty() {
+      lines.push(line.to_string());
+      continue;
+    }
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    let (config_fname, inp_fname) = (fields[0], fields[1]);
+    let digest = vk_digest(config_fname, inp_fname);
+    if fields.get(2) != Some(&digest.as_str()) {
+      mismatches.push(format!(
+        "{}: golden {}, current {}",
+        config_fname,
+        fields.get(2).unwrap_or(&"(none)"),
+        digest
+      ));
+    }
+    lines.push(format!("{} {} {}", config_fname, inp_fname, digest));
+  }
+
+  if update {
+    std::fs::write(DIGESTS_FILE, lines.join("\n") + "\n").unwrap();
+    return;
+  }
+  assert!(mismatches.is_empty(), "vkey digests changed:\n{}", mismatches.join("\n"));
+}